@@ -4,14 +4,19 @@
 #![warn(unused_crate_dependencies, unused_extern_crates)]
 #![warn(variant_size_differences)]
 
-use std::{ffi::OsStr, fs::File, ops::Deref, panic, path::PathBuf};
+use std::{env, ffi::OsStr, fs, fs::File, ops::Deref, panic, path::PathBuf};
 
-use clap::{crate_version, Args, Parser, Subcommand};
+use clap::{crate_version, Args, Parser, Subcommand, ValueEnum};
 use env_logger::{fmt::TimestampPrecision, Builder};
 use humantime::Duration;
 use log::warn;
 use miette::{IntoDiagnostic, Report, Result};
-use shackle::{error::InternalError, Error, Message, Model, Solver, Status};
+use shackle::{
+	error::InternalError,
+	report::{sarif_log, DiagnosticReport, ReportSeverity},
+	Error, FormatOptions, Message, Model, OutputSections, PipelineOptions, Program, ProjectConfig,
+	Solver, Status, Warning, THIR_PASSES,
+};
 
 /// The main function is the entry point for the `shackle` executable.
 ///
@@ -57,6 +62,9 @@ fn main() -> Result<()> {
 		SubCommand::Compile(c) => c.dispatch(),
 		SubCommand::Solve(s) => s.dispatch(),
 		SubCommand::Check(c) => c.dispatch(),
+		SubCommand::Fmt(f) => f.dispatch(),
+		SubCommand::Doc(d) => d.dispatch(),
+		SubCommand::Stats(s) => s.dispatch(),
 	}) {
 		Err(_) => Err(InternalError::new("Panic occurred during execution").into()),
 		Ok(res) => res,
@@ -86,6 +94,13 @@ enum SubCommand {
 	///
 	Solve(Box<Solve>),
 	Check(Box<Check>),
+	/// Format model files, or check whether they are formatted
+	Fmt(Box<Fmt>),
+	/// Generate API documentation for a model library
+	Doc(Box<Doc>),
+	/// Report statistics about the size of a compiled model instance and the
+	/// time taken to compile it
+	Stats(Box<Stats>),
 }
 
 /// Solve the given model instance using the given solver
@@ -95,6 +110,12 @@ struct Solve {
 	statistics: bool,
 	#[arg(long)]
 	time_limit: Option<Duration>,
+	/// Only print the given output sections (separated by commas)
+	#[arg(long, value_delimiter = ',', value_name = "SECTION")]
+	only_sections: Option<Vec<String>>,
+	/// Do not print the given output sections (separated by commas)
+	#[arg(long, value_delimiter = ',', value_name = "SECTION")]
+	not_sections: Vec<String>,
 	#[command(flatten)]
 	base: Compile,
 }
@@ -103,20 +124,32 @@ impl Solve {
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, data) = self.base.sort_files()?;
-		let slv = self.base.solver()?;
+		self.base.reject_pipeline_inspection("solve")?;
+		let project = self.base.project()?;
+		let (model, data) = self.base.sort_files(project.as_ref())?;
+		let slv = self.base.solver(project.as_ref())?;
 
 		// Construct model, typecheck, and compile into program
-		let model = Model::from_file(model);
-		let mut program = model.compile(&slv)?;
+		// (the data is checked when it is added to the program)
+		let model = self.base.model(model, project.as_ref(), &slv);
+		let mut program = match self.base.compile(model, &slv)? {
+			Some(p) => p,
+			None => return Ok(()),
+		};
 
-		program.add_data_files(data.iter().map(|f| f.deref()))?;
+		if let Err(e) = program.add_data_files(data.iter().map(|f| f.deref())) {
+			return self.base.report(vec![e], Vec::new());
+		}
 
 		// Set program options
 		if let Some(time_limit) = self.time_limit {
 			program = program.with_time_limit(time_limit.into());
 		}
 		program = program.with_statistics(self.statistics);
+		program = program.with_output_sections(OutputSections {
+			only: self.only_sections.clone(),
+			not: self.not_sections.clone(),
+		});
 
 		// Run resulting program and show results
 		let display_fn = |x: &Message| {
@@ -151,16 +184,134 @@ impl Check {
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, data) = self.base.sort_files()?;
+		self.base.reject_pipeline_inspection("check")?;
+		let project = self.base.project()?;
+		let (model, data) = self.base.sort_files(project.as_ref())?;
 
-		let slv = self.base.solver()?;
-		let model = Model::from_file(model);
+		let slv = self.base.solver(project.as_ref())?;
+		let model = self.base.model(model, project.as_ref(), &slv);
 		let errors = model.check(&slv, &data, self.check_complete);
+		self.base.report(errors, model.warnings())
+	}
+}
 
-		if errors.is_empty() {
-			Ok(())
+/// Report statistics about a model instance
+#[derive(Args)]
+struct Stats {
+	/// Output the statistics as a JSON object
+	#[arg(long)]
+	json: bool,
+	#[command(flatten)]
+	base: Compile,
+}
+
+impl Stats {
+	/// The dispatch method checks the validity of the user input and then call
+	/// the corresponding functions in the modelling libraries.
+	pub fn dispatch(&self) -> Result<()> {
+		self.base.reject_pipeline_inspection("stats")?;
+		let project = self.base.project()?;
+		let (model, data) = self.base.sort_files(project.as_ref())?;
+
+		let slv = self.base.solver(project.as_ref())?;
+		let model = self.base.model(model, project.as_ref(), &slv);
+		// The model and data are checked when the statistics are collected
+		let warnings = model.warnings();
+		let stats = match model.statistics(&slv, &data) {
+			Ok(stats) => stats,
+			Err(e) => return self.base.report(vec![e], warnings),
+		};
+		self.base.report(Vec::new(), warnings)?;
+		if self.json {
+			println!("{}", stats.to_json());
+		} else {
+			print!("{}", stats);
+		}
+		Ok(())
+	}
+}
+
+/// Format model files
+#[derive(Args)]
+struct Fmt {
+	/// Check whether the files are formatted instead of formatting them
+	#[arg(long)]
+	check: bool,
+	/// The number of columns per level of indentation
+	#[arg(long, default_value_t = FormatOptions::default().indent_width)]
+	indent_width: usize,
+	/// Indent using tabs instead of spaces
+	#[arg(long)]
+	use_tabs: bool,
+	/// The maximum width of a line
+	#[arg(long, default_value_t = FormatOptions::default().line_width)]
+	line_width: usize,
+	#[arg(required = true)]
+	files: Vec<PathBuf>,
+}
+
+impl Fmt {
+	/// The dispatch method formats each of the files in place, or reports the
+	/// files which are not formatted when checking.
+	pub fn dispatch(&self) -> Result<()> {
+		let options = FormatOptions {
+			indent_width: self.indent_width,
+			use_tabs: self.use_tabs,
+			line_width: self.line_width,
+		};
+		let mut unformatted = 0;
+		for file in self.files.iter() {
+			let source = fs::read_to_string(file).into_diagnostic()?;
+			let formatted = Model::from_file(file.clone()).format(&options)?;
+			if formatted == source {
+				continue;
+			}
+			if self.check {
+				println!("`{}' is not formatted", file.display());
+				unformatted += 1;
+			} else {
+				fs::write(file, formatted).into_diagnostic()?;
+			}
+		}
+		if unformatted > 0 {
+			Err(Report::msg(format!(
+				"{} file(s) would be reformatted",
+				unformatted
+			)))
 		} else {
-			Err(Error::try_from(errors).unwrap().into())
+			Ok(())
+		}
+	}
+}
+
+/// Generate API documentation
+#[derive(Args)]
+struct Doc {
+	/// The format of the generated documentation
+	#[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+	format: DocFormat,
+	/// The file to write the documentation to (defaults to standard output)
+	#[arg(short, long)]
+	output: Option<PathBuf>,
+	/// The model file of the library
+	file: PathBuf,
+}
+
+impl Doc {
+	/// The dispatch method generates documentation for the library and writes
+	/// it to the output file.
+	pub fn dispatch(&self) -> Result<()> {
+		let format = match self.format {
+			DocFormat::Markdown => shackle::DocFormat::Markdown,
+			DocFormat::Html => shackle::DocFormat::Html,
+		};
+		let docs = Model::from_file(self.file.clone()).documentation(format)?;
+		match &self.output {
+			Some(path) => fs::write(path, docs).into_diagnostic(),
+			None => {
+				print!("{}", docs);
+				Ok(())
+			}
 		}
 	}
 }
@@ -168,19 +319,62 @@ impl Check {
 /// Compile the given model to a shackle intermediate format
 #[derive(Args)]
 pub struct Compile {
-	#[arg(long, default_value = "gecode")]
-	solver: String,
-	#[arg(required = true)]
+	/// The solver to use (defaults to the solver of the project, or gecode)
+	#[arg(long)]
+	solver: Option<String>,
+	/// The project file to use (if no files are given, `shackle.toml` is
+	/// searched for in the current directory and its ancestors)
+	#[arg(long)]
+	project: Option<PathBuf>,
+	/// The format used to report errors and warnings
+	#[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+	message_format: MessageFormat,
+	/// Print the model after the given THIR passes (separated by commas)
+	#[arg(long, value_delimiter = ',', value_name = "PASS")]
+	dump_after: Vec<String>,
+	/// Stop running THIR passes after the given pass
+	#[arg(long, value_name = "PASS")]
+	stop_after: Option<String>,
+	/// Sanity check the model after every THIR pass
+	#[arg(long)]
+	sanity_check: bool,
+	/// Store compiled programs in the given directory, and reuse them when the
+	/// model has not changed (warnings are only reported when it is compiled)
+	#[arg(long, value_name = "DIR")]
+	cache: Option<PathBuf>,
 	files: Vec<PathBuf>,
 }
 
 impl Compile {
-	/// Sort through the files in the command line arguments and split the model
-	/// from the data
-	pub fn sort_files(&self) -> Result<(PathBuf, Vec<PathBuf>)> {
+	/// Load the project configuration from the project file, if one is given
+	/// or found
+	pub fn project(&self) -> Result<Option<ProjectConfig>> {
+		let path = match &self.project {
+			Some(p) => p.clone(),
+			None if self.files.is_empty() => {
+				match ProjectConfig::find(&env::current_dir().into_diagnostic()?) {
+					Some(p) => p,
+					None => return Ok(None),
+				}
+			}
+			None => return Ok(None),
+		};
+		let config = ProjectConfig::from_file(&path)?;
+		Ok(Some(config))
+	}
+
+	/// Sort through the files in the command line arguments (or the project if
+	/// no files are given) and split the model from the data
+	pub fn sort_files(&self, project: Option<&ProjectConfig>) -> Result<(PathBuf, Vec<PathBuf>)> {
+		let files = match project {
+			Some(p) if self.files.is_empty() => {
+				p.models.iter().chain(p.data.iter()).cloned().collect()
+			}
+			_ => self.files.clone(),
+		};
 		let mut model_file: Option<PathBuf> = None;
-		let mut data = Vec::with_capacity(self.files.len() - 1);
-		for f in self.files.iter() {
+		let mut data = Vec::with_capacity(files.len());
+		for f in files.iter() {
 			match f.extension().and_then(OsStr::to_str) {
 				Some("mzn") | Some("eprime") => {
 					if let Some(other) = model_file {
@@ -209,29 +403,161 @@ impl Compile {
 		}
 	}
 
-	/// Resolve shackle [`Solver`] from the solver command line flag
-	pub fn solver(&self) -> Result<Solver> {
-		match shackle::Solver::lookup(self.solver.as_str()) {
+	/// Resolve shackle [`Solver`] from the solver command line flag, or the
+	/// solver of the project
+	pub fn solver(&self, project: Option<&ProjectConfig>) -> Result<Solver> {
+		let solver = self
+			.solver
+			.as_deref()
+			.or_else(|| project.and_then(|p| p.solver.as_deref()))
+			.unwrap_or("gecode");
+		match shackle::Solver::lookup(solver) {
 			Some(slv) => Ok(slv),
 			None => Err(Report::msg(format!(
 				"no solver has been registered with the tag `{}'",
-				self.solver,
+				solver,
 			))),
 		}
 	}
 
+	/// Create the [`Model`] for the given model file using the settings of the
+	/// project and the globals library of the solver
+	pub fn model(&self, path: PathBuf, project: Option<&ProjectConfig>, slv: &Solver) -> Model {
+		let default = ProjectConfig::default();
+		let model = Model::from_file(path).with_project_config(project.unwrap_or(&default), slv);
+		match &self.cache {
+			Some(dir) => model.with_cache(dir.clone()),
+			None => model,
+		}
+	}
+
+	/// Reject the options for inspecting the THIR pipeline, which are only
+	/// supported by `shackle compile`
+	pub fn reject_pipeline_inspection(&self, command: &str) -> Result<()> {
+		if !self.dump_after.is_empty() || self.stop_after.is_some() || self.sanity_check {
+			return Err(Report::msg(format!(
+				"`--dump-after', `--stop-after' and `--sanity-check' cannot be used with `shackle {}' (use `shackle compile' instead)",
+				command
+			)));
+		}
+		Ok(())
+	}
+
+	/// Check and compile the [`Model`] into a [`Program`] (or load it from the
+	/// cache), printing the model after the passes selected using `--dump-after`
+	pub fn compile(&self, model: Model, slv: &Solver) -> Result<Option<Program>> {
+		let options = PipelineOptions {
+			dump_after: self.dump_after.clone(),
+			stop_after: self.stop_after.clone(),
+			sanity_check: self.sanity_check,
+		};
+		if let Some(name) = options.unknown_passes().next() {
+			return Err(Report::msg(format!(
+				"unknown pass `{}' (expected one of: {})",
+				name,
+				THIR_PASSES
+					.iter()
+					.map(|p| p.name)
+					.collect::<Vec<_>>()
+					.join(", ")
+			)));
+		}
+		// Cached programs were checked when they were compiled
+		let model = if options.is_default() {
+			match model.load_cached(slv) {
+				Ok(p) => return Ok(Some(p)),
+				Err(model) => *model,
+			}
+		} else {
+			model
+		};
+		self.report(model.check(slv, &[], false), model.warnings())?;
+		let dump = |name: &str, code: &str| println!("% After pass `{}'\n{}", name, code);
+		match model.compile_with_pipeline(slv, &options, dump) {
+			Ok(p) => Ok(Some(p)),
+			Err(e) => self.report(vec![e], Vec::new()).map(|_| None),
+		}
+	}
+
+	/// Report the errors and warnings of a model using the selected message
+	/// format.
+	///
+	/// Returns an error if there are any errors to report.
+	pub fn report(&self, errors: Vec<Error>, warnings: Vec<Warning>) -> Result<()> {
+		if self.message_format == MessageFormat::Human {
+			for warning in warnings {
+				eprintln!("{:?}", Report::new(warning));
+			}
+			return if errors.is_empty() {
+				Ok(())
+			} else {
+				Err(Error::try_from(errors).unwrap().into())
+			};
+		}
+		let reports = errors
+			.iter()
+			.flat_map(DiagnosticReport::from_error)
+			.chain(warnings.iter().map(DiagnosticReport::from_warning))
+			.collect::<Vec<_>>();
+		match self.message_format {
+			MessageFormat::Json => {
+				for r in reports.iter() {
+					println!("{}", r.to_json());
+				}
+			}
+			MessageFormat::Sarif => println!("{}", sarif_log(&reports)),
+			MessageFormat::Human => unreachable!(),
+		}
+		let error_count = reports
+			.iter()
+			.filter(|r| r.severity == ReportSeverity::Error)
+			.count();
+		if error_count > 0 {
+			Err(Report::msg(format!(
+				"aborting due to {} previous error(s)",
+				error_count
+			)))
+		} else {
+			Ok(())
+		}
+	}
+
 	/// The dispatch method checks the validity of the user input and then call
 	/// the corresponding functions in the modelling libraries.
 	pub fn dispatch(&self) -> Result<()> {
-		let (model, _data) = self.sort_files()?;
+		let project = self.project()?;
+		let (model, _data) = self.sort_files(project.as_ref())?;
 
 		let filename = model.with_extension("shackle.mzn");
 
-		let slv = self.solver()?;
-		let model = Model::from_file(model);
-		let prg = model.compile(&slv)?;
+		let slv = self.solver(project.as_ref())?;
+		let model = self.model(model, project.as_ref(), &slv);
+		let prg = match self.compile(model, &slv)? {
+			Some(p) => p,
+			None => return Ok(()),
+		};
 
 		let mut file = File::create(filename).into_diagnostic()?;
 		prg.write(&mut file).into_diagnostic()
 	}
 }
+
+/// The format of the generated documentation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DocFormat {
+	/// Markdown
+	Markdown,
+	/// A standalone HTML page
+	Html,
+}
+
+/// The format used to report errors and warnings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+	/// Human readable reports
+	Human,
+	/// A JSON object on a separate line for every error and warning
+	Json,
+	/// A SARIF log containing all errors and warnings
+	Sarif,
+}
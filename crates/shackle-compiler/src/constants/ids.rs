@@ -4,6 +4,16 @@ macro_rules! id_registry {
 	($struct:ident, $($tail:tt)*) => {
 		id_registry!(@def $struct ($($tail)*) ());
 		id_registry!(@imp $struct db ($($tail)*) ());
+		id_registry!(@all $struct ($($tail)*));
+	};
+
+	(@all $struct:ident ($($name:ident $(:$value:expr)?),* $(,)?)) => {
+		impl $struct {
+			/// Get all identifiers in the registry
+			pub fn all(&self) -> Vec<Identifier> {
+				vec![$(self.$name),*]
+			}
+		}
 	};
 
 	(@def $struct:ident ($($name:ident $(:$value:expr)?)?) ($($rest:tt)*)) => {
@@ -97,6 +107,11 @@ id_registry!(
 	times: "*",
 	sum,
 	product,
+	abs,
+	min,
+	max,
+	bool2int,
+	int2float,
 	erase_enum,
 	forall,
 	exists,
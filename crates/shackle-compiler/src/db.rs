@@ -10,6 +10,8 @@ use std::{
 	sync::Arc,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
 	constants::TypeRegistry,
 	diagnostics::FileError,
@@ -155,7 +157,7 @@ impl salsa::InternKey for InternedString {
 }
 
 /// String data
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InternedStringData(pub String);
 
 impl<T> From<T> for InternedStringData
@@ -262,6 +264,16 @@ impl HasFileHandler for CompilerDatabase {
 		);
 		let f = FileRef::new(file, self);
 		FileContentsQuery.in_db_mut(self).invalidate(&f);
+		// Input files are referenced by index rather than by path
+		let inputs = self
+			.input_file_refs()
+			.iter()
+			.copied()
+			.filter(|f| f.path(self).as_deref() == Some(file))
+			.collect::<Vec<_>>();
+		for f in inputs {
+			FileContentsQuery.in_db_mut(self).invalidate(&f);
+		}
 	}
 }
 
@@ -183,6 +183,71 @@ pub struct CyclicDefinition {
 	pub span: SourceSpan,
 }
 
+/// A suggested replacement of source code which fixes an error
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct FixIt {
+	/// Description of the fix
+	pub message: String,
+	/// The span to replace
+	pub span: SourceSpan,
+	/// The replacement text
+	pub replacement: String,
+}
+
+/// Help message for an error, along with machine-applicable fixes
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Suggestions {
+	/// The help message
+	pub help: String,
+	/// The suggested fixes
+	pub fix_its: Vec<FixIt>,
+}
+
+impl Suggestions {
+	/// Create a help message without any fixes
+	pub fn help(help: impl Into<String>) -> Box<Self> {
+		Box::new(Self {
+			help: help.into(),
+			fix_its: Vec::new(),
+		})
+	}
+
+	/// Suggest replacing the given span with each of the given (similarly named) identifiers.
+	///
+	/// Returns `None` if there are no identifiers to suggest.
+	pub fn did_you_mean(
+		span: SourceSpan,
+		identifiers: impl IntoIterator<Item = String>,
+	) -> Option<Box<Self>> {
+		let fix_its = identifiers
+			.into_iter()
+			.map(|i| FixIt {
+				message: format!("Replace with '{}'", i),
+				span,
+				replacement: i,
+			})
+			.collect::<Vec<_>>();
+		let help = match fix_its.as_slice() {
+			[] => return None,
+			[f] => format!("Did you mean '{}'?", f.replacement),
+			fs => format!(
+				"Did you mean one of {}?",
+				fs.iter()
+					.map(|f| format!("'{}'", f.replacement))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+		};
+		Some(Box::new(Self { help, fix_its }))
+	}
+}
+
+impl Display for Suggestions {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+		write!(f, "{}", self.help)
+	}
+}
+
 /// An undefined identifier error
 #[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
 #[error("Undefined identifier")]
@@ -196,6 +261,9 @@ pub struct UndefinedIdentifier {
 	pub span: SourceSpan,
 	/// The identifier which is undefined
 	pub identifier: String,
+	/// Suggestions for similarly named identifiers
+	#[help]
+	pub suggestions: Option<Box<Suggestions>>,
 }
 
 /// An identifier already defined error
@@ -304,6 +372,9 @@ pub struct NoMatchingFunction {
 	/// The span associated with the error
 	#[label("{msg}")]
 	pub span: SourceSpan,
+	/// Suggestions for similarly named functions or the closest overload
+	#[help]
+	pub suggestions: Option<Box<Suggestions>>,
 }
 
 /// Ambiguous call
@@ -319,6 +390,9 @@ pub struct AmbiguousCall {
 	/// The span associated with the error
 	#[label("{msg}")]
 	pub span: SourceSpan,
+	/// The candidate overloads
+	#[help]
+	pub suggestions: Option<Box<Suggestions>>,
 }
 
 /// Illegal overloading
@@ -458,6 +532,9 @@ pub struct NonExhaustivePatternMatching {
 	/// The span associated with the error
 	#[label("{msg}")]
 	pub span: SourceSpan,
+	/// Suggestion to add the missing case arms
+	#[help]
+	pub suggestions: Option<Box<Suggestions>>,
 }
 
 /// Invalid numeric literal
@@ -490,6 +567,79 @@ pub struct TypeSpecialisationRecursionLimit {
 	pub span: SourceSpan,
 }
 
+/// An assertion which failed during compile-time evaluation
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Assertion failed")]
+#[diagnostic(code(shackle::assertion_failure))]
+pub struct AssertionFailure {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The assertion message
+	pub msg: String,
+	/// The span associated with the error
+	#[label("{msg}")]
+	pub span: SourceSpan,
+}
+
+/// A THIR pass which is not part of the pipeline
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Unknown THIR pass `{name}`")]
+#[diagnostic(
+	code(shackle::unknown_pass),
+	help("The available passes are: {available}")
+)]
+pub struct UnknownPass {
+	/// The name of the pass
+	pub name: String,
+	/// The names of the passes in the pipeline
+	pub available: String,
+}
+
+/// A parameter or enumerated type which was not given a value
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("No value given for {kind} '{name}'")]
+#[diagnostic(
+	code(shackle::missing_value),
+	help("Assign a value to '{name}' in a data file")
+)]
+pub struct MissingValue {
+	/// The kind of item (parameter or enumerated type)
+	pub kind: String,
+	/// The name of the item
+	pub name: String,
+}
+
+/// Constructor argument of an enumerated type whose members cannot be determined
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Unsupported constructor argument")]
+#[diagnostic(
+	code(shackle::unsupported_constructor_argument),
+	help("Use an enumerated type, or a range whose bounds are integer literals or parameters")
+)]
+pub struct UnsupportedConstructorArgument {
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The name of the enumerated type
+	pub name: String,
+	/// The span associated with the error
+	#[label("The members of '{name}' cannot be determined from this argument")]
+	pub span: SourceSpan,
+}
+
+/// A THIR pass with the same name as another pass in the pipeline
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("THIR pass `{name}` is already in the pipeline")]
+#[diagnostic(
+	code(shackle::duplicate_pass),
+	help("Give the pass a name which is not used by any other pass")
+)]
+pub struct DuplicatePass {
+	/// The name of the pass
+	pub name: String,
+}
+
 /// Main Shackle error type
 #[derive(Error, Diagnostic, Debug, PartialEq, Eq, Clone)]
 pub enum Error {
@@ -593,6 +743,26 @@ pub enum Error {
 	#[error(transparent)]
 	#[diagnostic(transparent)]
 	TypeSpecialisationRecursionLimit(#[from] TypeSpecialisationRecursionLimit),
+	/// Assertion failed during compile-time evaluation
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	AssertionFailure(#[from] AssertionFailure),
+	/// Unknown THIR pass
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	UnknownPass(#[from] UnknownPass),
+	/// THIR pass with a name which is already used
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	DuplicatePass(#[from] DuplicatePass),
+	/// Missing value for a parameter or enumerated type
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	MissingValue(#[from] MissingValue),
+	/// Unsupported enum constructor argument
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	UnsupportedConstructorArgument(#[from] UnsupportedConstructorArgument),
 	/// An internal error
 	#[error("Internal Error - Please report this issue to the Shackle developers")]
 	InternalError(#[from] InternalError),
@@ -632,6 +802,21 @@ impl TryFrom<Diagnostics<Error>> for Error {
 }
 
 impl Error {
+	/// Get the suggested fixes for this error
+	pub fn fix_its(&self) -> &[FixIt] {
+		let suggestions = match self {
+			Error::UndefinedIdentifier(e) => &e.suggestions,
+			Error::NoMatchingFunction(e) => &e.suggestions,
+			Error::AmbiguousCall(e) => &e.suggestions,
+			Error::NonExhaustivePatternMatching(e) => &e.suggestions,
+			_ => &None,
+		};
+		suggestions
+			.as_ref()
+			.map(|s| s.fix_its.as_slice())
+			.unwrap_or_default()
+	}
+
 	/// Parse an error from JSON
 	pub fn from_serde_json(err: serde_json::Error, src: &SourceFile) -> Self {
 		use serde_json::error::Category;
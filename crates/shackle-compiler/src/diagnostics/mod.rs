@@ -1,6 +1,7 @@
 //! Handling of errors and warnings during compilation
 
 pub mod error;
+pub mod report;
 pub mod warning;
 
 use std::sync::Arc;
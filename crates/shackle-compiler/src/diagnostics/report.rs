@@ -0,0 +1,379 @@
+//! Machine-readable diagnostic reports
+//!
+//! Converts [`Diagnostic`]s into JSON objects (one per error/warning) or into a
+//! SARIF log, so that tools other than a terminal can consume them.
+
+use miette::{Diagnostic, Severity, SourceCode, SourceSpan};
+use serde_json::{json, Value};
+
+use super::{Error, FixIt, Warning};
+
+/// Line and column of a position in a source file (both starting at 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LineColumn {
+	/// The line number
+	pub line: usize,
+	/// The column number (in bytes)
+	pub column: usize,
+	/// The column number (in UTF-16 code units, as used by SARIF)
+	pub utf16_column: usize,
+}
+
+/// A labelled span of source code
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReportLabel {
+	/// The label message
+	pub message: Option<String>,
+	/// Start byte offset
+	pub byte_start: usize,
+	/// End byte offset (exclusive)
+	pub byte_end: usize,
+	/// Start position
+	pub start: LineColumn,
+	/// End position
+	pub end: LineColumn,
+}
+
+/// A diagnostic flattened into plain data
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DiagnosticReport {
+	/// The diagnostic code (e.g. `shackle::type_mismatch`)
+	pub code: Option<String>,
+	/// The severity of the diagnostic
+	pub severity: ReportSeverity,
+	/// The main message
+	pub message: String,
+	/// Help text if any
+	pub help: Option<String>,
+	/// The file the diagnostic refers to
+	pub file: Option<String>,
+	/// The labelled spans, where the first is the primary span
+	pub labels: Vec<ReportLabel>,
+	/// Related diagnostics
+	pub related: Vec<DiagnosticReport>,
+	/// Machine-applicable fixes
+	pub fix_its: Vec<FixIt>,
+}
+
+/// Severity of a diagnostic report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReportSeverity {
+	/// An error
+	Error,
+	/// A warning
+	Warning,
+	/// Advice
+	Advice,
+}
+
+impl ReportSeverity {
+	/// Get the name of this severity
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ReportSeverity::Error => "error",
+			ReportSeverity::Warning => "warning",
+			ReportSeverity::Advice => "advice",
+		}
+	}
+
+	/// Get the SARIF `level` for this severity
+	pub fn sarif_level(&self) -> &'static str {
+		match self {
+			ReportSeverity::Error => "error",
+			ReportSeverity::Warning => "warning",
+			ReportSeverity::Advice => "note",
+		}
+	}
+}
+
+impl DiagnosticReport {
+	/// Create a report from the given diagnostic.
+	///
+	/// The severity defaults to `default_severity` if the diagnostic does not specify one.
+	pub fn new(diagnostic: &dyn Diagnostic, default_severity: ReportSeverity) -> Self {
+		let severity = match diagnostic.severity() {
+			Some(Severity::Error) => ReportSeverity::Error,
+			Some(Severity::Warning) => ReportSeverity::Warning,
+			Some(Severity::Advice) => ReportSeverity::Advice,
+			None => default_severity,
+		};
+		let mut file = None;
+		let mut labels = Vec::new();
+		if let (Some(src), Some(ls)) = (diagnostic.source_code(), diagnostic.labels()) {
+			for l in ls {
+				if let Some((name, label)) = read_label(src, l.inner(), l.label()) {
+					if file.is_none() {
+						file = name;
+					}
+					labels.push(label);
+				}
+			}
+		}
+		let related = diagnostic
+			.related()
+			.map(|rs| rs.map(|r| DiagnosticReport::new(r, severity)).collect())
+			.unwrap_or_default();
+		Self {
+			code: diagnostic.code().map(|c| c.to_string()),
+			severity,
+			message: diagnostic.to_string(),
+			help: diagnostic.help().map(|h| h.to_string()),
+			file,
+			labels,
+			related,
+			fix_its: Vec::new(),
+		}
+	}
+
+	/// Create reports for an error, flattening multiple errors into separate reports
+	pub fn from_error(error: &Error) -> Vec<Self> {
+		match error {
+			Error::MultipleErrors(e) => e.errors.iter().flat_map(Self::from_error).collect(),
+			_ => {
+				let mut report = Self::new(error, ReportSeverity::Error);
+				report.fix_its = error.fix_its().to_vec();
+				vec![report]
+			}
+		}
+	}
+
+	/// Create a report for a warning
+	pub fn from_warning(warning: &Warning) -> Self {
+		let mut report = Self::new(warning, ReportSeverity::Warning);
+		report.fix_its = warning.fix_its().to_vec();
+		report
+	}
+
+	/// Get the primary label of this report
+	pub fn primary_label(&self) -> Option<&ReportLabel> {
+		self.labels.first()
+	}
+
+	/// Convert this report into a JSON object
+	pub fn to_json(&self) -> Value {
+		json!({
+			"code": self.code,
+			"severity": self.severity.as_str(),
+			"message": self.message,
+			"help": self.help,
+			"file": self.file,
+			"labels": self.labels.iter().map(|l| l.to_json()).collect::<Vec<_>>(),
+			"related": self.related.iter().map(|r| r.to_json()).collect::<Vec<_>>(),
+			"fix_its": self.fix_its.iter().map(|f| json!({
+				"message": f.message,
+				"byte_start": f.span.offset(),
+				"byte_end": f.span.offset() + f.span.len(),
+				"replacement": f.replacement,
+			})).collect::<Vec<_>>(),
+		})
+	}
+
+	/// Convert this report into a SARIF `result` object
+	fn to_sarif_result(&self) -> Value {
+		let locations = |labels: &[ReportLabel]| {
+			labels
+				.iter()
+				.map(|l| {
+					json!({
+						"physicalLocation": {
+							"artifactLocation": { "uri": self.file },
+							"region": l.to_sarif_region(),
+						},
+						"message": { "text": l.message.as_deref().unwrap_or(&self.message) },
+					})
+				})
+				.collect::<Vec<_>>()
+		};
+		let message = match self.primary_label().and_then(|l| l.message.as_ref()) {
+			Some(label) => format!("{}: {}", self.message, label),
+			None => self.message.clone(),
+		};
+		let (primary, related) = if self.file.is_some() && !self.labels.is_empty() {
+			(locations(&self.labels[..1]), locations(&self.labels[1..]))
+		} else {
+			(Vec::new(), Vec::new())
+		};
+		let fixes = self
+			.fix_its
+			.iter()
+			.map(|f| {
+				json!({
+					"description": { "text": f.message },
+					"artifactChanges": [{
+						"artifactLocation": { "uri": self.file },
+						"replacements": [{
+							"deletedRegion": {
+								"byteOffset": f.span.offset(),
+								"byteLength": f.span.len(),
+							},
+							"insertedContent": { "text": f.replacement },
+						}],
+					}],
+				})
+			})
+			.collect::<Vec<_>>();
+		json!({
+			"ruleId": self.code,
+			"level": self.severity.sarif_level(),
+			"message": { "text": message },
+			"locations": primary,
+			"relatedLocations": related,
+			"fixes": fixes,
+		})
+	}
+}
+
+impl ReportLabel {
+	/// Convert this label into a JSON object
+	pub fn to_json(&self) -> Value {
+		json!({
+			"message": self.message,
+			"byte_start": self.byte_start,
+			"byte_end": self.byte_end,
+			"line_start": self.start.line,
+			"column_start": self.start.column,
+			"line_end": self.end.line,
+			"column_end": self.end.column,
+		})
+	}
+
+	fn to_sarif_region(&self) -> Value {
+		json!({
+			"byteOffset": self.byte_start,
+			"byteLength": self.byte_end - self.byte_start,
+			"startLine": self.start.line,
+			"startColumn": self.start.utf16_column,
+			"endLine": self.end.line,
+			"endColumn": self.end.utf16_column,
+		})
+	}
+}
+
+/// Read the location of a label from the source code
+fn read_label(
+	src: &dyn SourceCode,
+	span: &SourceSpan,
+	message: Option<&str>,
+) -> Option<(Option<String>, ReportLabel)> {
+	// Read the preceding line as context, so that the start of the span's line is included
+	let contents = src.read_span(span, 1, 0).ok()?;
+	let offset = span.offset() - contents.span().offset();
+	let prefix = &contents.data()[..offset];
+	let line_start = prefix
+		.iter()
+		.rposition(|c| matches!(c, b'\r' | b'\n'))
+		.map_or(0, |i| i + 1);
+	let start = LineColumn {
+		// The context line is only present if the span is not on the first line
+		line: contents.line() + usize::from(line_start > 0) + 1,
+		column: offset - line_start + 1,
+		utf16_column: utf16_len(&prefix[line_start..]) + 1,
+	};
+	let data = &contents.data()[offset..];
+	let data = &data[..span.len().min(data.len())];
+	let mut end = start;
+	let mut iter = data.iter().copied().enumerate().peekable();
+	let mut segment_start = 0;
+	while let Some((_, c)) = iter.next() {
+		if matches!(c, b'\r' | b'\n') {
+			if c == b'\r' {
+				let _ = iter.next_if(|(_, c)| *c == b'\n');
+			}
+			end.line += 1;
+			end.column = 1;
+			end.utf16_column = 1;
+			segment_start = iter.peek().map_or(data.len(), |(i, _)| *i);
+		} else {
+			end.column += 1;
+		}
+	}
+	end.utf16_column += utf16_len(&data[segment_start..]);
+	Some((
+		contents.name().map(|n| n.to_owned()),
+		ReportLabel {
+			message: message.map(|m| m.to_owned()),
+			byte_start: span.offset(),
+			byte_end: span.offset() + span.len(),
+			start,
+			end,
+		},
+	))
+}
+
+/// Get the number of UTF-16 code units needed to encode the given UTF-8 bytes
+fn utf16_len(data: &[u8]) -> usize {
+	String::from_utf8_lossy(data).encode_utf16().count()
+}
+
+/// Create a SARIF log containing the given reports
+pub fn sarif_log<'a>(reports: impl IntoIterator<Item = &'a DiagnosticReport>) -> Value {
+	json!({
+		"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": "shackle",
+					"version": env!("CARGO_PKG_VERSION"),
+				}
+			},
+			"columnKind": "utf16CodeUnits",
+			"results": reports.into_iter().map(|r| r.to_sarif_result()).collect::<Vec<_>>(),
+		}]
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use std::{path::PathBuf, sync::Arc};
+
+	use expect_test::expect;
+
+	use super::{sarif_log, DiagnosticReport};
+	use crate::{diagnostics::TypeMismatch, file::SourceFile, Error};
+
+	fn type_mismatch() -> Error {
+		let src = SourceFile::from(Arc::new("int: x = 1;\nbool: y =\n  x;\n".to_owned()));
+		TypeMismatch {
+			src,
+			msg: "Expected bool but got int".to_owned(),
+			span: (20, 4).into(),
+		}
+		.into()
+	}
+
+	#[test]
+	fn test_json_report() {
+		let reports = DiagnosticReport::from_error(&type_mismatch());
+		assert_eq!(reports.len(), 1);
+		expect![[r#"{"code":"shackle::type_mismatch","file":null,"fix_its":[],"help":null,"labels":[{"byte_end":24,"byte_start":20,"column_end":3,"column_start":9,"line_end":3,"line_start":2,"message":"Expected bool but got int"}],"message":"Type mismatch","related":[],"severity":"error"}"#]]
+			.assert_eq(&reports[0].to_json().to_string());
+	}
+
+	#[test]
+	fn test_sarif_report() {
+		let reports = DiagnosticReport::from_error(&type_mismatch());
+		let log = sarif_log(&reports);
+		expect![[r#"[{"fixes":[],"level":"error","locations":[],"message":{"text":"Type mismatch: Expected bool but got int"},"relatedLocations":[],"ruleId":"shackle::type_mismatch"}]"#]]
+			.assert_eq(&log["runs"][0]["results"].to_string());
+	}
+
+	#[test]
+	fn test_sarif_named_file() {
+		// The span covers `“x”` on the second line, which is 3 UTF-16 code units wide
+		let src = SourceFile::with_path(
+			PathBuf::from("/models/model.mzn"),
+			Arc::new("int: x = 1;\nbool: é = “x”;\n".to_owned()),
+		);
+		let error: Error = TypeMismatch {
+			src,
+			msg: "Expected bool but got string".to_owned(),
+			span: (23, 7).into(),
+		}
+		.into();
+		let reports = DiagnosticReport::from_error(&error);
+		let log = sarif_log(&reports);
+		expect![[r#"[{"message":{"text":"Expected bool but got string"},"physicalLocation":{"artifactLocation":{"uri":"/models/model.mzn"},"region":{"byteLength":7,"byteOffset":23,"endColumn":14,"endLine":2,"startColumn":11,"startLine":2}}}]"#]].assert_eq(&log["runs"][0]["results"][0]["locations"].to_string());
+		expect![[r#""utf16CodeUnits""#]].assert_eq(&log["runs"][0]["columnKind"].to_string());
+	}
+}
@@ -3,6 +3,7 @@
 use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
+use super::{FixIt, Suggestions};
 use crate::file::SourceFile;
 
 /// Identifier shadows another
@@ -34,6 +35,28 @@ pub struct UnreachablePattern {
 	/// The span associated with the error
 	#[label("Pattern is unreachable")]
 	pub span: SourceSpan,
+	/// Suggestion to remove the case arm
+	#[help]
+	pub suggestions: Option<Box<Suggestions>>,
+}
+
+/// Integer variable without bounds
+#[derive(Error, Debug, Diagnostic, PartialEq, Eq, Clone)]
+#[error("Unbounded integer variable")]
+#[diagnostic(
+	code(shackle::unbounded_variable),
+	severity(Warning),
+	help("Many solvers perform poorly on unbounded integers, consider giving {name} a domain")
+)]
+pub struct UnboundedVariable {
+	/// The name of the variable
+	pub name: String,
+	/// The source code
+	#[source_code]
+	pub src: SourceFile,
+	/// The span of the variable declaration
+	#[label("Bounds for {name} could not be determined")]
+	pub span: SourceSpan,
 }
 
 /// Shackle warning type
@@ -47,4 +70,22 @@ pub enum Warning {
 	#[error(transparent)]
 	#[diagnostic(transparent)]
 	UnreachablePattern(#[from] UnreachablePattern),
+	/// Integer variable without bounds
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	UnboundedVariable(#[from] UnboundedVariable),
+}
+
+impl Warning {
+	/// Get the suggested fixes for this warning
+	pub fn fix_its(&self) -> &[FixIt] {
+		let suggestions = match self {
+			Warning::UnreachablePattern(w) => &w.suggestions,
+			_ => &None,
+		};
+		suggestions
+			.as_ref()
+			.map(|s| s.fix_its.as_slice())
+			.unwrap_or_default()
+	}
 }
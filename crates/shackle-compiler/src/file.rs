@@ -11,6 +11,7 @@ use std::{
 };
 
 use miette::{MietteSpanContents, SourceCode};
+use serde::{Deserialize, Serialize};
 
 use crate::{db::FileReader, diagnostics::FileError};
 
@@ -61,6 +62,14 @@ impl SourceFile {
 		})
 	}
 
+	/// Create a new source file from the contents of the file at the given path
+	pub fn with_path(path: PathBuf, source: Arc<String>) -> Self {
+		Self(SourceFileInner::Text {
+			name: Some(path),
+			source,
+		})
+	}
+
 	/// Create a new introduced source file
 	pub fn introduced(name: &'static str) -> Self {
 		Self(SourceFileInner::Introduced(name))
@@ -198,7 +207,7 @@ impl FileRef {
 }
 
 /// Reference to an input file or external file
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileRefData {
 	/// From input
 	InputFile(usize),
@@ -244,7 +253,7 @@ pub fn file_contents(db: &dyn FileReader, file: FileRef) -> Result<Arc<String>,
 }
 
 /// A reference to model file
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModelRef(FileRef);
 
 impl From<FileRef> for ModelRef {
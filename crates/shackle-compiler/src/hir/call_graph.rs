@@ -0,0 +1,144 @@
+//! Call graph of function items.
+//!
+//! Calls are resolved to the function item of the overload chosen by the typer,
+//! so the graph records which definitions are actually used by each item.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use super::{
+	db::Hir,
+	ids::{ExpressionRef, ItemRef, LocalItemRef},
+	Expression,
+};
+
+/// A call to a function item
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct FunctionCall {
+	/// The function identifier expression of the call
+	pub expression: ExpressionRef,
+	/// The function item which is called
+	pub callee: ItemRef,
+}
+
+impl FunctionCall {
+	/// The item containing the call
+	pub fn caller(&self) -> ItemRef {
+		self.expression.item()
+	}
+}
+
+/// Get the calls to function items made by the given item
+pub fn lookup_item_calls(db: &dyn Hir, item: ItemRef) -> Arc<Vec<FunctionCall>> {
+	let model = item.model(db);
+	let data = item.local_item_ref(db).data(&model);
+	let types = db.lookup_item_types(item);
+	let mut calls = Vec::new();
+	for (_, expression) in data.expressions.iter() {
+		let Expression::Call(c) = expression else {
+			continue;
+		};
+		let Some(function) = types.name_resolution(c.function) else {
+			continue;
+		};
+		if let LocalItemRef::Function(_) = function.item().local_item_ref(db) {
+			calls.push(FunctionCall {
+				expression: ExpressionRef::new(item, c.function),
+				callee: function.item(),
+			});
+		}
+	}
+	Arc::new(calls)
+}
+
+/// The calls made between all items in the program
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CallGraph {
+	incoming: FxHashMap<ItemRef, Vec<FunctionCall>>,
+}
+
+impl CallGraph {
+	/// Get the calls made to the given function item
+	pub fn incoming_calls(&self, item: ItemRef) -> &[FunctionCall] {
+		self.incoming
+			.get(&item)
+			.map(|calls| calls.as_slice())
+			.unwrap_or_default()
+	}
+}
+
+/// Build the call graph for all items in the program
+pub fn call_graph(db: &dyn Hir) -> Arc<CallGraph> {
+	let mut graph = CallGraph::default();
+	let Ok(models) = db.resolve_includes() else {
+		return Arc::new(graph);
+	};
+	for model in models.iter() {
+		for item in db.lookup_items(*model).iter() {
+			for call in db.lookup_item_calls(*item).iter() {
+				graph.incoming.entry(call.callee).or_default().push(*call);
+			}
+		}
+	}
+	Arc::new(graph)
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use crate::{
+		db::{CompilerDatabase, FileReader, Inputs},
+		file::InputFile,
+		hir::{db::Hir, ids::LocalItemRef},
+	};
+
+	#[test]
+	fn test_call_graph() {
+		let mut db = CompilerDatabase::default();
+		db.set_ignore_stdlib(true);
+		db.set_input_files(Arc::new(vec![InputFile::ModelString(
+			r#"
+function int: foo(int: x) = x;
+function float: foo(float: x) = x;
+function int: bar(int: x) = foo(x);
+int: y = bar(foo(1));
+float: z = foo(1.0);
+"#
+			.to_owned(),
+		)]));
+		let model = db.input_models()[0];
+		let items = db.lookup_items(model);
+		let name = |item| format!("item {}", items.iter().position(|i| *i == item).unwrap());
+		let graph = db.call_graph();
+		let mut out = Vec::new();
+		for item in items.iter() {
+			if let LocalItemRef::Function(_) = item.local_item_ref(&db) {
+				let callers = graph
+					.incoming_calls(*item)
+					.iter()
+					.map(|c| name(c.caller()))
+					.collect::<Vec<_>>();
+				let callees = db
+					.lookup_item_calls(*item)
+					.iter()
+					.map(|c| name(c.callee))
+					.collect::<Vec<_>>();
+				out.push(format!(
+					"{}: called by {:?}, calls {:?}",
+					name(*item),
+					callers,
+					callees
+				));
+			}
+		}
+		expect![[r#"
+    item 0: called by ["item 2", "item 3"], calls []
+    item 1: called by ["item 4"], calls []
+    item 2: called by ["item 3"], calls ["item 0"]"#]]
+		.assert_eq(&out.join("\n"));
+	}
+}
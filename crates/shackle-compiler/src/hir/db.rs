@@ -7,6 +7,7 @@ use std::{collections::HashSet, path::Path, sync::Arc};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use super::{
+	call_graph::{CallGraph, FunctionCall},
 	ids::{EntityRef, EntityRefData, ItemRef, ItemRefData, PatternRef},
 	scope::{ScopeData, ScopeResult},
 	source::SourceMap,
@@ -193,6 +194,14 @@ pub trait Hir:
 	#[salsa::invoke(super::pattern_matching::lookup_enum_constructors)]
 	fn lookup_enum_constructors(&self, e: EnumRef) -> Option<Arc<Vec<PatternRef>>>;
 
+	/// Get the calls to function items made by the given item
+	#[salsa::invoke(super::call_graph::lookup_item_calls)]
+	fn lookup_item_calls(&self, item: ItemRef) -> Arc<Vec<FunctionCall>>;
+
+	/// Get the call graph for all items in the program
+	#[salsa::invoke(super::call_graph::call_graph)]
+	fn call_graph(&self) -> Arc<CallGraph>;
+
 	/// Get the items in the given model which contain a case expression
 	///
 	/// Allows us to only perform case exhaustiveness checking if there are actually
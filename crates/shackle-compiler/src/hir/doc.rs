@@ -0,0 +1,558 @@
+//! Documentation comments
+//!
+//! An item is documented by a block comment starting with `/**` placed directly before it.
+//! The comment may contain the tags
+//! - `@group <id>` to place the item in a documentation group
+//! - `@param <name>: <description>` to document a parameter
+//!
+//! and may refer to parameters using `\a name` and to other predicates using `\p name`.
+//!
+//! A file-level comment starting with `/***` defines documentation groups using
+//! `@groupdef <id> <title>` followed by a description of the group.
+
+use std::fmt::Write;
+
+use super::{
+	db::Hir,
+	ids::{ItemRef, LocalItemRef, PatternRef},
+	Constructor, Function, Item, Model, Pattern, PatternTy, TypeResult,
+};
+use crate::{
+	file::ModelRef,
+	syntax::ast::{self, AstNode},
+	utils::arena::ArenaIndex,
+};
+
+/// A documentation comment for an item
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocComment {
+	/// The documentation group of the item
+	pub group: Option<String>,
+	/// The description of the item in markdown
+	pub description: String,
+	/// The name and description of each documented parameter
+	pub parameters: Vec<(String, String)>,
+}
+
+impl DocComment {
+	/// Get the documentation comment directly preceding the given item
+	pub fn from_item(item: &ast::Item) -> Option<Self> {
+		let node = item.cst_node();
+		let comment = node.as_ref().prev_sibling()?;
+		if comment.kind() != "block_comment" {
+			return None;
+		}
+		Self::parse(&node.cst().text()[comment.byte_range()])
+	}
+
+	/// Parse the text of a documentation comment (including the comment delimiters)
+	pub fn parse(text: &str) -> Option<Self> {
+		if text.starts_with("/***") {
+			return None;
+		}
+		let body = text.strip_prefix("/**")?.strip_suffix("*/")?;
+		let mut doc = DocComment::default();
+		let mut lines = Vec::new();
+		let mut in_parameter = false;
+		for line in comment_lines(body) {
+			let mut line = line;
+			if let Some(rest) = line.strip_prefix("@group ") {
+				let rest = rest.trim_start();
+				let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+				doc.group = Some(rest[..end].to_owned());
+				line = rest[end..].trim_start();
+			}
+			if let Some(rest) = line.strip_prefix("@param ") {
+				let (name, description) = rest.split_once(':').unwrap_or((rest, ""));
+				doc.parameters.push((
+					name.trim().to_owned(),
+					convert_references(description.trim()),
+				));
+				in_parameter = true;
+			} else if in_parameter && !line.is_empty() {
+				// Continuation of a parameter description
+				let (_, description) = doc.parameters.last_mut().unwrap();
+				description.push(' ');
+				description.push_str(&convert_references(line));
+			} else {
+				in_parameter = false;
+				lines.push(line);
+			}
+		}
+		let start = lines
+			.iter()
+			.position(|l| !l.is_empty())
+			.unwrap_or(lines.len());
+		let end = lines
+			.iter()
+			.rposition(|l| !l.is_empty())
+			.map_or(start, |i| i + 1);
+		doc.description = convert_references(&lines[start..end].join("\n"));
+		Some(doc)
+	}
+
+	/// Get the description of the given parameter
+	pub fn parameter(&self, name: &str) -> Option<&str> {
+		self.parameters
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, d)| d.as_str())
+	}
+
+	/// Render this documentation as markdown
+	pub fn to_markdown(&self) -> String {
+		let mut out = self.description.clone();
+		if !self.parameters.is_empty() {
+			if !out.is_empty() {
+				out.push_str("\n\n");
+			}
+			out.push_str("**Parameters**\n");
+			for (name, description) in self.parameters.iter() {
+				write!(&mut out, "\n- `{}`: {}", name, description).unwrap();
+			}
+		}
+		out
+	}
+}
+
+/// A documentation group defined using `@groupdef`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupDefinition {
+	/// The identifier of the group
+	pub id: String,
+	/// The title of the group
+	pub title: String,
+	/// The description of the group in markdown
+	pub description: String,
+}
+
+/// Get the documentation groups defined in the given model
+pub fn group_definitions(db: &dyn Hir, model: ModelRef) -> Vec<GroupDefinition> {
+	let Ok(cst) = db.cst(*model) else {
+		return Vec::new();
+	};
+	let root = cst.root_node();
+	let mut cursor = root.walk();
+	let mut groups = Vec::new();
+	for comment in root
+		.children(&mut cursor)
+		.filter(|n| n.kind() == "block_comment")
+	{
+		let Some(body) = cst.text()[comment.byte_range()]
+			.strip_prefix("/***")
+			.and_then(|b| b.strip_suffix("*/"))
+		else {
+			continue;
+		};
+		let mut current: Option<GroupDefinition> = None;
+		let mut description = Vec::new();
+		for line in comment_lines(body.trim_start_matches('*')) {
+			if let Some(rest) = line.strip_prefix("@groupdef ") {
+				if let Some(mut g) = current.take() {
+					g.description = convert_references(description.join("\n").trim());
+					groups.push(g);
+				}
+				description.clear();
+				let rest = rest.trim_start();
+				let (id, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+				current = Some(GroupDefinition {
+					id: id.to_owned(),
+					title: title.trim().to_owned(),
+					description: String::new(),
+				});
+			} else {
+				description.push(line);
+			}
+		}
+		if let Some(mut g) = current {
+			g.description = convert_references(description.join("\n").trim());
+			groups.push(g);
+		}
+	}
+	groups
+}
+
+/// Get the documentation in markdown for the item or parameter declared by the given pattern
+pub fn pattern_documentation(db: &dyn Hir, pattern: PatternRef) -> Option<String> {
+	let item = pattern.item();
+	let model = item.model(db);
+	let local_item = item.local_item_ref(db);
+	let doc = model.docs.get(&local_item)?;
+	if primary_pattern(&model, local_item) == Some(pattern.pattern()) {
+		return Some(doc.to_markdown());
+	}
+	if let LocalItemRef::Function(f) = local_item {
+		let parameter = model[f]
+			.parameters
+			.iter()
+			.find(|p| p.pattern == Some(pattern.pattern()))?;
+		let name = model[f].data[parameter.pattern?].identifier()?;
+		return doc.parameter(&name.lookup(db)).map(|d| d.to_owned());
+	}
+	None
+}
+
+/// Pretty print the signature of the function declared by the given pattern, including the
+/// names of its parameters
+pub fn pattern_function_signature(db: &dyn Hir, pattern: PatternRef) -> Option<String> {
+	let item = pattern.item();
+	let LocalItemRef::Function(f) = item.local_item_ref(db) else {
+		return None;
+	};
+	let model = item.model(db);
+	if model[f].pattern != pattern.pattern() {
+		return None;
+	}
+	function_signature(db, &db.lookup_item_types(item), &model, f)
+}
+
+/// Get the pattern for the name declared by an item
+fn primary_pattern(model: &Model, item: LocalItemRef) -> Option<ArenaIndex<Pattern>> {
+	match item {
+		LocalItemRef::Annotation(a) => match &model[a].constructor {
+			Constructor::Atom { pattern } => Some(*pattern),
+			Constructor::Function { constructor, .. } => Some(*constructor),
+		},
+		LocalItemRef::Declaration(d) => Some(model[d].pattern),
+		LocalItemRef::Enumeration(e) => Some(model[e].pattern),
+		LocalItemRef::Function(f) => Some(model[f].pattern),
+		LocalItemRef::TypeAlias(t) => Some(model[t].name),
+		_ => None,
+	}
+}
+
+/// Pretty print a function item including the names of its parameters
+fn function_signature(
+	db: &dyn Hir,
+	types: &TypeResult,
+	model: &Model,
+	function: ArenaIndex<Item<Function>>,
+) -> Option<String> {
+	let it = &model[function];
+	let PatternTy::Function(entry) = types.get_pattern(it.pattern)? else {
+		return None;
+	};
+	let name = it.data[it.pattern].identifier()?;
+	let item = entry.overload.pretty_print_item(db.upcast(), name);
+	let call = entry
+		.overload
+		.pretty_print_call_signature(db.upcast(), name);
+	let parameters = entry
+		.overload
+		.params()
+		.iter()
+		.zip(it.parameters.iter())
+		.map(
+			|(ty, p)| match p.pattern.and_then(|p| it.data[p].identifier()) {
+				Some(name) => format!(
+					"{}: {}",
+					ty.pretty_print(db.upcast()),
+					name.pretty_print(db)
+				),
+				None => ty.pretty_print(db.upcast()),
+			},
+		)
+		.collect::<Vec<_>>();
+	Some(format!(
+		"{}{}({})",
+		item.strip_suffix(&call)?,
+		name.pretty_print(db),
+		parameters.join(", ")
+	))
+}
+
+/// The format of generated documentation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocFormat {
+	/// Markdown
+	Markdown,
+	/// A standalone HTML page
+	Html,
+}
+
+/// Generate the API documentation for the items in the given models
+pub fn generate_documentation(db: &dyn Hir, models: &[ModelRef], format: DocFormat) -> String {
+	struct Section {
+		id: String,
+		title: String,
+		description: String,
+		entries: Vec<(String, Option<String>)>,
+	}
+	let definitions = models
+		.iter()
+		.flat_map(|m| group_definitions(db, *m))
+		.collect::<Vec<_>>();
+	let mut sections: Vec<Section> = Vec::new();
+	for model_ref in models.iter() {
+		let model = db.lookup_model(*model_ref);
+		let file_name = model_ref
+			.path(db.upcast())
+			.and_then(|p| p.file_name().map(|f| f.to_string_lossy().into_owned()))
+			.unwrap_or_else(|| model_ref.pretty_print(db.upcast()));
+		for local_item in model.items.iter() {
+			let Some(pattern) = primary_pattern(&model, *local_item) else {
+				continue;
+			};
+			let item = ItemRef::new(db, *model_ref, *local_item);
+			let data = local_item.data(&model);
+			let types = db.lookup_item_types(item);
+			let signature = match local_item {
+				LocalItemRef::Enumeration(_) => data[pattern]
+					.identifier()
+					.map(|i| format!("enum {}", i.pretty_print(db))),
+				LocalItemRef::Function(f) => function_signature(db, &types, &model, *f),
+				_ => types.pretty_print_pattern_ty(db, data, pattern),
+			};
+			let Some(signature) = signature else {
+				continue;
+			};
+			let doc = model.docs.get(local_item);
+			let id = doc
+				.and_then(|d| d.group.clone())
+				.unwrap_or_else(|| file_name.clone());
+			let index = match sections.iter().position(|s| s.id == id) {
+				Some(i) => i,
+				None => {
+					let definition = definitions.iter().find(|g| g.id == id);
+					sections.push(Section {
+						title: definition
+							.map(|g| g.title.clone())
+							.unwrap_or_else(|| id.clone()),
+						description: definition
+							.map(|g| g.description.clone())
+							.unwrap_or_default(),
+						id,
+						entries: Vec::new(),
+					});
+					sections.len() - 1
+				}
+			};
+			sections[index]
+				.entries
+				.push((signature, doc.map(|d| d.to_markdown())));
+		}
+	}
+
+	let mut out = String::new();
+	match format {
+		DocFormat::Markdown => {
+			for section in sections.iter() {
+				writeln!(&mut out, "## {}\n", section.title).unwrap();
+				if !section.description.is_empty() {
+					writeln!(&mut out, "{}\n", section.description).unwrap();
+				}
+				for (signature, doc) in section.entries.iter() {
+					writeln!(&mut out, "```minizinc\n{}\n```\n", signature).unwrap();
+					if let Some(doc) = doc.as_ref().filter(|d| !d.is_empty()) {
+						writeln!(&mut out, "{}\n", doc).unwrap();
+					}
+				}
+			}
+		}
+		DocFormat::Html => {
+			writeln!(
+				&mut out,
+				"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Documentation</title>\n</head>\n<body>"
+			)
+			.unwrap();
+			for section in sections.iter() {
+				writeln!(
+					&mut out,
+					"<h2 id=\"{}\">{}</h2>",
+					escape_html(&section.id),
+					escape_html(&section.title)
+				)
+				.unwrap();
+				if !section.description.is_empty() {
+					out.push_str(&markdown_to_html(&section.description));
+				}
+				for (signature, doc) in section.entries.iter() {
+					writeln!(
+						&mut out,
+						"<pre><code>{}</code></pre>",
+						escape_html(signature)
+					)
+					.unwrap();
+					if let Some(doc) = doc {
+						out.push_str(&markdown_to_html(doc));
+					}
+				}
+			}
+			writeln!(&mut out, "</body>\n</html>").unwrap();
+		}
+	}
+	out
+}
+
+/// Get the lines of a comment body with indentation and leading `*`s removed
+fn comment_lines(body: &str) -> impl '_ + Iterator<Item = &str> {
+	body.lines().map(|line| {
+		let line = line.trim();
+		line.strip_prefix("* ")
+			.or_else(|| line.strip_prefix('*'))
+			.unwrap_or(line)
+			.trim()
+	})
+}
+
+/// Convert `\a x` and `\p x` references into inline code
+fn convert_references(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(i) = rest.find('\\') {
+		out.push_str(&rest[..i]);
+		let after = &rest[i + 1..];
+		match after
+			.strip_prefix("a ")
+			.or_else(|| after.strip_prefix("p "))
+		{
+			Some(reference) => {
+				let end = reference
+					.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+					.unwrap_or(reference.len());
+				write!(&mut out, "`{}`", &reference[..end]).unwrap();
+				rest = &reference[end..];
+			}
+			None => {
+				out.push('\\');
+				rest = after;
+			}
+		}
+	}
+	out.push_str(rest);
+	out
+}
+
+fn escape_html(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+/// Convert the subset of markdown used in documentation comments to HTML
+fn markdown_to_html(text: &str) -> String {
+	let inline = |line: &str| {
+		escape_html(line)
+			.split('`')
+			.enumerate()
+			.map(|(i, s)| {
+				if i % 2 == 1 {
+					format!("<code>{}</code>", s)
+				} else {
+					s.replace("**", "")
+				}
+			})
+			.collect::<String>()
+	};
+	let mut out = String::new();
+	for paragraph in text.split("\n\n") {
+		let lines = paragraph.lines().collect::<Vec<_>>();
+		if !lines.is_empty() && lines.iter().all(|l| l.starts_with("- ")) {
+			out.push_str("<ul>\n");
+			for line in lines {
+				writeln!(&mut out, "<li>{}</li>", inline(&line[2..])).unwrap();
+			}
+			out.push_str("</ul>\n");
+		} else if !lines.is_empty() {
+			writeln!(&mut out, "<p>{}</p>", inline(&lines.join("\n"))).unwrap();
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use super::{generate_documentation, DocComment, DocFormat};
+	use crate::{
+		db::{CompilerDatabase, FileReader, Inputs},
+		file::InputFile,
+	};
+
+	#[test]
+	fn test_parse_doc_comment() {
+		let doc = DocComment::parse(
+			r"/** @group globals.graph
+    Constrains the subgraph \a ns and \a es of a given directed graph to be a tree.
+
+    @param from: the leaving node for each edge
+    @param to: the entering node
+      for each edge
+    @param r: the root node (see \p dtree)
+*/",
+		)
+		.unwrap();
+		assert_eq!(doc.group.as_deref(), Some("globals.graph"));
+		expect![[r#"
+            Constrains the subgraph `ns` and `es` of a given directed graph to be a tree.
+
+            **Parameters**
+
+            - `from`: the leaving node for each edge
+            - `to`: the entering node for each edge
+            - `r`: the root node (see `dtree`)"#]]
+		.assert_eq(&doc.to_markdown());
+		assert!(DocComment::parse("/*** @groupdef foo Foo */").is_none());
+		assert!(DocComment::parse("/* not documentation */").is_none());
+	}
+
+	#[test]
+	fn test_generate_documentation() {
+		let mut db = CompilerDatabase::default();
+		db.set_ignore_stdlib(true);
+		db.set_input_files(Arc::new(vec![InputFile::ModelString(
+			r#"
+/***
+  @groupdef example.counting Counting
+
+  Functions for counting things.
+*/
+
+/** @group example.counting Return \a x incremented by one
+  @param x: the value to increment
+*/
+function int: inc(int: x) = x;
+
+/** The number of things */
+int: n = 3;
+
+enum Foo = {A, B};
+"#
+			.to_owned(),
+		)]));
+		let models = db.input_models();
+		expect![[r#"
+            ## Counting
+
+            Functions for counting things.
+
+            ```minizinc
+            function int: inc(int: x)
+            ```
+
+            Return `x` incremented by one
+
+            **Parameters**
+
+            - `x`: the value to increment
+
+            ## <unnamed file>
+
+            ```minizinc
+            int: n
+            ```
+
+            The number of things
+
+            ```minizinc
+            enum Foo
+            ```
+
+        "#]]
+		.assert_eq(&generate_documentation(&db, &models, DocFormat::Markdown));
+	}
+}
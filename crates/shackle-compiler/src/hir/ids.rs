@@ -3,6 +3,7 @@
 use std::sync::Arc;
 
 use miette::SourceSpan;
+use serde::{Deserialize, Serialize};
 
 use super::{
 	db::Hir, Annotation, Assignment, Constraint, Declaration, EnumAssignment, Enumeration,
@@ -15,7 +16,7 @@ use crate::{
 };
 
 /// Reference to an item local to a model.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LocalItemRef {
 	/// Annotation item ID
 	Annotation(ArenaIndex<Item<Annotation>>),
@@ -125,7 +126,7 @@ impl salsa::InternKey for ItemRef {
 }
 
 /// Global reference to an item.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ItemRefData(ModelRef, LocalItemRef);
 
 /// Reference to a top-level item of known type.
@@ -238,7 +239,7 @@ impl TypeRef {
 }
 
 /// Global reference to a pattern.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PatternRef(ItemRef, ArenaIndex<Pattern>);
 
 impl PatternRef {
@@ -272,7 +273,7 @@ impl PatternRef {
 }
 
 /// Local reference to an entity (expression, type, or pattern) owned by an item.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LocalEntityRef {
 	/// Expression ID
 	Expression(ArenaIndex<Expression>),
@@ -342,11 +343,11 @@ impl salsa::InternKey for EntityRef {
 }
 
 /// Global reference to an entity (expression, type, or pattern).
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EntityRefData(ItemRef, LocalEntityRef);
 
 /// Reference to an HIR node (used to map back to AST).
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NodeRef {
 	/// Model reference
 	Model(ModelRef),
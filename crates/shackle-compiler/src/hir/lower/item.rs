@@ -7,6 +7,7 @@ use crate::{
 	file::ModelRef,
 	hir::{
 		db::Hir,
+		doc::DocComment,
 		ids::ItemRef,
 		source::{Origin, SourceMap},
 		*,
@@ -75,6 +76,9 @@ impl ItemCollector<'_> {
 			ast::Item::TypeAlias(t) => self.collect_type_alias(t),
 		};
 		log::debug!("Produced HIR item {:?}", it);
+		if let Some(doc) = DocComment::from_item(&item) {
+			self.model.docs.insert(it.local_item_ref(self.db), doc);
+		}
 		self.source_map.insert(it.into(), Origin::new(&item));
 		self.source_map.add_from_item_data(self.db, it, &sm);
 	}
@@ -15,8 +15,10 @@
 //!   module
 //! - Validation of whole program (see the `validate` module)
 
+pub mod call_graph;
 pub mod container;
 pub mod db;
+pub mod doc;
 pub mod expression;
 pub mod ids;
 pub mod item;
@@ -39,7 +41,9 @@ pub use scope::*;
 pub use typecheck::*;
 pub use types::*;
 
-use self::ids::LocalItemRef;
+use rustc_hash::FxHashMap;
+
+use self::{doc::DocComment, ids::LocalItemRef};
 use crate::utils::{
 	arena::{Arena, ArenaIndex},
 	impl_index,
@@ -72,6 +76,9 @@ pub struct Model {
 	pub solves: Arena<Item<Solve>>,
 	/// Type alias items
 	pub type_aliases: Arena<Item<TypeAlias>>,
+
+	/// Documentation comments for items
+	pub docs: FxHashMap<LocalItemRef, DocComment>,
 }
 
 impl_index!(Model[self, index: ArenaIndex<Item<Annotation>>] -> Item<Annotation> { self.annotations[index] });
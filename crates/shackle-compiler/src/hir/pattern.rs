@@ -1,6 +1,8 @@
 //! Destructuring/case matching patterns
 //!
 
+use serde::{Deserialize, Serialize};
+
 use super::{db::Hir, BooleanLiteral, FloatLiteral, IntegerLiteral, ItemData, StringLiteral};
 use crate::{
 	db::{InternedString, InternedStringData},
@@ -125,7 +127,7 @@ impl Pattern {
 }
 
 /// Identifier
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Identifier(pub InternedString);
 
 impl Identifier {
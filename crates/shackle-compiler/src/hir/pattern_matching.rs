@@ -2,7 +2,7 @@
 //!
 //! See http://moscova.inria.fr/~maranget/papers/warn/warn.pdf for algorithm details
 
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use rustc_hash::{FxHashMap, FxHashSet};
 
@@ -14,7 +14,7 @@ use super::{
 };
 use crate::{
 	constants::TypeRegistry,
-	diagnostics::{NonExhaustivePatternMatching, UnreachablePattern, Warning},
+	diagnostics::{FixIt, NonExhaustivePatternMatching, Suggestions, UnreachablePattern, Warning},
 	ty::{EnumRef, Ty, TyData},
 	utils::arena::ArenaIndex,
 	Error,
@@ -70,11 +70,12 @@ pub fn check_case_exhaustiveness(
 	let local = item.local_item_ref(db);
 	let data = local.data(&model);
 	let types = db.lookup_item_types(item);
-	for e in data.expressions.values() {
+	for (idx, e) in data.expressions.iter() {
 		if let Expression::Case(c) = e {
 			let checker = ExhaustivenessChecker::new(db, data, &types);
+			let arms = case_arm_ranges(db, item, idx);
 			let mut matrix = Matrix::with_capacity(c.cases.len());
-			for arm in c.cases.iter() {
+			for (i, arm) in c.cases.iter().enumerate() {
 				let pat = checker.lower_pattern(arm.pattern);
 				let row = vec![pat];
 				if !checker.is_useful(&matrix, &row) {
@@ -82,7 +83,15 @@ pub fn check_case_exhaustiveness(
 					let (src, span) =
 						NodeRef::from(PatternRef::new(item, arm.pattern).into_entity(db))
 							.source_span(db);
-					warnings.push(UnreachablePattern { src, span }.into());
+					let suggestions = arms.as_ref().and_then(|arms| remove_arm(arms, i));
+					warnings.push(
+						UnreachablePattern {
+							src,
+							span,
+							suggestions,
+						}
+						.into(),
+					);
 				}
 				matrix.add_row(row);
 			}
@@ -90,11 +99,17 @@ pub fn check_case_exhaustiveness(
 				// Non-exhaustive
 				let (src, span) =
 					NodeRef::from(EntityRef::new(db, item, c.expression)).source_span(db);
+				let missing = checker.missing_cases(matrix, types[c.expression]);
+				let body = placeholder(db, types[idx]);
+				let suggestions = arms
+					.as_ref()
+					.and_then(|arms| add_arms(src.contents(), arms, &missing, body));
 				errors.push(
 					NonExhaustivePatternMatching {
 						src,
 						span,
 						msg: format!("Case '{}' not covered", pat),
+						suggestions,
 					}
 					.into(),
 				);
@@ -104,6 +119,132 @@ pub fn check_case_exhaustiveness(
 	(Arc::new(errors), Arc::new(warnings))
 }
 
+/// Get the byte ranges of the arms of the given case expression
+fn case_arm_ranges(
+	db: &dyn Hir,
+	item: ItemRef,
+	expression: ArenaIndex<Expression>,
+) -> Option<Vec<Range<usize>>> {
+	let model = item.model_ref(db);
+	let sm = db.lookup_source_map(model);
+	let (_, span) = sm
+		.get_origin(EntityRef::new(db, item, expression).into())?
+		.source_span(db);
+	let cst = db.cst(*model).ok()?;
+	let node = cst
+		.root_node()
+		.descendant_for_byte_range(span.offset(), span.offset() + span.len())?;
+	if node.kind() != "case_expression" {
+		return None;
+	}
+	let mut cursor = node.walk();
+	let arms = node
+		.children_by_field_name("case", &mut cursor)
+		.map(|n| n.byte_range())
+		.collect();
+	Some(arms)
+}
+
+/// Suggest removing the case arm with the given index (along with its separator)
+fn remove_arm(arms: &[Range<usize>], index: usize) -> Option<Box<Suggestions>> {
+	let span = if index + 1 < arms.len() {
+		arms[index].start..arms[index + 1].start
+	} else if index > 0 {
+		arms[index - 1].end..arms[index].end
+	} else {
+		return None;
+	};
+	Some(Box::new(Suggestions {
+		help: "Remove the unreachable case arm".to_owned(),
+		fix_its: vec![FixIt {
+			message: "Remove unreachable case arm".to_owned(),
+			span: span.into(),
+			replacement: String::new(),
+		}],
+	}))
+}
+
+/// Suggest adding arms for the given missing cases after the last case arm.
+///
+/// The new arms use the given body, or the body of the last arm if there is no
+/// placeholder value for the type of the case expression.
+fn add_arms(
+	source: &str,
+	arms: &[Range<usize>],
+	missing: &[String],
+	body: Option<String>,
+) -> Option<Box<Suggestions>> {
+	let last = arms.last()?;
+	let body = match body {
+		Some(body) => body,
+		None => source[last.clone()].split_once("=>")?.1.trim().to_owned(),
+	};
+	let line_start = source[..last.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+	let indent = &source[line_start..last.start];
+	let separator = if indent.trim().is_empty() {
+		format!(",\n{}", indent)
+	} else {
+		", ".to_owned()
+	};
+	let replacement = missing
+		.iter()
+		.map(|p| format!("{}{} => {}", separator, p, body))
+		.collect::<String>();
+	let cases = missing
+		.iter()
+		.map(|p| format!("'{}'", p))
+		.collect::<Vec<_>>()
+		.join(", ");
+	Some(Box::new(Suggestions {
+		help: format!("Add case arms for {}", cases),
+		fix_its: vec![FixIt {
+			message: "Add missing case arms".to_owned(),
+			span: (last.end, 0).into(),
+			replacement,
+		}],
+	}))
+}
+
+/// Get a placeholder value of the given type for the body of a new case arm
+fn placeholder(db: &dyn Hir, ty: Ty) -> Option<String> {
+	if ty.opt(db.upcast()) == Some(OptType::Opt) {
+		return Some("<>".to_owned());
+	}
+	Some(match ty.lookup(db.upcast()) {
+		TyData::Boolean(_, _) => "false".to_owned(),
+		TyData::Integer(_, _) => "0".to_owned(),
+		TyData::Float(_, _) => "0.0".to_owned(),
+		TyData::String(_) => "\"\"".to_owned(),
+		TyData::Set(_, _, _) => "{}".to_owned(),
+		TyData::Array { dim, .. } if !matches!(dim.lookup(db.upcast()), TyData::Tuple(_, _)) => {
+			"[]".to_owned()
+		}
+		TyData::Tuple(_, fs) => {
+			let fields = fs
+				.iter()
+				.map(|f| placeholder(db, *f))
+				.collect::<Option<Vec<_>>>()?;
+			if fields.len() == 1 {
+				format!("({},)", fields[0])
+			} else {
+				format!("({})", fields.join(", "))
+			}
+		}
+		TyData::Record(_, fs) => format!(
+			"({})",
+			fs.iter()
+				.map(|(n, f)| Some(format!(
+					"{}: {}",
+					n.value(db.upcast()),
+					placeholder(db, *f)?
+				)))
+				.collect::<Option<Vec<_>>>()?
+				.join(", ")
+		),
+		_ => return None,
+	})
+}
+
 /// A pattern distilled into its meaning in the context of pattern matching.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(variant_size_differences)]
@@ -449,6 +590,20 @@ impl<'a> ExhaustivenessChecker<'a> {
 		Some(ps.first().unwrap().pretty_print(self.db))
 	}
 
+	/// Get examples of all of the cases not covered by the matrix (up to a limit)
+	fn missing_cases(&self, mut matrix: Matrix, ty: Ty) -> Vec<String> {
+		const LIMIT: usize = 32;
+		let mut missing = Vec::new();
+		while missing.len() < LIMIT {
+			let Some(ps) = self.generate_counter_example(&matrix, &[ty]) else {
+				break;
+			};
+			missing.push(ps[0].pretty_print(self.db));
+			matrix.add_row(ps);
+		}
+		missing
+	}
+
 	fn generate_counter_example(
 		&self,
 		matrix: &Matrix,
@@ -592,3 +747,92 @@ impl<'a> ExhaustivenessChecker<'a> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::{expect, Expect};
+
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		file::InputFile,
+		hir::db::Hir,
+	};
+
+	fn check_fix_its(model: &str, expected: Expect) {
+		let mut db = CompilerDatabase::default();
+		db.set_ignore_stdlib(true);
+		db.set_input_files(Arc::new(vec![InputFile::ModelString(model.to_owned())]));
+		let mut fix_its = db
+			.all_errors()
+			.iter()
+			.flat_map(|e| e.fix_its().to_vec())
+			.chain(db.all_warnings().iter().flat_map(|w| w.fix_its().to_vec()))
+			.collect::<Vec<_>>();
+		fix_its.sort_by_key(|f| std::cmp::Reverse(f.span.offset()));
+		let mut result = model.to_owned();
+		for f in fix_its {
+			result.replace_range(
+				f.span.offset()..f.span.offset() + f.span.len(),
+				&f.replacement,
+			);
+		}
+		expected.assert_eq(&result);
+	}
+
+	#[test]
+	fn test_add_missing_arms() {
+		check_fix_its(
+			r#"
+enum Foo = {A, B, C, D};
+Foo: x;
+int: y = case x of
+  B => 1,
+  A => 2
+endcase;
+int: z = case (x, true) of (A, _) => 1 endcase;
+Foo: w = case x of A => B endcase;
+"#,
+			expect![[r#"
+
+    enum Foo = {A, B, C, D};
+    Foo: x;
+    int: y = case x of
+      B => 1,
+      A => 2,
+      C => 0,
+      D => 0
+    endcase;
+    int: z = case (x, true) of (A, _) => 1, (B, _) => 0, (C, _) => 0, (D, _) => 0 endcase;
+    Foo: w = case x of A => B, B => B, C => B, D => B endcase;
+"#]],
+		);
+	}
+
+	#[test]
+	fn test_remove_unreachable_arm() {
+		check_fix_its(
+			r#"
+enum Foo = {A, B};
+Foo: x;
+int: y = case x of
+  A => 1,
+  _ => 2,
+  B => 3
+endcase;
+int: z = case x of _ => 1, A => 2 endcase;
+"#,
+			expect![[r#"
+
+    enum Foo = {A, B};
+    Foo: x;
+    int: y = case x of
+      A => 1,
+      _ => 2
+    endcase;
+    int: z = case x of _ => 1 endcase;
+"#]],
+		);
+	}
+}
@@ -2,22 +2,24 @@
 //!
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 use super::{db::Hir, Identifier};
 use crate::db::{InternedString, InternedStringData};
 
 /// An integer literal
-#[derive(Copy, Clone, Default, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IntegerLiteral(pub i64);
 
 /// A boolean literal
-#[derive(Copy, Clone, Default, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BooleanLiteral(pub bool);
 
 /// A float literal
 ///
 /// Uses u64 for storage so that Eq and Hash can be defined
 /// (since float literals in MiniZinc are always finite)
-#[derive(Copy, Clone, Default, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FloatLiteral(u64);
 
 impl FloatLiteral {
@@ -39,7 +41,7 @@ impl fmt::Debug for FloatLiteral {
 }
 
 /// A string literal
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StringLiteral(InternedString);
 
 impl StringLiteral {
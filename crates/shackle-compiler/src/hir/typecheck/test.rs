@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
 use expect_test::{expect, Expect};
+use miette::Diagnostic;
 
 use crate::{
 	db::{CompilerDatabase, FileReader, Inputs},
 	file::InputFile,
 	hir::{db::Hir, ids::LocalItemRef},
 	ty::Ty,
+	Error,
 };
 
 #[derive(Default)]
@@ -28,6 +30,38 @@ impl TypeTester {
 	}
 
 	fn check_error(&mut self, model: &str, expected: Expect) {
+		let result = self
+			.collect_errors(model)
+			.iter()
+			.map(|e| e.to_string())
+			.collect::<Vec<_>>()
+			.join("\n");
+		expected.assert_eq(&result);
+	}
+
+	fn check_suggestions(&mut self, model: &str, expected: Expect) {
+		let result = self
+			.collect_errors(model)
+			.iter()
+			.map(|e| {
+				let fix_its = e
+					.fix_its()
+					.iter()
+					.map(|f| format!("\n  {}: {:?}", f.message, f.replacement))
+					.collect::<String>();
+				format!(
+					"{}: {}{}",
+					e,
+					e.help().map(|h| h.to_string()).unwrap_or_default(),
+					fix_its
+				)
+			})
+			.collect::<Vec<_>>()
+			.join("\n");
+		expected.assert_eq(&result);
+	}
+
+	fn collect_errors(&mut self, model: &str) -> Vec<Error> {
 		self.db
 			.set_input_files(Arc::new(vec![InputFile::ModelString(model.to_owned())]));
 		let mut errors = Vec::new();
@@ -38,12 +72,7 @@ impl TypeTester {
 				}
 			}
 		}
-		let result = errors
-			.iter()
-			.map(|e| e.to_string())
-			.collect::<Vec<_>>()
-			.join("\n");
-		expected.assert_eq(&result);
+		errors
 	}
 
 	fn type_expression(&mut self, preamble: &str, expr: &str) -> Ty {
@@ -249,6 +278,71 @@ fn test_function_resolution() {
 	);
 }
 
+#[test]
+fn test_higher_order_functions() {
+	let mut tester = TypeTester::default();
+	tester.check_expression_preamble(
+		r#"
+        function $T: apply($T: x, op($T: ($T)): f) = f(x);
+        "#,
+		"apply(1, lambda int: (int: x) => x + 1)",
+		expect!("int"),
+	);
+	tester.check_expression_preamble(
+		r#"
+        function $T: apply($T: x, op($T: ($T)): f) = f(x);
+        "#,
+		"apply(1, lambda float: (float: x) => x / 2.0)",
+		expect!("float"),
+	);
+	tester.check_expression_preamble(
+		r#"
+        function var bool: all_hold(array [int] of var int: xs, op(var bool: (var int)): p) =
+            forall (x in xs) (p(x));
+        var 1..3: x;
+        "#,
+		"all_hold([x], lambda var bool: (var int: y) => y > 1)",
+		expect!("var bool"),
+	);
+	tester.check_expression_preamble(
+		r#"
+        function var bool: all_hold(array [int] of var int: xs, op(var bool: (var int)): p) =
+            forall (x in xs) (p(x));
+        var 1..3: x;
+        "#,
+		"all_hold([x], lambda bool: (int: y) => y > 1)",
+		expect!("error"),
+	);
+}
+
+#[test]
+fn test_function_type_arrow_syntax() {
+	let mut tester = TypeTester::default();
+	tester.check_expression_preamble(
+		r#"
+        function var bool: all_hold(array [int] of var int: xs, op(var int: x) -> var bool: p) =
+            forall (x in xs) (p(x));
+        var 1..3: x;
+        "#,
+		"all_hold([x], lambda var bool: (var int: y) => y > 1)",
+		expect!("var bool"),
+	);
+	tester.check_expression_preamble(
+		r#"
+        function $T: apply($T: x, op($T) -> $T: f) = f(x);
+        "#,
+		"apply(1, lambda int: (int: x) => x + 1)",
+		expect!("int"),
+	);
+	tester.check_expression_preamble(
+		r#"
+        op(int: x, int: y) -> int: f = lambda int: (int: a, int: b) => a + b;
+        "#,
+		"f",
+		expect!("op(int: (int, int))"),
+	);
+}
+
 #[test]
 fn test_type_errors() {
 	let mut tester = TypeTester::default();
@@ -283,3 +377,68 @@ fn test_type_errors() {
 		expect!("Undefined identifier"),
 	);
 }
+
+#[test]
+fn test_suggestions() {
+	let mut tester = TypeTester::default();
+	tester.check_suggestions(
+		r#"
+		int: value = 1;
+		any: x = valeu;
+		"#,
+		expect![[r#"
+    Undefined identifier: Did you mean 'value'?
+      Replace with 'value': "value""#]],
+	);
+	tester.check_suggestions(
+		r#"
+		function int: my_function(int: a) = a;
+		any: x = my_fnuction(1);
+		"#,
+		expect![[r#"
+    No matching function: Did you mean 'my_function'?
+      Replace with 'my_function': "my_function""#]],
+	);
+	tester.check_suggestions(
+		r#"
+		function int: my_function(int: a) = a;
+		function int: my_function(int: a, int: b) = a + b;
+		any: x = my_function(1, 2, 3);
+		"#,
+		expect![[r#"
+    No matching function: The closest candidate is 'function int: my_function(int, int)'
+      Remove the extra argument: """#]],
+	);
+	tester.check_suggestions(
+		r#"
+		function int: my_function(int: a) = a;
+		var int: v;
+		any: x = my_function(v);
+		"#,
+		expect![[r#"
+    No matching function: The closest candidate is 'function int: my_function(int)'
+      Convert argument 1 using 'fix': "fix(v)""#]],
+	);
+	tester.check_suggestions(
+		r#"
+		function int: my_function(int: a, float: b) = 1;
+		function int: my_function(float: a, int: b) = 2;
+		any: x = my_function(1, 1);
+		"#,
+		expect![[r#"
+    Ambiguous call: Could not choose an overload from the candidates 'function int: my_function(int, float)', 'function int: my_function(float, int)'
+      Call 'function int: my_function(int, float)' by converting argument 2 using 'int2float': "int2float(1)"
+      Call 'function int: my_function(float, int)' by converting argument 1 using 'int2float': "int2float(1)""#]],
+	);
+	tester.check_suggestions(
+		r#"
+		function int: my_function(int: a, int: b, float: c) = 1;
+		function int: my_function(float: a, float: b, int: c) = 2;
+		any: x = my_function(1, 2, 3);
+		"#,
+		expect![[r#"
+    Ambiguous call: Could not choose an overload from the candidates 'function int: my_function(int, int, float)', 'function int: my_function(float, float, int)'
+      Call 'function int: my_function(int, int, float)' by converting argument 3 using 'int2float': "int2float(3)"
+      Call 'function int: my_function(float, float, int)' by converting argument 1 using 'int2float', argument 2 using 'int2float': "int2float(1), int2float(2)""#]],
+	);
+}
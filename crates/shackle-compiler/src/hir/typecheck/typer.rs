@@ -6,8 +6,9 @@ use super::{PatternTy, TypeContext};
 use crate::{
 	constants::{IdentifierRegistry, TypeRegistry},
 	diagnostics::{
-		AmbiguousCall, BranchMismatch, IllegalType, InvalidArrayLiteral, InvalidFieldAccess,
-		NoMatchingFunction, SyntaxError, TypeInferenceFailure, TypeMismatch, UndefinedIdentifier,
+		AmbiguousCall, BranchMismatch, FixIt, IllegalType, InvalidArrayLiteral, InvalidFieldAccess,
+		NoMatchingFunction, Suggestions, SyntaxError, TypeInferenceFailure, TypeMismatch,
+		UndefinedIdentifier,
 	},
 	hir::{
 		db::Hir,
@@ -21,7 +22,7 @@ use crate::{
 		FunctionEntry, FunctionResolutionError, FunctionType, InstantiationError, OptType, Ty,
 		TyData, VarType,
 	},
-	utils::{arena::ArenaIndex, maybe_grow_stack},
+	utils::{arena::ArenaIndex, closest_matches, maybe_grow_stack, pretty_print_identifier},
 	Error,
 };
 
@@ -284,12 +285,14 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 		}
 
 		let (src, span) = NodeRef::from(EntityRef::new(db, self.item, expr)).source_span(db);
+		let suggestions = Suggestions::did_you_mean(span, self.similar_variables(expr, *i));
 		self.ctx.add_diagnostic(
 			self.item,
 			UndefinedIdentifier {
 				identifier: i.pretty_print(db),
 				src,
 				span,
+				suggestions,
 			},
 		);
 		self.types.error
@@ -310,7 +313,8 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 
 		match self.data[c.function] {
 			Expression::Identifier(i) => {
-				let (op, ret) = self.resolve_overloading(c.function, i, &args, is_annotation_for);
+				let (op, ret) =
+					self.resolve_overloading(c.function, i, &c.arguments, &args, is_annotation_for);
 				self.collect_annotations(c.function, op);
 				ret
 			}
@@ -333,6 +337,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 										.collect::<Vec<_>>()
 										.join(", ")
 								),
+								suggestions: None,
 							},
 						);
 						return self.types.error;
@@ -1663,6 +1668,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 		&mut self,
 		expr: ArenaIndex<Expression>,
 		i: Identifier,
+		arguments: &[ArenaIndex<Expression>],
 		args: &[Ty],
 		is_annotation_for: Option<Ty>,
 	) -> (Ty, Ty) {
@@ -1741,6 +1747,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 		let patterns = self.find_function(expr, i);
 		if patterns.is_empty() {
 			let (src, span) = NodeRef::from(EntityRef::new(db, self.item, expr)).source_span(db);
+			let suggestions = Suggestions::did_you_mean(span, self.similar_functions(expr, i));
 			self.ctx.add_diagnostic(
 				self.item,
 				NoMatchingFunction {
@@ -1750,6 +1757,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 						"No function with name '{}' could be found.",
 						i.pretty_print(db)
 					),
+					suggestions,
 				},
 			);
 			self.ctx
@@ -1826,35 +1834,59 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 				(op, ret)
 			}
 			Err(FunctionResolutionError::AmbiguousOverloading(ps)) => {
-				let mut msg = format!(
-					"Call with argument types {} is ambiguous.\n",
+				let msg = format!(
+					"Call with argument types {} is ambiguous.",
 					args.iter()
 						.map(|t| format!("'{}'", t.pretty_print(db.upcast())))
 						.collect::<Vec<_>>()
 						.join(", ")
 				);
-				writeln!(
-					&mut msg,
-					"Could not choose an overload from the candidate functions:"
-				)
-				.unwrap();
-				for (_, f) in ps.iter() {
-					writeln!(
-						&mut msg,
-						"  {}",
-						f.overload.pretty_print_item(db.upcast(), i)
-					)
-					.unwrap();
-				}
+				let fix_its = if is_annotation_for.is_none() {
+					ps.iter()
+						.filter_map(|(_, f)| {
+							self.select_overload_fix_it(expr, i, arguments, args, f)
+						})
+						.collect()
+				} else {
+					Vec::new()
+				};
+				let suggestions = Some(Box::new(Suggestions {
+					help: format!(
+						"Could not choose an overload from the candidates {}",
+						ps.iter()
+							.map(|(_, f)| format!(
+								"'{}'",
+								f.overload.pretty_print_item(db.upcast(), i)
+							))
+							.collect::<Vec<_>>()
+							.join(", ")
+					),
+					fix_its,
+				}));
 				let (src, span) =
 					NodeRef::from(EntityRef::new(db, self.item, expr)).source_span(db);
-				self.ctx
-					.add_diagnostic(self.item, AmbiguousCall { src, span, msg });
+				self.ctx.add_diagnostic(
+					self.item,
+					AmbiguousCall {
+						src,
+						span,
+						msg,
+						suggestions,
+					},
+				);
 				self.ctx
 					.add_expression(ExpressionRef::new(self.item, expr), self.types.error);
 				error
 			}
-			Err(FunctionResolutionError::NoMatchingFunction(es)) => {
+			Err(FunctionResolutionError::NoMatchingFunction(mut es)) => {
+				// List the overloads closest to matching first
+				es.sort_by_key(|(_, _, e)| match e {
+					InstantiationError::ArgumentMismatch { .. }
+					| InstantiationError::IncompatibleTypeInstVariable { .. } => 0,
+					InstantiationError::ArgumentCountMismatch { expected, actual } => {
+						1 + expected.abs_diff(*actual)
+					}
+				});
 				let mut msg = String::new();
 				if args.is_empty() {
 					writeln!(
@@ -1933,10 +1965,33 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 					}
 				}
 
+				let fix_its = if is_annotation_for.is_none() {
+					self.call_fix_its(expr, arguments, &es[0].2)
+				} else {
+					Vec::new()
+				};
+				let suggestions = if es.len() > 1 || !fix_its.is_empty() {
+					Some(Box::new(Suggestions {
+						help: format!(
+							"The closest candidate is '{}'",
+							es[0].1.overload.pretty_print_item(db.upcast(), i)
+						),
+						fix_its,
+					}))
+				} else {
+					None
+				};
 				let (src, span) =
 					NodeRef::from(EntityRef::new(db, self.item, expr)).source_span(db);
-				self.ctx
-					.add_diagnostic(self.item, NoMatchingFunction { src, span, msg });
+				self.ctx.add_diagnostic(
+					self.item,
+					NoMatchingFunction {
+						src,
+						span,
+						msg,
+						suggestions,
+					},
+				);
 				self.ctx
 					.add_expression(ExpressionRef::new(self.item, expr), self.types.error);
 				error
@@ -1944,6 +1999,178 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 		}
 	}
 
+	/// Get machine-applicable fixes which make a call match the closest candidate overload
+	fn call_fix_its(
+		&mut self,
+		expr: ArenaIndex<Expression>,
+		arguments: &[ArenaIndex<Expression>],
+		error: &InstantiationError,
+	) -> Vec<FixIt> {
+		let db = self.db;
+		match error {
+			InstantiationError::ArgumentCountMismatch { expected, actual }
+				if actual > expected && *actual == arguments.len() =>
+			{
+				// Remove the extra arguments (along with the preceding comma)
+				let (_, first) = NodeRef::from(EntityRef::new(db, self.item, arguments[*expected]))
+					.source_span(db);
+				let (_, last) = NodeRef::from(EntityRef::new(db, self.item, arguments[actual - 1]))
+					.source_span(db);
+				let start = if *expected == 0 {
+					first.offset()
+				} else {
+					let (_, previous) =
+						NodeRef::from(EntityRef::new(db, self.item, arguments[expected - 1]))
+							.source_span(db);
+					previous.offset() + previous.len()
+				};
+				let count = actual - expected;
+				vec![FixIt {
+					message: if count == 1 {
+						"Remove the extra argument".to_owned()
+					} else {
+						format!("Remove the {} extra arguments", count)
+					},
+					span: (start, last.offset() + last.len() - start).into(),
+					replacement: String::new(),
+				}]
+			}
+			InstantiationError::ArgumentMismatch {
+				index,
+				expected,
+				actual,
+			} if *index < arguments.len() => {
+				// Wrap the argument in a conversion function
+				let Some(conversion) = self.find_conversion(expr, *actual, *expected) else {
+					return Vec::new();
+				};
+				let message = format!(
+					"Convert argument {} using '{}'",
+					index + 1,
+					conversion.pretty_print(db)
+				);
+				self.convert_arguments_fix_it(arguments, &[(*index, conversion)], message)
+					.into_iter()
+					.collect()
+			}
+			_ => Vec::new(),
+		}
+	}
+
+	/// Get a machine-applicable fix which makes an ambiguous call select the given overload by
+	/// converting arguments to its parameter types
+	fn select_overload_fix_it(
+		&mut self,
+		expr: ArenaIndex<Expression>,
+		i: Identifier,
+		arguments: &[ArenaIndex<Expression>],
+		args: &[Ty],
+		candidate: &FunctionEntry,
+	) -> Option<FixIt> {
+		let db = self.db;
+		if arguments.len() != args.len() {
+			return None;
+		}
+		let (_, fn_type) = candidate
+			.overload
+			.instantiate_ty_params(db.upcast(), args)
+			.ok()?;
+		let mut conversions = Vec::new();
+		for (index, (actual, expected)) in args.iter().zip(fn_type.params.iter()).enumerate() {
+			if actual != expected {
+				conversions.push((index, self.find_conversion(expr, *actual, *expected)?));
+			}
+		}
+		if conversions.is_empty() {
+			return None;
+		}
+		let message = format!(
+			"Call '{}' by converting {}",
+			candidate.overload.pretty_print_item(db.upcast(), i),
+			conversions
+				.iter()
+				.map(|(index, conversion)| format!(
+					"argument {} using '{}'",
+					index + 1,
+					conversion.pretty_print(db)
+				))
+				.collect::<Vec<_>>()
+				.join(", ")
+		);
+		self.convert_arguments_fix_it(arguments, &conversions, message)
+	}
+
+	/// Find a conversion function which converts a value of type `actual` into a value whose
+	/// type is a subtype of `expected`
+	fn find_conversion(
+		&mut self,
+		expr: ArenaIndex<Expression>,
+		actual: Ty,
+		expected: Ty,
+	) -> Option<Identifier> {
+		let db = self.db;
+		let conversions = [
+			self.identifiers.int2float,
+			self.identifiers.bool2int,
+			self.identifiers.fix,
+			self.identifiers.deopt,
+		];
+		for conversion in conversions {
+			let mut overloads = Vec::new();
+			for p in self.find_function(expr, conversion).iter() {
+				if let PatternTy::Function(function) = self.ctx.type_pattern(db, *p) {
+					overloads.push((*p, *function.clone()));
+				}
+			}
+			if let Ok((_, fe, tvs)) = FunctionEntry::match_fn(db.upcast(), overloads, &[actual]) {
+				let ret = fe.overload.instantiate(db.upcast(), &tvs).return_type;
+				if ret.is_subtype_of(db.upcast(), expected) {
+					return Some(conversion);
+				}
+			}
+		}
+		None
+	}
+
+	/// Create a fix-it which wraps the given arguments of a call (in order) in conversion
+	/// functions
+	fn convert_arguments_fix_it(
+		&self,
+		arguments: &[ArenaIndex<Expression>],
+		conversions: &[(usize, Identifier)],
+		message: String,
+	) -> Option<FixIt> {
+		let db = self.db;
+		let (src, first) = NodeRef::from(EntityRef::new(
+			db,
+			self.item,
+			arguments[conversions.first()?.0],
+		))
+		.source_span(db);
+		let contents = src.contents();
+		let start = first.offset();
+		let mut end = start;
+		let mut replacement = String::new();
+		for (index, conversion) in conversions {
+			let (_, span) =
+				NodeRef::from(EntityRef::new(db, self.item, arguments[*index])).source_span(db);
+			replacement.push_str(contents.get(end..span.offset())?);
+			end = span.offset() + span.len();
+			write!(
+				&mut replacement,
+				"{}({})",
+				conversion.pretty_print(db),
+				contents.get(span.offset()..end)?
+			)
+			.unwrap();
+		}
+		Some(FixIt {
+			message,
+			span: (start, end - start).into(),
+			replacement,
+		})
+	}
+
 	/// Collect the type of a pattern
 	pub fn collect_pattern(
 		&mut self,
@@ -2061,6 +2288,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 										name.pretty_print(db),
 										expected.pretty_print(db.upcast())
 									),
+									suggestions: None,
 								},
 							);
 							None
@@ -2079,6 +2307,7 @@ impl<'a, T: TypeContext> Typer<'a, T> {
 									c.overload.params().len(),
 									arguments.len()
 								),
+								suggestions: None,
 							},
 						);
 					}
@@ -2414,12 +2643,17 @@ supported in operation types."
 						} else {
 							let (src, span) = NodeRef::from(EntityRef::new(db, self.item, *domain))
 								.source_span(db);
+							let suggestions = Suggestions::did_you_mean(
+								span,
+								self.similar_variables(*domain, *i),
+							);
 							self.ctx.add_diagnostic(
 								self.item,
 								UndefinedIdentifier {
 									identifier: i.pretty_print(db),
 									src,
 									span,
+									suggestions,
 								},
 							);
 							return self.types.error;
@@ -2780,4 +3014,39 @@ supported in operation types."
 		let scope = self.db.lookup_item_scope(self.item);
 		scope.find_function(self.db, expression, identifier)
 	}
+
+	/// Find the names of variables in scope which are similar to the given (undefined) identifier
+	fn similar_variables(
+		&self,
+		expression: ArenaIndex<Expression>,
+		identifier: Identifier,
+	) -> Vec<String> {
+		let scope = self.db.lookup_item_scope(self.item);
+		let candidates = scope
+			.variables_in_scope(self.db, expression)
+			.into_iter()
+			.map(|(i, _)| i.lookup(self.db));
+		closest_matches(&identifier.lookup(self.db), candidates, 3)
+			.iter()
+			.map(|n| pretty_print_identifier(n))
+			.collect()
+	}
+
+	/// Find the names of functions in scope which are similar to the given (undefined) identifier
+	fn similar_functions(
+		&self,
+		expression: ArenaIndex<Expression>,
+		identifier: Identifier,
+	) -> Vec<String> {
+		let scope = self.db.lookup_item_scope(self.item);
+		let candidates = scope
+			.functions_in_scope(self.db, expression)
+			.into_iter()
+			.filter(|(_, ps)| !ps.is_empty())
+			.map(|(i, _)| i.lookup(self.db));
+		closest_matches(&identifier.lookup(self.db), candidates, 3)
+			.iter()
+			.map(|n| pretty_print_identifier(n))
+			.collect()
+	}
 }
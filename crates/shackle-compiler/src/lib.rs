@@ -12,6 +12,7 @@ pub mod diagnostics;
 pub mod file;
 pub mod hir;
 pub mod mir;
+pub mod project;
 pub mod syntax;
 pub mod thir;
 pub mod ty;
@@ -0,0 +1,203 @@
+//! Project configuration.
+//!
+//! A project file (`shackle.toml`) describes the model and data files which make up a problem
+//! instance, along with the settings used to compile them. It is read by both the command line
+//! interface and the language server, so that the editor reports the same diagnostics as solving.
+//!
+//! ```toml
+//! models = ["model.mzn"]
+//! data = ["data/small.dzn"]
+//! search-directories = ["lib"]
+//! solver = "gecode"
+//! ```
+//!
+//! Relative paths are resolved from the directory containing the project file. Unless a
+//! `globals-directory` is given, the library for the solver in the standard library directory is
+//! used (where the solver may be overridden when compiling, e.g. using `--solver`).
+
+use std::{
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use serde::Deserialize;
+
+use crate::{
+	db::{CompilerDatabase, Inputs},
+	diagnostics::SyntaxError,
+	file::SourceFile,
+	Result,
+};
+
+/// The file name of a project file
+pub const PROJECT_FILE_NAME: &str = "shackle.toml";
+
+/// The configuration of a project
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProjectConfig {
+	/// The model files
+	pub models: Vec<PathBuf>,
+	/// The data files
+	pub data: Vec<PathBuf>,
+	/// Additional directories to search for included files
+	pub search_directories: Vec<PathBuf>,
+	/// The `share/minizinc` directory containing the standard library
+	pub stdlib_directory: Option<PathBuf>,
+	/// The directory containing solver specific globals
+	pub globals_directory: Option<PathBuf>,
+	/// The solver to use
+	pub solver: Option<String>,
+}
+
+impl ProjectConfig {
+	/// Read the project file at the given path
+	pub fn from_file(path: &Path) -> Result<Self> {
+		Self::parse(&SourceFile::try_from(path)?)
+	}
+
+	/// Parse the contents of a project file
+	pub fn parse(src: &SourceFile) -> Result<Self> {
+		let config: Self = toml::from_str(src.contents()).map_err(|err| SyntaxError {
+			src: src.clone(),
+			span: err.span().unwrap_or_default().into(),
+			msg: err.message().to_owned(),
+			other: Vec::new(),
+		})?;
+		let root = src.path().and_then(Path::parent).unwrap_or(Path::new(""));
+		Ok(config.resolve_paths(root))
+	}
+
+	/// Find the project file for the given directory by searching it and its ancestors
+	pub fn find(dir: &Path) -> Option<PathBuf> {
+		dir.ancestors()
+			.map(|d| d.join(PROJECT_FILE_NAME))
+			.find(|p| p.is_file())
+	}
+
+	/// Whether the given file is one of the model or data files of this project
+	pub fn contains(&self, path: &Path) -> bool {
+		self.models
+			.iter()
+			.chain(self.data.iter())
+			.any(|p| p == path)
+	}
+
+	/// Use the settings from this project when compiling for the given solver (or the solver of
+	/// the project if `None`)
+	pub fn apply(&self, db: &mut CompilerDatabase, solver: Option<&str>) {
+		db.set_search_directories(Arc::new(self.search_directories.clone()));
+		if let Some(stdlib) = &self.stdlib_directory {
+			db.set_stdlib_directory(Some(Arc::new(stdlib.clone())));
+		}
+		// Use the solver's library from the standard library directory unless
+		// another globals directory is given
+		let globals = self
+			.globals_directory
+			.clone()
+			.or_else(|| solver.or(self.solver.as_deref()).map(PathBuf::from));
+		db.set_globals_directory(globals.map(Arc::new));
+	}
+
+	fn resolve_paths(mut self, root: &Path) -> Self {
+		for p in self
+			.models
+			.iter_mut()
+			.chain(self.data.iter_mut())
+			.chain(self.search_directories.iter_mut())
+			.chain(self.stdlib_directory.iter_mut())
+			.chain(self.globals_directory.iter_mut())
+		{
+			*p = root.join(&*p);
+		}
+		self
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		path::{Path, PathBuf},
+		sync::Arc,
+	};
+
+	use expect_test::expect;
+
+	use super::ProjectConfig;
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		file::SourceFile,
+		Error,
+	};
+
+	fn parse(path: &str, contents: &str) -> Result<ProjectConfig, Error> {
+		ProjectConfig::parse(&SourceFile::with_path(
+			PathBuf::from(path),
+			Arc::new(contents.to_owned()),
+		))
+	}
+
+	#[test]
+	fn test_parse_project() {
+		let config = parse(
+			"/project/shackle.toml",
+			r#"
+models = ["model.mzn"]
+data = ["data/small.dzn", "/data/large.json"]
+search-directories = ["lib"]
+globals-directory = "gecode"
+solver = "gecode"
+"#,
+		)
+		.unwrap();
+		assert_eq!(
+			config,
+			ProjectConfig {
+				models: vec![PathBuf::from("/project/model.mzn")],
+				data: vec![
+					PathBuf::from("/project/data/small.dzn"),
+					PathBuf::from("/data/large.json")
+				],
+				search_directories: vec![PathBuf::from("/project/lib")],
+				stdlib_directory: None,
+				globals_directory: Some(PathBuf::from("/project/gecode")),
+				solver: Some("gecode".to_owned()),
+			}
+		);
+		assert!(config.contains(Path::new("/project/data/small.dzn")));
+		assert!(!config.contains(Path::new("/project/other.mzn")));
+	}
+
+	#[test]
+	fn test_apply_project_solver() {
+		let globals = |config: &ProjectConfig, solver| {
+			let mut db = CompilerDatabase::default();
+			config.apply(&mut db, solver);
+			db.globals_directory()
+		};
+		let mut config = parse("/project/shackle.toml", "solver = \"gecode\"\n").unwrap();
+		assert_eq!(
+			globals(&config, None),
+			Some(Arc::new(PathBuf::from("gecode")))
+		);
+		assert_eq!(
+			globals(&config, Some("chuffed")),
+			Some(Arc::new(PathBuf::from("chuffed")))
+		);
+		config.globals_directory = Some(PathBuf::from("/globals"));
+		assert_eq!(
+			globals(&config, Some("chuffed")),
+			Some(Arc::new(PathBuf::from("/globals")))
+		);
+	}
+
+	#[test]
+	fn test_parse_project_error() {
+		let Error::SyntaxError(err) = parse("shackle.toml", "model = \"model.mzn\"\n").unwrap_err()
+		else {
+			panic!("expected syntax error");
+		};
+		assert_eq!(err.span, (0, 5).into());
+		expect![[r#"unknown field `model`, expected one of `models`, `data`, `search-directories`, `stdlib-directory`, `globals-directory`, `solver`"#]].assert_eq(&err.msg);
+	}
+}
@@ -1,5 +1,7 @@
 //! AST representation for types
 
+use serde::{Deserialize, Serialize};
+
 use super::{helpers::*, AstNode, Children, Expression, Identifier};
 
 ast_enum!(
@@ -118,7 +120,10 @@ impl RecordField {
 }
 
 ast_node!(
-	/// Type of an operation (function)
+	/// Type of an operation (function).
+	///
+	/// Written either as `op(int: (bool, float))` or as `op(bool: x, float) -> int`, where the
+	/// parameter names are optional and do not affect the type.
 	OperationType,
 	return_type,
 	parameter_types,
@@ -192,7 +197,7 @@ ast_node!(
 );
 
 /// Whether a type is var or par
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum VarType {
 	/// Fixed parameter
 	Par,
@@ -211,7 +216,7 @@ impl VarType {
 }
 
 /// Whether a type is opt or non-opt
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum OptType {
 	/// Non-optional variable
 	NonOpt,
@@ -0,0 +1,824 @@
+//! Formatting of MiniZinc source code.
+//!
+//! The formatter works on the CST so that comments are preserved. The tree is
+//! converted into a document made up of groups, where each group is laid out
+//! on a single line if it fits within the line width, and is otherwise broken
+//! over multiple lines.
+
+use std::ops::Range;
+
+use tree_sitter::{Node, Parser};
+
+use super::cst::Cst;
+
+/// Options for formatting MiniZinc source code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+	/// The number of columns per level of indentation
+	pub indent_width: usize,
+	/// Whether to indent using tabs instead of spaces
+	pub use_tabs: bool,
+	/// The maximum width of a line
+	pub line_width: usize,
+}
+
+impl Default for FormatOptions {
+	fn default() -> Self {
+		Self {
+			indent_width: 2,
+			use_tabs: false,
+			line_width: 80,
+		}
+	}
+}
+
+/// Format the source code of the given CST.
+///
+/// Returns `None` if the source contains syntax errors.
+pub fn format(cst: &Cst, options: &FormatOptions) -> Option<String> {
+	if cst.root_node().child_count() == 0 {
+		return (!cst.root_node().has_error()).then(String::new);
+	}
+	let (_, mut text) = format_range(cst, options, 0..cst.text().len())?;
+	text.push('\n');
+	Some(text)
+}
+
+/// Format the top-level items of the CST which overlap the given byte range.
+///
+/// Returns the byte range of the source which was formatted (which spans
+/// whole items), along with the formatted text to replace it with.
+///
+/// Returns `None` if the source contains syntax errors, or if there are no
+/// items in the range.
+pub fn format_range(
+	cst: &Cst,
+	options: &FormatOptions,
+	range: Range<usize>,
+) -> Option<(Range<usize>, String)> {
+	let root = cst.root_node();
+	if root.has_error() {
+		return None;
+	}
+	let nodes = children(root);
+	let first = nodes.iter().position(|n| n.end_byte() >= range.start)?;
+	let mut last = nodes.iter().rposition(|n| n.start_byte() <= range.end)?;
+	if last < first {
+		return None;
+	}
+	if nodes.get(last + 1).map(|n| n.kind()) == Some(";") {
+		last += 1;
+	}
+	let span = nodes[first].start_byte()..nodes[last].end_byte();
+
+	let formatter = Formatter { source: cst.text() };
+	let doc = formatter.items(&nodes[first..=last]);
+	let text = Printer::new(options).print(&doc);
+
+	// Guard against changing the meaning of the source
+	match (tokens(&cst.text()[span.clone()]), tokens(&text)) {
+		(Some(before), Some(after)) if before == after => Some((span, text)),
+		_ => {
+			log::warn!("formatting changed the tokens of the source, ignoring");
+			None
+		}
+	}
+}
+
+/// Document built from the CST, which is then laid out by the `Printer`
+#[derive(Clone, Debug)]
+enum Doc {
+	/// Literal text
+	Text(String),
+	/// A space, which is omitted at the start of a line
+	Space,
+	/// A space if the enclosing group is flat, otherwise a newline
+	Line,
+	/// Nothing if the enclosing group is flat, otherwise a newline
+	SoftLine,
+	/// Always a newline
+	HardLine,
+	/// Always a newline followed by an empty line
+	BlankLine,
+	/// Increase the indentation of any newlines in the contents
+	Indent(Vec<Doc>),
+	/// Lay out the contents on a single line if possible
+	Group(Vec<Doc>),
+	/// Concatenation of documents
+	Concat(Vec<Doc>),
+}
+
+impl Doc {
+	fn text(s: &str) -> Self {
+		Doc::Text(s.to_owned())
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+	Flat,
+	Break,
+}
+
+/// Lays out a `Doc`.
+///
+/// Whitespace is output lazily, so that consecutive newlines collapse and
+/// lines never contain trailing whitespace.
+struct Printer<'a> {
+	options: &'a FormatOptions,
+	out: String,
+	column: usize,
+	newlines: usize,
+	indent: usize,
+	space: bool,
+}
+
+impl<'a> Printer<'a> {
+	fn new(options: &'a FormatOptions) -> Self {
+		Self {
+			options,
+			out: String::new(),
+			column: 0,
+			newlines: 0,
+			indent: 0,
+			space: false,
+		}
+	}
+
+	fn print(mut self, doc: &Doc) -> String {
+		let mut stack = vec![(0, Mode::Break, doc)];
+		while let Some((indent, mode, doc)) = stack.pop() {
+			match doc {
+				Doc::Text(s) => self.text(s),
+				Doc::Space => self.space(),
+				Doc::Line if mode == Mode::Flat => self.space(),
+				Doc::SoftLine if mode == Mode::Flat => (),
+				Doc::Line | Doc::SoftLine | Doc::HardLine => self.newline(indent, 1),
+				Doc::BlankLine => self.newline(indent, 2),
+				Doc::Indent(docs) => stack.extend(docs.iter().rev().map(|d| (indent + 1, mode, d))),
+				Doc::Group(docs) => {
+					let mode = if mode == Mode::Flat || self.fits(docs, &stack) {
+						Mode::Flat
+					} else {
+						Mode::Break
+					};
+					stack.extend(docs.iter().rev().map(|d| (indent, mode, d)));
+				}
+				Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (indent, mode, d))),
+			}
+		}
+		self.out
+	}
+
+	/// Whether the given documents fit on the current line when laid out flat,
+	/// along with the following documents up to the next possible line break
+	fn fits(&self, docs: &[Doc], rest: &[(usize, Mode, &Doc)]) -> bool {
+		let start = if self.newlines > 0 {
+			self.indent * self.options.indent_width
+		} else {
+			self.column + usize::from(self.space)
+		};
+		let mut width = self.options.line_width as isize - start as isize;
+		let mut stack = docs
+			.iter()
+			.rev()
+			.map(|d| (Mode::Flat, d))
+			.collect::<Vec<_>>();
+		let mut rest = rest.iter().rev();
+		while width >= 0 {
+			let (mode, doc) = match stack.pop() {
+				Some(next) => next,
+				None => match rest.next() {
+					Some((_, mode, doc)) => (*mode, *doc),
+					None => return true,
+				},
+			};
+			match doc {
+				Doc::Text(s) => {
+					if s.contains('\n') {
+						return mode == Mode::Break;
+					}
+					width -= s.chars().count() as isize;
+				}
+				Doc::Space => width -= 1,
+				Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::BlankLine
+					if mode == Mode::Break =>
+				{
+					return true
+				}
+				Doc::Line => width -= 1,
+				Doc::SoftLine => (),
+				Doc::HardLine | Doc::BlankLine => return false,
+				Doc::Indent(docs) | Doc::Group(docs) | Doc::Concat(docs) => {
+					stack.extend(docs.iter().rev().map(|d| (mode, d)))
+				}
+			}
+		}
+		false
+	}
+
+	fn text(&mut self, s: &str) {
+		if self.newlines > 0 {
+			for _ in 0..self.newlines {
+				self.out.push('\n');
+			}
+			self.column = self.indent * self.options.indent_width;
+			if self.options.use_tabs {
+				self.out.push_str(&"\t".repeat(self.indent));
+			} else {
+				self.out.push_str(&" ".repeat(self.column));
+			}
+			self.newlines = 0;
+		} else if self.space {
+			self.out.push(' ');
+			self.column += 1;
+		}
+		self.space = false;
+		self.out.push_str(s);
+		match s.rfind('\n') {
+			Some(i) => self.column = s[i + 1..].chars().count(),
+			None => self.column += s.chars().count(),
+		}
+	}
+
+	fn space(&mut self) {
+		if self.newlines == 0 && !self.out.is_empty() {
+			self.space = true;
+		}
+	}
+
+	fn newline(&mut self, indent: usize, count: usize) {
+		if !self.out.is_empty() {
+			self.newlines = self.newlines.max(count);
+			self.indent = indent;
+			self.space = false;
+		}
+	}
+}
+
+const RANGE_OPERATORS: [&str; 4] = ["..", "<..", "..<", "<..<"];
+
+/// Converts CST nodes into documents
+struct Formatter<'a> {
+	source: &'a str,
+}
+
+impl<'a> Formatter<'a> {
+	fn text(&self, node: Node) -> Doc {
+		Doc::text(&self.source[node.byte_range()])
+	}
+
+	/// Lay out top-level items (and comments) one per line
+	fn items(&self, nodes: &[Node<'a>]) -> Doc {
+		let mut docs = Vec::new();
+		let mut prev: Option<Node> = None;
+		for node in nodes.iter() {
+			if let Some(p) = prev {
+				if node.kind() == ";" {
+					// Separator directly follows the item unless it is after a line comment
+					if p.kind() == "line_comment" {
+						docs.push(Doc::HardLine);
+					}
+				} else if node.is_extra() && !self.starts_line(*node) {
+					docs.push(Doc::Space);
+				} else if self.source[p.end_byte()..node.start_byte()]
+					.matches('\n')
+					.count() > 1
+				{
+					docs.push(Doc::BlankLine);
+				} else {
+					docs.push(Doc::HardLine);
+				}
+			}
+			if node.is_extra() {
+				docs.push(Doc::text(self.source[node.byte_range()].trim_end()));
+			} else {
+				docs.push(self.node(*node));
+			}
+			prev = Some(*node);
+		}
+		Doc::Concat(docs)
+	}
+
+	fn node(&self, node: Node<'a>) -> Doc {
+		match node.kind() {
+			"string_literal" | "string_interpolation" => self.text(node),
+			"line_comment" | "block_comment" => self.comment(node),
+			"infix_operator" => self.infix_operator(node),
+			"if_then_else" => self.if_then_else(node),
+			"let_expression" => self.let_expression(node),
+			"case_expression" => self.case_expression(node),
+			"array_comprehension" | "set_comprehension" => self.comprehension(node),
+			"array_literal_2d" => self.array_literal_2d(node),
+			_ if node.child_count() == 0 => self.text(node),
+			_ => Doc::Concat(self.sequence(&children(node))),
+		}
+	}
+
+	/// Lay out a sequence of sibling nodes, grouping the contents of brackets
+	fn sequence(&self, nodes: &[Node<'a>]) -> Vec<Doc> {
+		let mut docs = Vec::new();
+		let mut prev: Option<Node> = None;
+		let mut i = 0;
+		while i < nodes.len() {
+			let node = nodes[i];
+			i += 1;
+			if node.is_extra() {
+				docs.push(self.comment(node));
+				continue;
+			}
+			if let Some(p) = prev {
+				if self.space_between(p, node) {
+					docs.push(Doc::Space);
+				}
+			}
+			prev = Some(node);
+			if let Some(close) = closing_bracket(node.kind()) {
+				let mut depth = 0;
+				let end = nodes[i..].iter().position(|n| {
+					if n.kind() == node.kind() {
+						depth += 1;
+					} else if n.kind() == close {
+						if depth == 0 {
+							return true;
+						}
+						depth -= 1;
+					}
+					false
+				});
+				if let Some(end) = end {
+					docs.push(self.delimited(node, &nodes[i..i + end], nodes[i + end]));
+					prev = Some(nodes[i + end]);
+					i += end + 1;
+					continue;
+				}
+			}
+			docs.push(self.node(node));
+		}
+		docs
+	}
+
+	/// Lay out a comma separated list between brackets
+	fn delimited(&self, open: Node<'a>, nodes: &[Node<'a>], close: Node<'a>) -> Doc {
+		if nodes.is_empty() {
+			return Doc::Concat(vec![self.text(open), self.text(close)]);
+		}
+		let mut docs = vec![self.text(open)];
+		let mut i = 0;
+		while i < nodes.len() && nodes[i].is_extra() && !self.starts_line(nodes[i]) {
+			docs.push(self.comment(nodes[i]));
+			i += 1;
+		}
+		let mut inner = vec![Doc::SoftLine];
+		inner.extend(self.elements(&nodes[i..], &[","]));
+		docs.push(Doc::Indent(inner));
+		docs.push(Doc::SoftLine);
+		docs.push(self.text(close));
+		Doc::Group(docs)
+	}
+
+	/// Lay out elements with the given separators, with a line break after
+	/// each separator
+	fn elements(&self, nodes: &[Node<'a>], separators: &[&str]) -> Vec<Doc> {
+		let mut docs = Vec::new();
+		let mut depth = 0;
+		let mut start = 0;
+		let mut i = 0;
+		while i < nodes.len() {
+			let node = nodes[i];
+			i += 1;
+			match node.kind() {
+				"(" | "[" | "{" => depth += 1,
+				")" | "]" | "}" => depth -= 1,
+				kind if depth == 0 && separators.contains(&kind) => {
+					docs.extend(self.sequence(&nodes[start..i - 1]));
+					docs.push(self.text(node));
+					// Keep comments on the same line as the separator
+					while i < nodes.len() && nodes[i].is_extra() && !self.starts_line(nodes[i]) {
+						docs.push(self.comment(nodes[i]));
+						i += 1;
+					}
+					if i < nodes.len() {
+						docs.push(Doc::Line);
+					}
+					start = i;
+				}
+				_ => (),
+			}
+		}
+		docs.extend(self.sequence(&nodes[start..]));
+		docs
+	}
+
+	/// Lay out a chain of infix operators, breaking after each operator
+	fn infix_operator(&self, node: Node<'a>) -> Doc {
+		let operator = node
+			.child_by_field_name("operator")
+			.map(|o| o.kind())
+			.unwrap_or_default();
+		if RANGE_OPERATORS.contains(&operator) {
+			return Doc::Concat(self.sequence(&children(node)));
+		}
+		// Flatten left-associative chains of the same operator
+		let mut links = Vec::new();
+		let mut current = node;
+		let first = loop {
+			let nodes = children(current);
+			let left = current.child_by_field_name("left");
+			links.push(nodes.into_iter().filter(move |n| Some(*n) != left));
+			match left {
+				Some(l)
+					if l.kind() == "infix_operator"
+						&& l.child_by_field_name("operator").map(|o| o.kind())
+							== Some(operator) =>
+				{
+					current = l
+				}
+				Some(l) => break l,
+				None => return Doc::Concat(self.sequence(&children(node))),
+			}
+		};
+		let mut rest = Vec::new();
+		for link in links.into_iter().rev() {
+			for n in link {
+				if n.is_extra() {
+					rest.push(self.comment(n));
+				} else if n.kind() == operator {
+					rest.push(Doc::Space);
+					rest.push(self.text(n));
+				} else {
+					rest.push(Doc::Line);
+					rest.push(self.node(n));
+				}
+			}
+		}
+		// Continuation lines are already indented inside brackets
+		if matches!(
+			node.prev_sibling().map(|n| n.kind()),
+			Some("(" | "[" | "{" | ",")
+		) {
+			Doc::Group([self.node(first)].into_iter().chain(rest).collect())
+		} else {
+			Doc::Group(vec![self.node(first), Doc::Indent(rest)])
+		}
+	}
+
+	fn if_then_else(&self, node: Node<'a>) -> Doc {
+		let mut docs = Vec::new();
+		let mut prev = "";
+		for child in children(node) {
+			match child.kind() {
+				"if" => docs.push(self.text(child)),
+				"then" => {
+					docs.push(Doc::Space);
+					docs.push(self.text(child));
+				}
+				"elseif" | "else" | "endif" => {
+					docs.push(Doc::Line);
+					docs.push(self.text(child));
+				}
+				_ if child.is_extra() => {
+					docs.push(self.comment(child));
+					continue;
+				}
+				_ if matches!(prev, "if" | "elseif") => {
+					docs.push(Doc::Space);
+					docs.push(self.node(child));
+				}
+				_ => docs.push(Doc::Indent(vec![Doc::Line, self.node(child)])),
+			}
+			prev = child.kind();
+		}
+		Doc::Group(docs)
+	}
+
+	fn let_expression(&self, node: Node<'a>) -> Doc {
+		let nodes = children(node);
+		let (Some(open), Some(close)) = (
+			nodes.iter().position(|n| n.kind() == "{"),
+			nodes.iter().position(|n| n.kind() == "}"),
+		) else {
+			return Doc::Concat(self.sequence(&nodes));
+		};
+		let mut items = vec![Doc::Line];
+		items.extend(self.elements(&nodes[open + 1..close], &[";", ","]));
+		let mut docs = vec![Doc::Group(
+			self.sequence(&nodes[..=open])
+				.into_iter()
+				.chain([Doc::Indent(items), Doc::Line, self.text(nodes[close])])
+				.collect(),
+		)];
+		for n in nodes[close + 1..].iter() {
+			if n.is_extra() {
+				docs.push(self.comment(*n));
+			} else if n.kind() == "in" {
+				docs.push(Doc::Space);
+				docs.push(self.text(*n));
+			} else {
+				docs.push(Doc::Group(vec![Doc::Indent(vec![
+					Doc::Line,
+					self.node(*n),
+				])]));
+			}
+		}
+		Doc::Concat(docs)
+	}
+
+	fn case_expression(&self, node: Node<'a>) -> Doc {
+		let nodes = children(node);
+		let (Some(of), Some(end)) = (
+			nodes.iter().position(|n| n.kind() == "of"),
+			nodes.iter().position(|n| n.kind() == "endcase"),
+		) else {
+			return Doc::Concat(self.sequence(&nodes));
+		};
+		let mut cases = vec![Doc::Line];
+		cases.extend(self.elements(&nodes[of + 1..end], &[","]));
+		let mut docs = self.sequence(&nodes[..=of]);
+		docs.push(Doc::Indent(cases));
+		docs.push(Doc::Line);
+		docs.extend(self.sequence(&nodes[end..]));
+		Doc::Group(docs)
+	}
+
+	fn comprehension(&self, node: Node<'a>) -> Doc {
+		let nodes = children(node);
+		let Some(bar) = nodes.iter().position(|n| n.kind() == "|") else {
+			return Doc::Concat(self.sequence(&nodes));
+		};
+		let last = nodes.len() - 1;
+		let mut inner = vec![Doc::SoftLine];
+		inner.extend(self.sequence(&nodes[1..bar]));
+		inner.extend([
+			Doc::Line,
+			self.text(nodes[bar]),
+			Doc::Space,
+			Doc::Group(vec![Doc::Indent(
+				self.elements(&nodes[bar + 1..last], &[","]),
+			)]),
+		]);
+		Doc::Group(vec![
+			self.text(nodes[0]),
+			Doc::Indent(inner),
+			Doc::SoftLine,
+			self.text(nodes[last]),
+		])
+	}
+
+	/// Lay out a 2D array literal with a line per row
+	fn array_literal_2d(&self, node: Node<'a>) -> Doc {
+		let nodes = children(node);
+		let last = nodes.len() - 1;
+		if last < 2 {
+			return Doc::Concat(self.sequence(&nodes));
+		}
+		let mut inner = vec![Doc::Line];
+		let mut prev: Option<Node> = None;
+		for n in nodes[1..last].iter() {
+			if n.is_extra() {
+				inner.push(self.comment(*n));
+				continue;
+			}
+			if n.kind() == "|" {
+				inner.push(Doc::Line);
+			} else if let Some(p) = prev {
+				if self.space_between(p, *n) {
+					inner.push(Doc::Space);
+				}
+			}
+			inner.push(self.node(*n));
+			prev = Some(*n);
+		}
+		Doc::Group(vec![
+			self.text(nodes[0]),
+			Doc::Indent(inner),
+			Doc::Line,
+			self.text(nodes[last]),
+		])
+	}
+
+	/// Lay out a comment, keeping it on the same line as the preceding code
+	/// if it was originally
+	fn comment(&self, node: Node) -> Doc {
+		let before = if self.starts_line(node) {
+			Doc::HardLine
+		} else {
+			Doc::Space
+		};
+		let source = &self.source[node.end_byte()..];
+		let after = if node.kind() == "line_comment"
+			|| source
+				.trim_start_matches([' ', '\t', '\r'])
+				.starts_with('\n')
+		{
+			Doc::HardLine
+		} else {
+			Doc::Space
+		};
+		Doc::Concat(vec![
+			before,
+			Doc::text(self.source[node.byte_range()].trim_end()),
+			after,
+		])
+	}
+
+	/// Whether the node is the first thing on its line
+	fn starts_line(&self, node: Node) -> bool {
+		let before = self.source[..node.start_byte()].trim_end_matches([' ', '\t']);
+		before.is_empty() || before.ends_with('\n')
+	}
+
+	/// Whether to separate adjacent sibling nodes with a space
+	fn space_between(&self, prev: Node, next: Node) -> bool {
+		let parent = next.parent().map(|p| p.kind()).unwrap_or_default();
+		match (prev.kind(), next.kind()) {
+			(_, "," | ";" | ":" | ")" | "]" | "}" | "^-1" | "annotation_parameters") => false,
+			("(" | "[" | "{", _) => false,
+			(".", _) | (_, ".") => false,
+			("lambda" | ":", "(") => true,
+			(_, "(") => false,
+			(_, "[") => parent != "indexed_access",
+			("-" | "¬", _) => !matches!(parent, "prefix_operator" | "pattern_numeric_literal"),
+			(p, n) if RANGE_OPERATORS.contains(&p) || RANGE_OPERATORS.contains(&n) => !matches!(
+				parent,
+				"infix_operator" | "prefix_operator" | "postfix_operator"
+			),
+			_ => true,
+		}
+	}
+}
+
+fn closing_bracket(kind: &str) -> Option<&'static str> {
+	match kind {
+		"(" => Some(")"),
+		"[" => Some("]"),
+		"{" => Some("}"),
+		_ => None,
+	}
+}
+
+fn children(node: Node) -> Vec<Node> {
+	let mut cursor = node.walk();
+	let children = node.children(&mut cursor).collect();
+	children
+}
+
+/// Get the tokens of the given source, ignoring whitespace
+fn tokens(source: &str) -> Option<Vec<(&'static str, String)>> {
+	let mut parser = Parser::new();
+	parser
+		.set_language(tree_sitter_minizinc::language())
+		.expect("Failed to set Tree Sitter parser language");
+	let tree = parser.parse(source.as_bytes(), None)?;
+	if tree.root_node().has_error() {
+		return None;
+	}
+	let mut tokens = Vec::new();
+	let mut cursor = tree.walk();
+	'outer: loop {
+		let node = cursor.node();
+		if node.child_count() == 0 {
+			let text = &source[node.byte_range()];
+			tokens.push((
+				node.kind(),
+				if node.is_extra() {
+					text.split_whitespace().collect::<Vec<_>>().join(" ")
+				} else {
+					text.to_owned()
+				},
+			));
+		} else if cursor.goto_first_child() {
+			continue;
+		}
+		while !cursor.goto_next_sibling() {
+			if !cursor.goto_parent() {
+				break 'outer;
+			}
+		}
+	}
+	Some(tokens)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::{expect, Expect};
+	use tree_sitter::Parser;
+
+	use super::{format, format_range, FormatOptions};
+	use crate::syntax::cst::Cst;
+
+	fn parse(source: &str) -> Cst {
+		let mut parser = Parser::new();
+		parser
+			.set_language(tree_sitter_minizinc::language())
+			.unwrap();
+		let tree = parser.parse(source.as_bytes(), None).unwrap();
+		Cst::from_str(tree, source)
+	}
+
+	fn check_format(source: &str, options: FormatOptions, expected: Expect) {
+		let formatted = format(&parse(source), &options).expect("Failed to format");
+		expected.assert_eq(&formatted);
+		// Formatting should be idempotent
+		assert_eq!(
+			format(&parse(&formatted), &options).as_deref(),
+			Some(formatted.as_str())
+		);
+	}
+
+	#[test]
+	fn test_format_items() {
+		check_format(
+			r#"
+include "globals.mzn";
+int:n=3;array[1..n]of var 1..n:x;   % the variables
+
+
+constraint all_different ( x );
+constraint x[1]<x[n] /\ x [ 2 ] > -1;
+solve minimize sum(i in 1..n)(x[i]);
+output ["x = \(x)\n"];
+"#,
+			FormatOptions::default(),
+			expect![[r#"
+    include "globals.mzn";
+    int: n = 3;
+    array [1..n] of var 1..n: x; % the variables
+
+    constraint all_different(x);
+    constraint x[1] < x[n] /\ x[2] > -1;
+    solve minimize sum(i in 1..n)(x[i]);
+    output ["x = \(x)\n"];
+"#]],
+		);
+	}
+
+	#[test]
+	fn test_format_line_breaks() {
+		check_format(
+			r#"
+function var int: foo(var int: a, var int: b) = let { var int: c = a + b; constraint c > 0; } in if c > 10 then c else -c endif;
+constraint forall (i in 1..10, j in 1..10 where i < j) (some_long_predicate_name(i, j) /\ another_long_predicate(j, i) /\ i != j);
+array [1..2, 1..3] of int: d = [| 1, 2, 3 | 4, 5, 6 |];
+"#,
+			FormatOptions {
+				indent_width: 4,
+				line_width: 60,
+				..Default::default()
+			},
+			expect![[r#"
+    function var int: foo(var int: a, var int: b) = let {
+        var int: c = a + b;
+        constraint c > 0;
+    } in if c > 10 then c else -c endif;
+    constraint forall(i in 1..10, j in 1..10 where i < j)(
+        some_long_predicate_name(i, j) /\
+        another_long_predicate(j, i) /\
+        i != j
+    );
+    array [1..2, 1..3] of int: d = [| 1, 2, 3 | 4, 5, 6 |];
+"#]],
+		);
+	}
+
+	#[test]
+	fn test_format_comments() {
+		check_format(
+			r#"
+% Leading comment
+var bool: b; /* trailing */
+constraint foo(1, % first
+  2, /* second */ 3
+  % last
+);
+"#,
+			FormatOptions {
+				use_tabs: true,
+				..Default::default()
+			},
+			expect![[r#"
+    % Leading comment
+    var bool: b; /* trailing */
+    constraint foo(
+    	1, % first
+    	2, /* second */
+    	3
+    	% last
+    );
+"#]],
+		);
+	}
+
+	#[test]
+	fn test_format_range() {
+		let source = "int: a=1;\nint: b=2;\nint: c=3;\n";
+		let (range, text) =
+			format_range(&parse(source), &FormatOptions::default(), 12..12).unwrap();
+		expect![[r#"10..19 "int: b = 2;""#]].assert_eq(&format!("{:?} {:?}", range, text));
+	}
+
+	#[test]
+	fn test_format_syntax_error() {
+		assert!(format(&parse("int: x = ;"), &FormatOptions::default()).is_none());
+	}
+}
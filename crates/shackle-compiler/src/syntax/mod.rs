@@ -9,6 +9,9 @@
 //!
 //! The AST is then lowered into HIR, which is the main representation used by the compiler.
 //!
+//! The CST is also used directly by the formatter, since it must preserve comments.
+//!
 pub mod ast;
 pub mod cst;
 pub mod db;
+pub mod format;
@@ -5,7 +5,12 @@
 use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 use super::{transform::thir_transforms, Model};
-use crate::{db::Upcast, diagnostics::Diagnostics, hir::db::Hir, Error, Result};
+use crate::{
+	db::Upcast,
+	diagnostics::{Diagnostics, Warning},
+	hir::db::Hir,
+	Error, Result,
+};
 
 /// THIR queries
 #[salsa::query_group(ThirStorage)]
@@ -20,6 +25,10 @@ pub trait Thir: Hir + Upcast<dyn Hir> {
 	/// Check that the pretty printed THIR is a valid model
 	#[salsa::invoke(super::sanity_check::sanity_check_thir)]
 	fn sanity_check_thir(&self) -> Arc<Diagnostics<Error>>;
+
+	/// Get warnings for integer variables which have no bounds
+	#[salsa::invoke(super::transform::bounds::unbounded_variable_warnings)]
+	fn unbounded_variable_warnings(&self) -> Arc<Vec<Warning>>;
 }
 
 /// Represents an intermediate query result which can be taken
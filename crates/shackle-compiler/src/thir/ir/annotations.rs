@@ -3,12 +3,15 @@
 
 use std::ops::{Deref, DerefMut};
 
+use serde::{Deserialize, Serialize};
+
 use super::{
 	Call, Callable, Expression, ExpressionData, Identifier, Marker, Model, ResolvedIdentifier,
 };
 
 /// Collection of annotations
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Annotations<T: Marker = ()> {
 	annotations: Vec<Expression<T>>,
 }
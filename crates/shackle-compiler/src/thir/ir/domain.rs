@@ -1,6 +1,8 @@
 //! Representation of variable domains
 use std::ops::Deref;
 
+use serde::{Deserialize, Serialize};
+
 use super::{Expression, Marker};
 pub use crate::hir::{OptType, VarType};
 use crate::{
@@ -11,9 +13,11 @@ use crate::{
 };
 
 /// Ascribed domain of a variable
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Domain<T: Marker = ()> {
 	ty: Ty,
+	#[serde(with = "crate::thir::serialize::grow_stack")]
 	data: DomainData<T>,
 	origin: Origin,
 }
@@ -233,7 +237,8 @@ impl<T: Marker> Clone for Domain<T> {
 }
 
 /// Ascribed domain of a variable
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum DomainData<T: Marker = ()> {
 	/// Bounded by an expression
 	Bounded(Box<Expression<T>>),
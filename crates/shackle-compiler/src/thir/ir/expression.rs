@@ -6,6 +6,8 @@ use std::{
 	ops::{Deref, DerefMut},
 };
 
+use serde::{Deserialize, Serialize};
+
 use super::{
 	domain::{OptType, VarType},
 	AnnotationId, Annotations, ConstraintId, Declaration, DeclarationId, Domain, EnumerationId,
@@ -27,9 +29,11 @@ pub trait ExpressionBuilder<T: Marker = ()> {
 /// An expression.
 ///
 /// The data inside an expression is immutable (as modifying the data could invalidate the type).
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Expression<T: Marker = ()> {
 	ty: Ty,
+	#[serde(with = "crate::thir::serialize::grow_stack")]
 	data: ExpressionData<T>,
 	annotations: Annotations<T>,
 	origin: Origin,
@@ -114,7 +118,8 @@ impl<T: Marker> Clone for Expression<T> {
 }
 
 /// An expression
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum ExpressionData<T: Marker = ()> {
 	/// Absent `<>`
 	Absent,
@@ -275,7 +280,8 @@ impl<T: Marker> ExpressionBuilder<T> for Infinity {
 }
 
 /// Array literal
-#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ArrayLiteral<T: Marker = ()>(pub Vec<Expression<T>>);
 
 impl<T: Marker> ExpressionBuilder<T> for ArrayLiteral<T> {
@@ -310,7 +316,8 @@ impl<T: Marker> DerefMut for ArrayLiteral<T> {
 }
 
 /// Set literal
-#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct SetLiteral<T: Marker = ()>(pub Vec<Expression<T>>);
 
 impl<T: Marker> ExpressionBuilder<T> for SetLiteral<T> {
@@ -347,7 +354,8 @@ impl<T: Marker> DerefMut for SetLiteral<T> {
 }
 
 /// Tuple literal
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct TupleLiteral<T: Marker = ()>(pub Vec<Expression<T>>);
 
 impl<T: Marker> ExpressionBuilder<T> for TupleLiteral<T> {
@@ -375,7 +383,8 @@ impl<T: Marker> DerefMut for TupleLiteral<T> {
 }
 
 /// Record literal
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct RecordLiteral<T: Marker = ()>(pub Vec<(Identifier, Expression<T>)>);
 
 impl<T: Marker> ExpressionBuilder<T> for RecordLiteral<T> {
@@ -400,7 +409,8 @@ impl<T: Marker> DerefMut for RecordLiteral<T> {
 }
 
 /// Array comprehension
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ArrayComprehension<T: Marker = ()> {
 	/// Value of the comprehension
 	pub template: Box<Expression<T>>,
@@ -465,7 +475,8 @@ impl<T: Marker> ExpressionBuilder<T> for ArrayComprehension<T> {
 }
 
 /// Set comprehension
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct SetComprehension<T: Marker = ()> {
 	/// Value of the comprehension
 	pub template: Box<Expression<T>>,
@@ -501,7 +512,8 @@ impl<T: Marker> ExpressionBuilder<T> for SetComprehension<T> {
 }
 
 /// Array access
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ArrayAccess<T: Marker = ()> {
 	/// The array being indexed into
 	pub collection: Box<Expression<T>>,
@@ -544,7 +556,8 @@ impl<T: Marker> ExpressionBuilder<T> for ArrayAccess<T> {
 }
 
 /// Tuple access
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct TupleAccess<T: Marker = ()> {
 	/// Tuple being accessed
 	pub tuple: Box<Expression<T>>,
@@ -574,7 +587,8 @@ impl<T: Marker> ExpressionBuilder<T> for TupleAccess<T> {
 }
 
 /// Record access
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct RecordAccess<T: Marker = ()> {
 	/// Record being accessed
 	pub record: Box<Expression<T>>,
@@ -606,7 +620,8 @@ impl<T: Marker> ExpressionBuilder<T> for RecordAccess<T> {
 }
 
 /// If-then-else
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct IfThenElse<T: Marker = ()> {
 	/// The if-then and elseif-then branches
 	pub branches: Vec<Branch<T>>,
@@ -661,7 +676,8 @@ impl<T: Marker> ExpressionBuilder<T> for IfThenElse<T> {
 }
 
 /// Case expression
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Case<T: Marker = ()> {
 	/// The expression being matched on
 	pub scrutinee: Box<Expression<T>>,
@@ -690,7 +706,8 @@ impl<T: Marker> ExpressionBuilder<T> for Case<T> {
 }
 
 /// Target of a function call
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Callable<T: Marker = ()> {
 	/// Call to a function item
 	Function(FunctionId<T>),
@@ -707,7 +724,8 @@ pub enum Callable<T: Marker = ()> {
 }
 
 /// A function call
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Call<T: Marker = ()> {
 	/// Function being called
 	pub function: Callable<T>,
@@ -941,7 +959,8 @@ impl<T: Marker> ExpressionBuilder<T> for LookupIdentifier {
 }
 
 /// A let expression
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Let<T: Marker = ()> {
 	/// Items in this let expression
 	pub items: Vec<LetItem<T>>,
@@ -965,7 +984,8 @@ impl<T: Marker> ExpressionBuilder<T> for Let<T> {
 }
 
 /// A lambda function
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Lambda<T: Marker = ()>(pub FunctionId<T>);
 
 impl<T: Marker> ExpressionBuilder<T> for Lambda<T> {
@@ -1049,7 +1069,8 @@ impl<T: Marker> ExpressionBuilder<T> for Identifier {
 }
 
 /// An identifier which resolves to a declaration
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum ResolvedIdentifier<T: Marker = ()> {
 	/// Identifier resolves to an annotation atom
 	Annotation(AnnotationId<T>),
@@ -1078,7 +1099,8 @@ impl<T: Marker> ExpressionBuilder<T> for ResolvedIdentifier<T> {
 }
 
 /// Reference to a member of an enum
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct EnumMemberId<T: Marker = ()> {
 	parent: EnumerationId<T>,
 	index: u32,
@@ -1201,7 +1223,8 @@ impl EnumConstructorKind {
 }
 
 /// Comprehension generator
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Generator<T: Marker = ()> {
 	/// Generator which iterates over a collection
 	Iterator {
@@ -1293,7 +1316,8 @@ impl<T: Marker> Generator<T> {
 }
 
 /// A branch of an `IfThenElse`
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Branch<T: Marker = ()> {
 	/// The boolean condition
 	pub condition: Expression<T>,
@@ -1314,7 +1338,8 @@ impl<T: Marker> Branch<T> {
 }
 
 /// A branch of a `Case`
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CaseBranch<T: Marker = ()> {
 	/// The pattern to match
 	pub pattern: Pattern<T>,
@@ -1337,7 +1362,8 @@ impl<T: Marker> CaseBranch<T> {
 /// Note that patterns at this level do not represent binding to variables.
 /// Instead, the anonymous wildcard pattern is used, and destructuring happens
 /// via destructor functions.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Pattern<T: Marker = ()> {
 	data: PatternData<T>,
 	origin: Origin,
@@ -1536,7 +1562,8 @@ impl<T: Marker> DerefMut for Pattern<T> {
 }
 
 /// A pattern for a case expression.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum PatternData<T: Marker = ()> {
 	/// Enum constructor call
 	EnumConstructor {
@@ -1563,7 +1590,8 @@ pub enum PatternData<T: Marker = ()> {
 }
 
 /// An item in a let expression
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum LetItem<T: Marker = ()> {
 	/// A local constraint item
 	Constraint(ConstraintId<T>),
@@ -2,6 +2,8 @@
 
 use std::ops::{Deref, DerefMut};
 
+use serde::{Deserialize, Serialize};
+
 use super::{domain::Domain, Annotations, Expression, Identifier, Marker, Model};
 use crate::{
 	thir::{db::Thir, source::Origin},
@@ -13,7 +15,7 @@ use crate::{
 };
 
 /// An item of type `T`.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Item<T> {
 	item: T,
 	origin: Origin,
@@ -53,7 +55,8 @@ impl<T> Item<T> {
 }
 
 /// Annotation item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Annotation<T: Marker = ()> {
 	constructor: Constructor<T>,
 }
@@ -97,7 +100,8 @@ impl<T: Marker> Annotation<T> {
 }
 
 /// Constraint item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Constraint<T: Marker = ()> {
 	expression: Expression<T>,
 	annotations: Annotations<T>,
@@ -149,7 +153,8 @@ impl<T: Marker> Constraint<T> {
 pub type ConstraintId<T = ()> = ArenaIndex<ConstraintItem<T>>;
 
 /// A declaration item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Declaration<T: Marker = ()> {
 	domain: Domain<T>,
 	name: Option<Identifier>,
@@ -278,7 +283,8 @@ pub type EnumerationItem<T = ()> = Item<Enumeration<T>>;
 pub type EnumerationId<T = ()> = ArenaIndex<EnumerationItem<T>>;
 
 /// A enum item
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Enumeration<T: Marker = ()> {
 	enum_type: EnumRef,
 	definition: Option<Vec<Constructor<T>>>,
@@ -344,7 +350,8 @@ impl<T: Marker> Enumeration<T> {
 }
 
 /// A constructor (either atomic or a constructor function)
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Constructor<T: Marker = ()> {
 	/// The name of this constructor
 	pub name: Option<Identifier>,
@@ -353,7 +360,7 @@ pub struct Constructor<T: Marker = ()> {
 }
 
 /// Function name or identifier for anonymous function
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FunctionName {
 	/// Named function.
 	Named(Identifier),
@@ -429,7 +436,8 @@ impl PartialEq<Identifier> for FunctionName {
 }
 
 /// Function item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Function<T: Marker = ()> {
 	domain: Domain<T>,
 	name: FunctionName,
@@ -643,7 +651,8 @@ impl<T: Marker> Function<T> {
 }
 
 /// Output item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Output<T: Marker = ()> {
 	section: Option<Expression<T>>,
 	expression: Expression<T>,
@@ -696,7 +705,8 @@ impl<T: Marker> Output<T> {
 }
 
 /// Solve item
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Solve<T: Marker = ()> {
 	/// Solve goal
 	goal: Goal<T>,
@@ -772,7 +782,8 @@ impl<T: Marker> Solve<T> {
 }
 
 /// Solve method and objective
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Goal<T: Marker = ()> {
 	/// Satisfaction problem
 	Satisfy,
@@ -789,7 +800,8 @@ pub enum Goal<T: Marker = ()> {
 }
 
 /// ID of an item
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum ItemId<T: Marker = ()> {
 	/// Annotation item
 	Annotation(AnnotationId<T>),
@@ -3,6 +3,8 @@
 
 use std::ops::Index;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
 	hir::Identifier,
 	ty::{FunctionEntry, FunctionResolutionError, OverloadedFunction, Ty, TyParamInstantiations},
@@ -61,7 +63,8 @@ impl From<crate::hir::db::EntityCounts> for EntityCounts {
 }
 
 /// A model
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Model<T: Marker = ()> {
 	annotations: Arena<AnnotationItem<T>>,
 	constraints: Arena<ConstraintItem<T>>,
@@ -12,7 +12,9 @@ pub mod db;
 pub mod lower;
 pub mod pretty_print;
 pub mod sanity_check;
+pub mod serialize;
 pub mod source;
+pub mod statistics;
 pub mod transform;
 
 mod ir;
@@ -62,6 +62,24 @@ impl<'a, T: Marker> PrettyPrinter<'a, T> {
 			writeln!(&mut buf, "solve satisfy;").unwrap();
 		}
 		if self.old_compat {
+			// Old MiniZinc only reports output sections created using output items
+			for (_, declaration) in self.model.top_level_declarations() {
+				if !declaration.annotations().has(self.model, ids.output_only) {
+					continue;
+				}
+				let Some(name) = declaration.name() else {
+					continue;
+				};
+				if let Some(section) = name.lookup(self.db.upcast()).strip_prefix("mzn_output_") {
+					writeln!(
+						&mut buf,
+						"output :: {:?} [{}];",
+						section,
+						name.pretty_print(self.db.upcast())
+					)
+					.unwrap();
+				}
+			}
 			writeln!(&mut buf, "{}", MINIZINC_COMPAT).unwrap();
 		}
 		buf
@@ -3,7 +3,7 @@
 
 use std::sync::Arc;
 
-use super::{db::Thir, pretty_print::PrettyPrinter};
+use super::{db::Thir, pretty_print::PrettyPrinter, Model};
 use crate::{
 	db::{CompilerDatabase, Inputs},
 	diagnostics::Diagnostics,
@@ -19,9 +19,15 @@ use crate::{
 pub fn sanity_check_thir(db: &dyn Thir) -> Arc<Diagnostics<Error>> {
 	let initial_thir = db.model_thir();
 	let model = initial_thir.get();
+	sanity_check_model(db, model.as_ref())
+}
 
+/// Get the diagnostics for running the pretty printed version of the given model.
+///
+/// Used to check the output of each THIR transform when debugging the pipeline.
+pub fn sanity_check_model(db: &dyn Thir, model: &Model) -> Arc<Diagnostics<Error>> {
 	// Pretty print with extra info for sanity checking types
-	let mut printer = PrettyPrinter::new(db, model.as_ref());
+	let mut printer = PrettyPrinter::new(db, model);
 	printer.old_compat = false;
 	printer.debug_types = true;
 	let code = printer.pretty_print();
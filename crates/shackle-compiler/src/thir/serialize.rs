@@ -0,0 +1,200 @@
+//! Serialisation of THIR models.
+//!
+//! Interned values (such as identifiers, types and references to HIR nodes) are serialised
+//! using their data, and interned again when deserialised. This requires access to the database,
+//! so models can only be serialised and deserialised inside [`with_database`].
+//!
+//! The origins of THIR nodes refer to the HIR of the input models, so a model must only be
+//! deserialised into a database with the same input files, whose contents have not changed.
+
+use std::{borrow::Cow, cell::Cell, collections::BTreeSet, sync::Mutex};
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{db::Thir, source::Origin};
+use crate::{
+	db::{InternedString, InternedStringData},
+	file::{FileRef, FileRefData},
+	hir::ids::{EntityRef, EntityRefData, ItemRef, ItemRefData, NodeRef},
+	ty::{NewType, NewTypeData, Ty, TyData},
+	utils::maybe_grow_stack,
+};
+
+thread_local! {
+	static DATABASE: Cell<Option<*const (dyn Thir + 'static)>> = const { Cell::new(None) };
+}
+
+const NO_DATABASE: &str = "THIR nodes can only be serialised inside `with_database`";
+
+/// Serialise or deserialise THIR models using the given database while running `f`
+pub fn with_database<R>(db: &(dyn Thir + 'static), f: impl FnOnce() -> R) -> R {
+	struct Restore(Option<*const (dyn Thir + 'static)>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			DATABASE.with(|d| d.set(self.0));
+		}
+	}
+
+	let _restore = Restore(DATABASE.with(|d| d.replace(Some(db))));
+	f()
+}
+
+/// Run `f` using the database given to [`with_database`], if any
+fn database<R>(f: impl FnOnce(&dyn Thir) -> R) -> Option<R> {
+	let db = DATABASE.with(|d| d.get())?;
+	// SAFETY: the pointer is only set while the database is borrowed by `with_database`
+	Some(f(unsafe { &*db }))
+}
+
+/// Serialise and deserialise recursive values, growing the stack if necessary
+pub(crate) mod grow_stack {
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+	use crate::utils::maybe_grow_stack;
+
+	pub fn serialize<T: Serialize, S: Serializer>(
+		value: &T,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		maybe_grow_stack(|| value.serialize(serializer))
+	}
+
+	pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<T, D::Error> {
+		maybe_grow_stack(|| T::deserialize(deserializer))
+	}
+}
+
+macro_rules! impl_interned_serde {
+	($ty:ty, $data:ty, $intern:ident, $lookup:ident) => {
+		impl Serialize for $ty {
+			fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				let data = database(|db| db.$lookup(*self))
+					.ok_or_else(|| <S::Error as ser::Error>::custom(NO_DATABASE))?;
+				maybe_grow_stack(|| data.serialize(serializer))
+			}
+		}
+
+		impl<'de> Deserialize<'de> for $ty {
+			fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				let data = maybe_grow_stack(|| <$data>::deserialize(deserializer))?;
+				database(|db| db.$intern(data))
+					.ok_or_else(|| <D::Error as de::Error>::custom(NO_DATABASE))
+			}
+		}
+	};
+}
+
+impl_interned_serde!(
+	InternedString,
+	InternedStringData,
+	intern_string,
+	lookup_intern_string
+);
+impl_interned_serde!(
+	FileRef,
+	FileRefData,
+	intern_file_ref,
+	lookup_intern_file_ref
+);
+impl_interned_serde!(
+	ItemRef,
+	ItemRefData,
+	intern_item_ref,
+	lookup_intern_item_ref
+);
+impl_interned_serde!(
+	EntityRef,
+	EntityRefData,
+	intern_entity_ref,
+	lookup_intern_entity_ref
+);
+impl_interned_serde!(NewType, NewTypeData, intern_newtype, lookup_intern_newtype);
+impl_interned_serde!(Ty, TyData, intern_ty, lookup_intern_ty);
+
+/// Serialisable version of an [`Origin`]
+#[derive(Serialize, Deserialize)]
+enum SerializedOrigin<'a> {
+	HirNode(NodeRef),
+	Introduced(#[serde(borrow)] Cow<'a, str>),
+}
+
+impl Serialize for Origin {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self {
+			Origin::HirNode(node) => SerializedOrigin::HirNode(*node),
+			Origin::Introduced(name) => SerializedOrigin::Introduced(Cow::Borrowed(name)),
+		}
+		.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Origin {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(match SerializedOrigin::deserialize(deserializer)? {
+			SerializedOrigin::HirNode(node) => Origin::HirNode(node),
+			SerializedOrigin::Introduced(name) => Origin::Introduced(introduced_name(&name)),
+		})
+	}
+}
+
+/// Get the name of an introduced origin as a static string.
+///
+/// These names come from a small fixed set, so each one is only leaked once.
+fn introduced_name(name: &str) -> &'static str {
+	static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+	let mut names = NAMES.lock().unwrap();
+	match names.get(name) {
+		Some(name) => name,
+		None => {
+			let name = Box::leak(name.to_owned().into_boxed_str());
+			names.insert(name);
+			name
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use super::with_database;
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		file::InputFile,
+		thir::{db::Thir, pretty_print::PrettyPrinter, Model},
+	};
+
+	#[test]
+	fn test_serialize_model() {
+		let database = || {
+			let mut db = CompilerDatabase::default();
+			db.set_input_files(Arc::new(vec![InputFile::ModelString(
+				r#"
+				enum E = {A, B, C};
+				array [E] of var 1..3: x;
+				constraint assert(card(E) = 3, "Unexpected enum");
+				solve maximize sum (e in E) (x[e]);
+				"#
+				.to_owned(),
+			)]));
+			db
+		};
+		let db = database();
+		let model = db.final_thir().unwrap();
+		let json = with_database(&db, || serde_json::to_string(&*model)).unwrap();
+		// The new database has not compiled the model
+		let other = database();
+		let loaded: Model = with_database(&other, || serde_json::from_str(&json)).unwrap();
+		assert_eq!(
+			PrettyPrinter::new(&other, &loaded).pretty_print(),
+			PrettyPrinter::new(&db, &model).pretty_print()
+		);
+		expect!["THIR nodes can only be serialised inside `with_database`"]
+			.assert_eq(&serde_json::to_string(&*model).unwrap_err().to_string());
+	}
+}
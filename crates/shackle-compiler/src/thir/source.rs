@@ -6,7 +6,7 @@ use miette::SourceSpan;
 
 use super::db::Thir;
 use crate::{
-	file::SourceFile,
+	file::{ModelRef, SourceFile},
 	hir::ids::{EntityRef, ItemRef, NodeRef},
 };
 
@@ -46,6 +46,22 @@ impl Origin {
 		}
 	}
 
+	/// Get the model containing this origin
+	pub fn model_ref(&self, db: &dyn Thir) -> Option<ModelRef> {
+		match self.node()? {
+			NodeRef::Item(item) => Some(item.model_ref(db.upcast())),
+			NodeRef::Entity(entity) => Some(entity.item(db.upcast()).model_ref(db.upcast())),
+			NodeRef::Model(m) => Some(m),
+		}
+	}
+
+	/// Whether this origin is in one of the input models (rather than an included library)
+	pub fn is_from_input(&self, db: &dyn Thir) -> bool {
+		self.model_ref(db)
+			.map(|m| db.input_models().contains(&m))
+			.unwrap_or(false)
+	}
+
 	/// Get the source file and span of this origin
 	pub fn source_span(&self, db: &dyn Thir) -> (SourceFile, SourceSpan) {
 		match self {
@@ -0,0 +1,193 @@
+//! Statistics about the complexity of a compiled model.
+//!
+//! These are used to track how large models are (and how long they take to compile) across
+//! releases.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde_json::{json, Value};
+
+use super::{
+	db::Thir,
+	transform::PassTiming,
+	traverse::{visit_expression, Visitor},
+	Callable, Expression, ExpressionData, Model,
+};
+use crate::{hir::db::EntityCounts, utils::maybe_grow_stack};
+
+/// Statistics about a model
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelStatistics {
+	/// Item counts of the source model (including the library files it uses)
+	pub source: EntityCounts,
+	/// Number of top-level declarations of each type-inst in the model (before type erasure)
+	pub declarations: BTreeMap<String, u32>,
+	/// Number of top-level constraints calling each predicate in the model (before type erasure)
+	pub constraints: BTreeMap<String, u32>,
+	/// Sizes of the final model
+	pub final_model: FinalModelSizes,
+	/// The time taken by each THIR pass
+	pub passes: Vec<PassTiming>,
+}
+
+/// The sizes of a model after all THIR passes have been run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinalModelSizes {
+	/// Number of functions (after type specialisation)
+	pub functions: u32,
+	/// Number of items
+	pub items: u32,
+	/// Number of expressions
+	pub expressions: u32,
+}
+
+impl ModelStatistics {
+	/// Collect statistics about the given model as lowered into the THIR, and the final model
+	/// produced from it by the THIR passes
+	pub fn new(db: &dyn Thir, model: &Model, final_model: &Model, passes: Vec<PassTiming>) -> Self {
+		let mut declarations = BTreeMap::new();
+		for (_, d) in model.top_level_declarations() {
+			*declarations
+				.entry(d.ty().pretty_print(db.upcast()))
+				.or_default() += 1;
+		}
+		let mut constraints = BTreeMap::new();
+		for (_, c) in model.top_level_constraints() {
+			let name = match &**c.expression() {
+				ExpressionData::Call(call) => match &call.function {
+					Callable::Function(f) => model[*f].name().pretty_print(db),
+					_ => "<other>".to_owned(),
+				},
+				_ => "<other>".to_owned(),
+			};
+			*constraints.entry(name).or_default() += 1;
+		}
+		let mut counter = ExpressionCounter(0);
+		counter.visit_model(final_model);
+		Self {
+			source: (*db.entity_counts()).clone(),
+			declarations,
+			constraints,
+			final_model: FinalModelSizes {
+				functions: final_model.functions_len(),
+				items: final_model.entity_counts().items(),
+				expressions: counter.0,
+			},
+			passes,
+		}
+	}
+
+	/// Convert these statistics into a JSON object
+	pub fn to_json(&self) -> Value {
+		json!({
+			"source": {
+				"declarations": self.source.declarations,
+				"constraints": self.source.constraints,
+				"functions": self.source.functions,
+				"enumerations": self.source.enumerations,
+				"expressions": self.source.expressions,
+			},
+			"declarations": self.declarations,
+			"constraints": self.constraints,
+			"final": {
+				"functions": self.final_model.functions,
+				"items": self.final_model.items,
+				"expressions": self.final_model.expressions,
+			},
+			"passes": self.passes.iter().map(|p| json!({
+				"name": p.name,
+				"seconds": p.duration.as_secs_f64(),
+			})).collect::<Vec<_>>(),
+		})
+	}
+}
+
+impl Display for ModelStatistics {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(
+			f,
+			"Source: {} declarations, {} constraints, {} functions, {} expressions",
+			self.source.declarations,
+			self.source.constraints,
+			self.source.functions,
+			self.source.expressions
+		)?;
+		writeln!(f, "Declarations:")?;
+		for (ty, count) in self.declarations.iter() {
+			writeln!(f, "  {}: {}", ty, count)?;
+		}
+		writeln!(f, "Constraints:")?;
+		for (name, count) in self.constraints.iter() {
+			writeln!(f, "  {}: {}", name, count)?;
+		}
+		writeln!(
+			f,
+			"Final model: {} functions, {} items, {} expressions",
+			self.final_model.functions, self.final_model.items, self.final_model.expressions
+		)?;
+		if !self.passes.is_empty() {
+			writeln!(f, "Passes:")?;
+			for pass in self.passes.iter() {
+				writeln!(f, "  {}: {:.3?}", pass.name, pass.duration)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Counts the expressions in a model
+struct ExpressionCounter(u32);
+
+impl<'a> Visitor<'a> for ExpressionCounter {
+	fn visit_expression(&mut self, model: &'a Model, expression: &'a Expression) {
+		maybe_grow_stack(|| {
+			self.0 += 1;
+			visit_expression(self, model, expression);
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use super::ModelStatistics;
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		file::InputFile,
+		thir::db::Thir,
+	};
+
+	#[test]
+	fn test_model_statistics() {
+		let mut db = CompilerDatabase::default();
+		db.set_ignore_stdlib(true);
+		db.set_input_files(Arc::new(vec![InputFile::ModelString(
+			r#"
+			predicate foo(var int: x);
+			var int: x;
+			var bool: b;
+			int: n = 3;
+			constraint foo(x);
+			constraint b;
+			"#
+			.to_owned(),
+		)]));
+		let model = db.model_thir().get().as_ref().clone();
+		let stats = ModelStatistics::new(&db, &model, &model, Vec::new());
+		expect![[r#"
+    Source: 3 declarations, 2 constraints, 1 functions, 5 expressions
+    Declarations:
+      int: 1
+      var bool: 1
+      var int: 1
+    Constraints:
+      <other>: 1
+      foo: 1
+    Final model: 1 functions, 7 items, 4 expressions
+"#]]
+		.assert_eq(&stats.to_string());
+	}
+}
@@ -0,0 +1,622 @@
+//! Infers bounds for `var int` and `var float` declarations.
+//!
+//! Performs an interval analysis over the model, and uses the results to give domains to
+//! - top-level and `let` declarations which have a definition but no domain
+//! - functions with a `var int` or `var float` return type but no return domain
+//!
+//! Only items from the input models are given domains, although the bounds of library
+//! functions are still used by the analysis. This runs before domains are rewritten into
+//! constraints, so inferred domains are enforced in the same way as user-written ones.
+//!
+//! The analysis understands literals, declarations with domains or definitions, arithmetic
+//! builtins, `sum`, `min`, `max`, `abs`, `bool2int`, conditionals and calls to user-defined
+//! functions. Bounds are only inferred when they are finite, and integer bounds which overflow
+//! are discarded.
+//!
+//! The same analysis is used to warn about integer variables which remain unbounded.
+
+use std::sync::Arc;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+	constants::{IdentifierRegistry, TypeRegistry},
+	diagnostics::{UnboundedVariable, Warning},
+	hir::{FloatLiteral, IntegerLiteral, OptType, VarType},
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{visit_generator, visit_let, Visitor},
+		Callable, DeclarationId, Domain, DomainData, Expression, ExpressionData, FunctionId,
+		FunctionName, Generator, Let, LetItem, LookupCall, Model, ResolvedIdentifier,
+	},
+	utils::maybe_grow_stack,
+	Result,
+};
+
+/// Bounds of a numeric value (or of the elements of an array or set)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bounds {
+	Int(i64, i64),
+	Float(f64, f64),
+}
+
+impl Bounds {
+	fn float(lb: f64, ub: f64) -> Option<Self> {
+		if lb.is_finite() && ub.is_finite() {
+			Some(Bounds::Float(lb, ub))
+		} else {
+			None
+		}
+	}
+
+	/// Apply a monotonic binary operation to every pair of bounds, and take the extremes
+	fn corners(
+		self,
+		other: Self,
+		int_op: impl Fn(i64, i64) -> Option<i64>,
+		float_op: impl Fn(f64, f64) -> f64,
+	) -> Option<Self> {
+		match (self, other) {
+			(Bounds::Int(a, b), Bounds::Int(c, d)) => {
+				let values = [int_op(a, c)?, int_op(a, d)?, int_op(b, c)?, int_op(b, d)?];
+				Some(Bounds::Int(
+					*values.iter().min().unwrap(),
+					*values.iter().max().unwrap(),
+				))
+			}
+			(Bounds::Float(a, b), Bounds::Float(c, d)) => {
+				let values = [
+					float_op(a, c),
+					float_op(a, d),
+					float_op(b, c),
+					float_op(b, d),
+				];
+				Bounds::float(
+					values.iter().copied().fold(f64::INFINITY, f64::min),
+					values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+				)
+			}
+			_ => None,
+		}
+	}
+
+	fn add(self, other: Self) -> Option<Self> {
+		self.corners(other, i64::checked_add, |a, b| a + b)
+	}
+
+	fn sub(self, other: Self) -> Option<Self> {
+		self.corners(other, i64::checked_sub, |a, b| a - b)
+	}
+
+	fn mul(self, other: Self) -> Option<Self> {
+		self.corners(other, i64::checked_mul, |a, b| a * b)
+	}
+
+	fn min(self, other: Self) -> Option<Self> {
+		self.corners(other, |a, b| Some(a.min(b)), f64::min)
+	}
+
+	fn max(self, other: Self) -> Option<Self> {
+		self.corners(other, |a, b| Some(a.max(b)), f64::max)
+	}
+
+	fn neg(self) -> Option<Self> {
+		match self {
+			Bounds::Int(lb, ub) => Some(Bounds::Int(ub.checked_neg()?, lb.checked_neg()?)),
+			Bounds::Float(lb, ub) => Some(Bounds::Float(-ub, -lb)),
+		}
+	}
+
+	fn abs(self) -> Option<Self> {
+		match self {
+			Bounds::Int(lb, _) if lb >= 0 => Some(self),
+			Bounds::Int(_, ub) if ub <= 0 => self.neg(),
+			Bounds::Int(lb, ub) => Some(Bounds::Int(0, lb.checked_neg()?.max(ub))),
+			Bounds::Float(lb, _) if lb >= 0.0 => Some(self),
+			Bounds::Float(_, ub) if ub <= 0.0 => self.neg(),
+			Bounds::Float(lb, ub) => Some(Bounds::Float(0.0, (-lb).max(ub))),
+		}
+	}
+
+	fn union(self, other: Self) -> Option<Self> {
+		match (self, other) {
+			(Bounds::Int(a, b), Bounds::Int(c, d)) => Some(Bounds::Int(a.min(c), b.max(d))),
+			(Bounds::Float(a, b), Bounds::Float(c, d)) => Some(Bounds::Float(a.min(c), b.max(d))),
+			_ => None,
+		}
+	}
+
+	fn intersect(self, other: Self) -> Option<Self> {
+		match (self, other) {
+			(Bounds::Int(a, b), Bounds::Int(c, d)) => Some(Bounds::Int(a.max(c), b.min(d))),
+			(Bounds::Float(a, b), Bounds::Float(c, d)) => Some(Bounds::Float(a.max(c), b.min(d))),
+			_ => None,
+		}
+	}
+
+	fn to_float(self) -> Self {
+		match self {
+			Bounds::Int(lb, ub) => Bounds::Float(lb as f64, ub as f64),
+			_ => self,
+		}
+	}
+
+	/// Bounds of the sum of between `min` and `max` values within these bounds
+	fn repeat(self, (min, max): (i64, i64)) -> Option<Self> {
+		let (lb, ub) = match self {
+			Bounds::Int(lb, ub) => (lb, ub),
+			Bounds::Float(lb, ub) => {
+				let (min, max) = (min as f64, max as f64);
+				return Bounds::float((lb * min).min(lb * max), (ub * min).max(ub * max));
+			}
+		};
+		Some(Bounds::Int(
+			lb.checked_mul(min)?.min(lb.checked_mul(max)?),
+			ub.checked_mul(min)?.max(ub.checked_mul(max)?),
+		))
+	}
+}
+
+/// Collects the comprehension generator and `let` declarations of a model
+#[derive(Default)]
+struct Locals<'a> {
+	generators: FxHashMap<DeclarationId, &'a Expression>,
+	lets: Vec<DeclarationId>,
+}
+
+impl<'a> Visitor<'a> for Locals<'a> {
+	fn visit_generator(&mut self, model: &'a Model, generator: &'a Generator) {
+		if let Generator::Iterator {
+			declarations,
+			collection,
+			..
+		} = generator
+		{
+			for d in declarations {
+				self.generators.insert(*d, collection);
+			}
+		}
+		visit_generator(self, model, generator);
+	}
+
+	fn visit_let(&mut self, model: &'a Model, l: &'a Let) {
+		for item in l.items.iter() {
+			if let LetItem::Declaration(d) = item {
+				self.lets.push(*d);
+			}
+		}
+		visit_let(self, model, l);
+	}
+}
+
+/// Interval analysis of a model
+struct BoundsAnalysis<'a> {
+	model: &'a Model,
+	ids: Arc<IdentifierRegistry>,
+	types: Arc<TypeRegistry>,
+	generators: FxHashMap<DeclarationId, &'a Expression>,
+	declarations: FxHashMap<DeclarationId, Option<Bounds>>,
+	functions: FxHashMap<FunctionId, Option<Bounds>>,
+}
+
+impl<'a> BoundsAnalysis<'a> {
+	/// Create a new analysis, also returning the `let` declarations of the model
+	fn new(db: &dyn Thir, model: &'a Model) -> (Self, Vec<DeclarationId>) {
+		let mut locals = Locals::default();
+		locals.visit_model(model);
+		let analysis = Self {
+			model,
+			ids: db.identifier_registry(),
+			types: db.type_registry(),
+			generators: locals.generators,
+			declarations: FxHashMap::default(),
+			functions: FxHashMap::default(),
+		};
+		(analysis, locals.lets)
+	}
+
+	/// Get the bounds of a declaration
+	fn declaration(&mut self, d: DeclarationId) -> Option<Bounds> {
+		if let Some(b) = self.declarations.get(&d) {
+			return *b;
+		}
+		// Cyclic definitions are unbounded
+		self.declarations.insert(d, None);
+		let model = self.model;
+		let bounds = if let Some(collection) = self.generators.get(&d).copied() {
+			self.expression(collection)
+		} else {
+			let domain = self.domain(model[d].domain());
+			let definition = model[d].definition().and_then(|e| self.expression(e));
+			match (domain, definition) {
+				(Some(a), Some(b)) => a.intersect(b),
+				(a, b) => a.or(b),
+			}
+		};
+		self.declarations.insert(d, bounds);
+		bounds
+	}
+
+	/// Get the bounds of the return value of a function
+	fn function(&mut self, f: FunctionId) -> Option<Bounds> {
+		if let Some(b) = self.functions.get(&f) {
+			return *b;
+		}
+		// Recursive functions are unbounded
+		self.functions.insert(f, None);
+		let model = self.model;
+		let bounds = if model[f].is_polymorphic() {
+			None
+		} else {
+			self.domain(model[f].domain())
+				.or_else(|| model[f].body().and_then(|e| self.expression(e)))
+		};
+		self.functions.insert(f, bounds);
+		bounds
+	}
+
+	/// Get the bounds given by a domain
+	fn domain(&mut self, domain: &'a Domain) -> Option<Bounds> {
+		match &**domain {
+			DomainData::Bounded(e) => self.expression(e),
+			DomainData::Array(_, elem) | DomainData::Set(elem) => self.domain(elem),
+			_ => None,
+		}
+	}
+
+	/// Get the bounds of an expression
+	///
+	/// For arrays and sets, these are the bounds of the elements.
+	fn expression(&mut self, expression: &'a Expression) -> Option<Bounds> {
+		maybe_grow_stack(|| match &**expression {
+			ExpressionData::IntegerLiteral(i) => Some(Bounds::Int(i.0, i.0)),
+			ExpressionData::FloatLiteral(f) => Bounds::float(f.value(), f.value()),
+			ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => self.declaration(*d),
+			ExpressionData::ArrayLiteral(al) => self.union(al.iter()),
+			ExpressionData::SetLiteral(sl) => self.union(sl.iter()),
+			ExpressionData::ArrayComprehension(c) => self.expression(&c.template),
+			ExpressionData::SetComprehension(c) => self.expression(&c.template),
+			ExpressionData::ArrayAccess(aa) => self.expression(&aa.collection),
+			ExpressionData::IfThenElse(ite) => self.union(
+				ite.branches
+					.iter()
+					.map(|b| &b.result)
+					.chain([&*ite.else_result]),
+			),
+			ExpressionData::Let(l) => self.expression(&l.in_expression),
+			ExpressionData::Call(c) => match &c.function {
+				Callable::Function(f) => {
+					let float = expression.ty() == self.types.par_float
+						|| expression.ty() == self.types.var_float;
+					self.call(*f, &c.arguments, float)
+				}
+				_ => None,
+			},
+			_ => None,
+		})
+	}
+
+	fn union(&mut self, expressions: impl IntoIterator<Item = &'a Expression>) -> Option<Bounds> {
+		let mut result: Option<Bounds> = None;
+		for e in expressions {
+			let b = self.expression(e)?;
+			result = Some(match result {
+				Some(r) => r.union(b)?,
+				None => b,
+			});
+		}
+		result
+	}
+
+	/// Get the bounds of an argument, converting integer bounds to float bounds if needed
+	fn argument(&mut self, expression: &'a Expression, float: bool) -> Option<Bounds> {
+		let bounds = self.expression(expression)?;
+		Some(if float { bounds.to_float() } else { bounds })
+	}
+
+	/// Get the bounds of a call to the given function
+	///
+	/// If the call has a float type, then integer arguments are coerced to floats.
+	fn call(&mut self, f: FunctionId, arguments: &'a [Expression], float: bool) -> Option<Bounds> {
+		let ids = self.ids.clone();
+		let name = match self.model[f].name() {
+			FunctionName::Named(name) => name,
+			_ => return self.function(f),
+		};
+		match arguments {
+			[a, b] if name == ids.plus => self.argument(a, float)?.add(self.argument(b, float)?),
+			[a, b] if name == ids.minus => self.argument(a, float)?.sub(self.argument(b, float)?),
+			[a, b] if name == ids.times => self.argument(a, float)?.mul(self.argument(b, float)?),
+			[a, b] if name == ids.min => self.argument(a, float)?.min(self.argument(b, float)?),
+			[a, b] if name == ids.max => self.argument(a, float)?.max(self.argument(b, float)?),
+			[a, b] if name == ids.dot_dot => match (self.expression(a)?, self.expression(b)?) {
+				(Bounds::Int(lb, _), Bounds::Int(_, ub)) if lb <= ub => Some(Bounds::Int(lb, ub)),
+				(Bounds::Float(lb, _), Bounds::Float(_, ub)) if lb <= ub => {
+					Some(Bounds::Float(lb, ub))
+				}
+				_ => None,
+			},
+			[a] if name == ids.plus => self.argument(a, float),
+			[a] if name == ids.minus => self.argument(a, float)?.neg(),
+			[a] if name == ids.abs => self.argument(a, float)?.abs(),
+			[a] if name == ids.min || name == ids.max => self.argument(a, float),
+			[a] if name == ids.int2float => Some(self.expression(a)?.to_float()),
+			[_] if name == ids.bool2int => Some(Bounds::Int(0, 1)),
+			[a] if name == ids.sum => {
+				let bounds = self.argument(a, float)?;
+				bounds.repeat(self.size(a)?)
+			}
+			_ => self.function(f),
+		}
+	}
+
+	/// Get the minimum and maximum number of elements in an array or set
+	fn size(&mut self, expression: &'a Expression) -> Option<(i64, i64)> {
+		let model = self.model;
+		match &**expression {
+			ExpressionData::ArrayLiteral(al) => Some((al.len() as i64, al.len() as i64)),
+			ExpressionData::SetLiteral(sl) => Some(((sl.len() as i64).min(1), sl.len() as i64)),
+			ExpressionData::ArrayComprehension(c) => {
+				let mut size = (1_i64, 1_i64);
+				for generator in c.generators.iter() {
+					let (count, where_clause) = match generator {
+						Generator::Iterator {
+							declarations,
+							collection,
+							where_clause,
+						} => {
+							let (min, max) = self.size(collection)?;
+							let n = declarations.len() as u32;
+							((min.checked_pow(n)?, max.checked_pow(n)?), where_clause)
+						}
+						Generator::Assignment { where_clause, .. } => ((1, 1), where_clause),
+					};
+					size = (size.0.checked_mul(count.0)?, size.1.checked_mul(count.1)?);
+					if where_clause.is_some() {
+						size.0 = 0;
+					}
+				}
+				Some(size)
+			}
+			ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => {
+				if let DomainData::Array(dims, _) = &**model[*d].domain() {
+					let dims = match &***dims {
+						DomainData::Bounded(e) => Some(vec![&**e]),
+						DomainData::Tuple(fields) => fields
+							.iter()
+							.map(|f| match &**f {
+								DomainData::Bounded(e) => Some(&**e),
+								_ => None,
+							})
+							.collect(),
+						_ => None,
+					};
+					if let Some(dims) = dims {
+						let mut size = (1_i64, 1_i64);
+						for dim in dims {
+							let count = self.size(dim)?;
+							size = (size.0.checked_mul(count.0)?, size.1.checked_mul(count.1)?);
+						}
+						return Some(size);
+					}
+				}
+				self.size(model[*d].definition()?)
+			}
+			ExpressionData::Call(c) => match (&c.function, &c.arguments[..]) {
+				(Callable::Function(f), [a, b])
+					if model[*f].name() == FunctionName::new(self.ids.dot_dot) =>
+				{
+					match (self.expression(a)?, self.expression(b)?) {
+						(Bounds::Int(a_lb, a_ub), Bounds::Int(b_lb, b_ub)) => Some((
+							b_lb.checked_sub(a_ub)?.checked_add(1)?.max(0),
+							b_ub.checked_sub(a_lb)?.checked_add(1)?.max(0),
+						)),
+						_ => None,
+					}
+				}
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+}
+
+/// Create a domain from the given bounds
+fn make_domain(db: &dyn Thir, model: &Model, origin: Origin, bounds: Bounds) -> Option<Domain> {
+	let ids = db.identifier_registry();
+	let (lb, ub) = match bounds {
+		Bounds::Int(lb, ub) => (
+			Expression::new(db, model, origin, IntegerLiteral(lb)),
+			Expression::new(db, model, origin, IntegerLiteral(ub)),
+		),
+		Bounds::Float(lb, ub) => (
+			Expression::new(db, model, origin, FloatLiteral::new(lb)),
+			Expression::new(db, model, origin, FloatLiteral::new(ub)),
+		),
+	};
+	model
+		.lookup_function(db, ids.dot_dot.into(), &[lb.ty(), ub.ty()])
+		.ok()?;
+	let range = Expression::new(
+		db,
+		model,
+		origin,
+		LookupCall {
+			function: ids.dot_dot.into(),
+			arguments: vec![lb, ub],
+		},
+	);
+	Some(Domain::bounded(
+		db,
+		origin,
+		VarType::Var,
+		OptType::NonOpt,
+		range,
+	))
+}
+
+/// Give domains to `var int` and `var float` declarations and functions in the input models
+/// using their inferred bounds
+pub fn infer_bounds(db: &dyn Thir, mut model: Model) -> Result<Model> {
+	log::info!("Inferring variable bounds");
+	let types = db.type_registry();
+	let is_numeric = |ty| ty == types.var_int || ty == types.var_float;
+	let (declarations, functions) = {
+		let (mut analysis, lets) = BoundsAnalysis::new(db, &model);
+		let declarations = model
+			.top_level_declarations()
+			.map(|(d, _)| d)
+			.chain(lets)
+			.filter(|d| {
+				is_numeric(model[*d].ty())
+					&& model[*d].origin().is_from_input(db)
+					&& model[*d].definition().is_some()
+					&& matches!(&**model[*d].domain(), DomainData::Unbounded)
+			})
+			.filter_map(|d| Some((d, analysis.declaration(d)?)))
+			.collect::<Vec<_>>();
+		let functions = model
+			.top_level_functions()
+			.filter(|(_, f)| {
+				is_numeric(f.return_type())
+					&& f.origin().is_from_input(db)
+					&& f.body().is_some()
+					&& matches!(&**f.domain(), DomainData::Unbounded)
+			})
+			.filter_map(|(f, _)| Some((f, analysis.function(f)?)))
+			.collect::<Vec<_>>();
+		(declarations, functions)
+	};
+	for (d, bounds) in declarations {
+		if let Some(domain) = make_domain(db, &model, model[d].domain().origin(), bounds) {
+			model[d].set_domain(domain);
+		}
+	}
+	for (f, bounds) in functions {
+		if let Some(domain) = make_domain(db, &model, model[f].domain().origin(), bounds) {
+			model[f].set_domain(domain);
+		}
+	}
+	Ok(model)
+}
+
+/// Get warnings for integer variables in the input models which have no domain, and for which
+/// bounds could not be inferred
+pub fn unbounded_variable_warnings(db: &dyn Thir) -> Arc<Vec<Warning>> {
+	let types = db.type_registry();
+	let intermediate = db.model_thir();
+	let value = intermediate.get();
+	let model = value.as_ref();
+	let (mut analysis, lets) = BoundsAnalysis::new(db, model);
+	let mut seen = FxHashSet::default();
+	let mut warnings = Vec::new();
+	for d in model.top_level_declarations().map(|(d, _)| d).chain(lets) {
+		let declaration = &model[d];
+		if !seen.insert(d) || !declaration.origin().is_from_input(db) {
+			continue;
+		}
+		let unbounded = if declaration.ty() == types.var_int {
+			analysis.declaration(d).is_none()
+		} else if declaration.ty().elem_ty(db.upcast()) == Some(types.var_int) {
+			declaration.definition().is_none() && analysis.domain(declaration.domain()).is_none()
+		} else {
+			false
+		};
+		if unbounded {
+			let (src, span) = declaration.origin().source_span(db);
+			warnings.push(
+				UnboundedVariable {
+					name: declaration
+						.name()
+						.map(|n| n.pretty_print(db.upcast()))
+						.unwrap_or_else(|| "<unnamed>".to_owned()),
+					src,
+					span,
+				}
+				.into(),
+			);
+		}
+	}
+	Arc::new(warnings)
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+
+	use super::infer_bounds;
+	use crate::{
+		db::{CompilerDatabase, Inputs},
+		diagnostics::Warning,
+		file::InputFile,
+		thir::{db::Thir, transform::test::check},
+	};
+
+	#[test]
+	fn test_infer_bounds() {
+		check(
+			infer_bounds,
+			r#"
+				function var int: g(var int: a) = bool2int(a > 2) + 1;
+				var 1..3: x;
+				var 0..5: y;
+				var int: z = x + 2 * y;
+				var int: w = sum (i in 1..3) (i * x);
+				var float: f = x + 0.5;
+				var int: v = let { var int: t = abs(x - y); } in t + g(x);
+				var int: u;
+				var int: r = z * u;
+			"#,
+			expect![[r#"
+    function var '..'(1, 2): g(var int: a) = '+'(bool2int('>'(a, 2)), 1);
+    var '..'(1, 3): x;
+    var '..'(0, 5): y;
+    var '..'(1, 13): z = '+'(x, '*'(2, y));
+    var '..'(3, 27): w = sum(['*'(i, x) | i in '..'(1, 3)]);
+    var '..'(1.5, 3.5): f = '+'(x, 0.5);
+    var '..'(1, 6): v = let {
+      var '..'(0, 4): t = abs('-'(x, y));
+    } in '+'(t, g(x));
+    var int: u;
+    var int: r = '*'(z, u);
+"#]],
+		)
+	}
+
+	#[test]
+	fn test_unbounded_variable_warnings() {
+		let mut db = CompilerDatabase::default();
+		db.set_input_files(Arc::new(vec![InputFile::ModelString(
+			r#"
+				var 1..3: x;
+				var int: y;
+				var int: z = x + 1;
+				var int: w = x + y;
+				array [1..3] of var int: a;
+				array [1..3] of var 0..1: b;
+				function var int: f(var int: p) = let { var int: q; } in p + q;
+			"#
+			.to_owned(),
+		)]));
+		let names = db
+			.unbounded_variable_warnings()
+			.iter()
+			.map(|w| match w {
+				Warning::UnboundedVariable(w) => w.name.clone(),
+				_ => unreachable!(),
+			})
+			.collect::<Vec<_>>();
+		expect![[r#"
+    [
+        "y",
+        "w",
+        "a",
+        "q",
+    ]
+"#]]
+		.assert_debug_eq(&names);
+	}
+}
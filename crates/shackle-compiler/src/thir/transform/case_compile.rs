@@ -0,0 +1,788 @@
+//! Compile `case` expressions into decision trees.
+//!
+//! Nested tuple, record, enum constructor and literal patterns are compiled into nested
+//! if-then-else expressions which test a single part of the scrutinee at a time. Each test is
+//! shared by all of the branches which depend on it, and parts of the scrutinee are accessed
+//! using tuple/record access and enum destructors inside the branch where the test succeeded,
+//! so the result is valid for `var` scrutinees too. The result of an arm taken by more than one
+//! leaf of the tree is bound in a `let` so that it is only generated once.
+//!
+//! Must be run before record, enum and option type erasure.
+
+use crate::{
+	hir::{BooleanLiteral, Identifier, IntegerLiteral},
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{fold_expression, Folder, ReplacementMap},
+		Branch, Call, Callable, Case, Declaration, DeclarationId, Domain, DomainData, DummyValue,
+		EnumMemberId, Expression, ExpressionData, IfThenElse, Item, Let, LetItem, LookupCall,
+		Marker, Model, Pattern, PatternData, RecordAccess, ResolvedIdentifier, TupleAccess,
+	},
+	ty::Ty,
+	utils::maybe_grow_stack,
+	Result,
+};
+
+use super::top_down_type::add_coercion;
+
+/// A pattern in the pattern matrix
+#[derive(Clone)]
+enum Pat<'a, T: Marker> {
+	/// Matches anything
+	Wildcard,
+	/// Tuple of patterns
+	Tuple(Vec<Pat<'a, T>>),
+	/// Record of patterns
+	Record(Vec<(Identifier, Pat<'a, T>)>),
+	/// Enum constructor (or atom if there are no arguments)
+	Constructor(EnumMemberId<T>, Vec<Pat<'a, T>>),
+	/// Literal value
+	Literal(&'a Expression<T>),
+}
+
+impl<'a, T: Marker> Pat<'a, T> {
+	/// Create a pattern, or `None` if the pattern cannot be compiled
+	fn new(pattern: &'a Pattern<T>) -> Option<Self> {
+		Some(match &**pattern {
+			PatternData::Anonymous(_) => Pat::Wildcard,
+			PatternData::Tuple(fs) => Pat::Tuple(fs.iter().map(Pat::new).collect::<Option<_>>()?),
+			PatternData::Record(fs) => Pat::Record(
+				fs.iter()
+					.map(|(i, p)| Some((*i, Pat::new(p)?)))
+					.collect::<Option<_>>()?,
+			),
+			PatternData::EnumConstructor { member, args } => {
+				Pat::Constructor(*member, args.iter().map(Pat::new).collect::<Option<_>>()?)
+			}
+			PatternData::AnnotationConstructor { .. } => return None,
+			PatternData::Expression(e) => Pat::from_expression(e)?,
+		})
+	}
+
+	/// Create a pattern from a literal expression
+	fn from_expression(expression: &'a Expression<T>) -> Option<Self> {
+		Some(match &**expression {
+			ExpressionData::TupleLiteral(tl) => {
+				Pat::Tuple(tl.iter().map(Pat::from_expression).collect::<Option<_>>()?)
+			}
+			ExpressionData::RecordLiteral(rl) => Pat::Record(
+				rl.iter()
+					.map(|(i, e)| Some((*i, Pat::from_expression(e)?)))
+					.collect::<Option<_>>()?,
+			),
+			ExpressionData::Call(Call {
+				function: Callable::EnumConstructor(member),
+				arguments,
+			}) => Pat::Constructor(
+				*member,
+				arguments
+					.iter()
+					.map(Pat::from_expression)
+					.collect::<Option<_>>()?,
+			),
+			ExpressionData::Identifier(ResolvedIdentifier::EnumerationMember(member)) => {
+				Pat::Constructor(*member, Vec::new())
+			}
+			ExpressionData::Identifier(ResolvedIdentifier::Annotation(_))
+			| ExpressionData::Call(Call {
+				function: Callable::Annotation(_),
+				..
+			}) => return None,
+			_ => Pat::Literal(expression),
+		})
+	}
+}
+
+/// The value tested by a node of the decision tree
+enum Head<'a, T: Marker> {
+	Constructor(EnumMemberId<T>),
+	Literal(&'a Expression<T>),
+}
+
+impl<'a, T: Marker> Head<'a, T> {
+	/// Get the head of the pattern if it is tested
+	fn of(pattern: &Pat<'a, T>) -> Option<Self> {
+		match pattern {
+			Pat::Constructor(member, _) => Some(Head::Constructor(*member)),
+			Pat::Literal(e) => Some(Head::Literal(e)),
+			_ => None,
+		}
+	}
+
+	/// Whether the pattern matches this head, returning the sub-patterns if it does
+	fn matches(&self, pattern: &Pat<'a, T>, arity: usize) -> Option<Vec<Pat<'a, T>>> {
+		match (self, pattern) {
+			(_, Pat::Wildcard) => Some(vec![Pat::Wildcard; arity]),
+			(Head::Constructor(a), Pat::Constructor(b, args)) if a == b => Some(args.clone()),
+			(Head::Literal(a), Pat::Literal(b)) if same_literal(a, b) => Some(Vec::new()),
+			_ => None,
+		}
+	}
+
+	fn same(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Head::Constructor(a), Head::Constructor(b)) => a == b,
+			(Head::Literal(a), Head::Literal(b)) => same_literal(a, b),
+			_ => false,
+		}
+	}
+}
+
+/// Whether two literal patterns match the same value
+fn same_literal<T: Marker>(a: &Expression<T>, b: &Expression<T>) -> bool {
+	match (&**a, &**b) {
+		(ExpressionData::Absent, ExpressionData::Absent) => true,
+		(ExpressionData::BooleanLiteral(a), ExpressionData::BooleanLiteral(b)) => a == b,
+		(ExpressionData::IntegerLiteral(a), ExpressionData::IntegerLiteral(b)) => a == b,
+		(ExpressionData::FloatLiteral(a), ExpressionData::FloatLiteral(b)) => a == b,
+		(ExpressionData::StringLiteral(a), ExpressionData::StringLiteral(b)) => a == b,
+		_ => false,
+	}
+}
+
+/// A row of the pattern matrix
+#[derive(Clone)]
+struct Row<'a, T: Marker> {
+	patterns: Vec<Pat<'a, T>>,
+	branch: usize,
+}
+
+/// A node of the decision tree
+enum Decision<'a, T: Marker> {
+	/// The arm with the given index is taken
+	Leaf(usize),
+	/// No arm matches (unreachable since matching is exhaustive)
+	Fail,
+	/// Split the tuple in the given column into its fields
+	Tuple {
+		column: usize,
+		arity: usize,
+		next: Box<Decision<'a, T>>,
+	},
+	/// Split the record in the given column into the given fields
+	Record {
+		column: usize,
+		fields: Vec<Identifier>,
+		next: Box<Decision<'a, T>>,
+	},
+	/// Test the value in the given column against each head in turn
+	Switch {
+		column: usize,
+		cases: Vec<SwitchCase<'a, T>>,
+		/// The decision if no test succeeds, or `None` if the last case always matches
+		default: Option<Box<Decision<'a, T>>>,
+	},
+}
+
+/// A case of a [`Decision::Switch`]
+struct SwitchCase<'a, T: Marker> {
+	head: Head<'a, T>,
+	arity: usize,
+	/// Whether the arguments of the constructor are tested
+	destructure: bool,
+	next: Decision<'a, T>,
+}
+
+impl<'a, T: Marker> Decision<'a, T> {
+	/// Build the decision tree for the pattern matrix
+	fn new(model: &'a Model<T>, rows: Vec<Row<'a, T>>) -> Self {
+		maybe_grow_stack(|| {
+			let first = match rows.first() {
+				Some(row) => row,
+				None => return Decision::Fail,
+			};
+			let column = match first
+				.patterns
+				.iter()
+				.position(|p| !matches!(p, Pat::Wildcard))
+			{
+				Some(column) => column,
+				None => return Decision::Leaf(first.branch),
+			};
+			match &first.patterns[column] {
+				Pat::Tuple(fs) => {
+					let arity = fs.len();
+					let rows = expand(rows, column, arity, |p| match p {
+						Pat::Tuple(fs) => Some(fs),
+						_ => unreachable!("Expected tuple pattern"),
+					});
+					Decision::Tuple {
+						column,
+						arity,
+						next: Box::new(Decision::new(model, rows)),
+					}
+				}
+				Pat::Record(fs) => {
+					let fields = fs.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+					let rows = expand(rows, column, fields.len(), |p| match p {
+						Pat::Record(mut fs) => Some(
+							fields
+								.iter()
+								.map(|n| {
+									fs.iter()
+										.position(|(i, _)| i == n)
+										.map(|idx| fs.swap_remove(idx).1)
+										.unwrap_or(Pat::Wildcard)
+								})
+								.collect(),
+						),
+						_ => unreachable!("Expected record pattern"),
+					});
+					Decision::Record {
+						column,
+						fields,
+						next: Box::new(Decision::new(model, rows)),
+					}
+				}
+				_ => {
+					// Collect the distinct values tested in this column
+					let mut heads: Vec<Head<'a, T>> = Vec::new();
+					for row in rows.iter() {
+						if let Some(head) = Head::of(&row.patterns[column]) {
+							if !heads.iter().any(|h| h.same(&head)) {
+								heads.push(head);
+							}
+						}
+					}
+					let complete = match heads.first() {
+						Some(Head::Constructor(m)) => {
+							model[m.enumeration_id()]
+								.definition()
+								.map(|d| d.len())
+								.unwrap_or(usize::MAX) == heads.len()
+						}
+						Some(Head::Literal(e)) => {
+							matches!(&***e, ExpressionData::BooleanLiteral(_)) && heads.len() == 2
+						}
+						None => false,
+					};
+					let mut cases = Vec::with_capacity(heads.len());
+					for head in heads {
+						let arity = match head {
+							Head::Constructor(m) => {
+								model[m].parameters.as_ref().map(|ps| ps.len()).unwrap_or(0)
+							}
+							Head::Literal(_) => 0,
+						};
+						// Only destructure if the arguments need to be tested
+						let destructure = rows.iter().any(|row| {
+							head.matches(&row.patterns[column], arity)
+								.map(|sub| sub.iter().any(|p| !matches!(p, Pat::Wildcard)))
+								.unwrap_or(false)
+						});
+						let rows = if destructure {
+							expand(rows.clone(), column, arity, |p| head.matches(&p, arity))
+						} else {
+							expand(rows.clone(), column, 0, |p| {
+								head.matches(&p, arity).map(|_| Vec::new())
+							})
+						};
+						cases.push(SwitchCase {
+							next: Decision::new(model, rows),
+							head,
+							arity,
+							destructure,
+						});
+					}
+					let default = if complete {
+						None
+					} else {
+						let rows = expand(rows, column, 0, |_| None);
+						Some(Box::new(Decision::new(model, rows)))
+					};
+					Decision::Switch {
+						column,
+						cases,
+						default,
+					}
+				}
+			}
+		})
+	}
+
+	/// Count the number of leaves which take each arm
+	fn count_leaves(&self, counts: &mut [usize]) {
+		match self {
+			Decision::Leaf(arm) => counts[*arm] += 1,
+			Decision::Fail => (),
+			Decision::Tuple { next, .. } | Decision::Record { next, .. } => {
+				next.count_leaves(counts)
+			}
+			Decision::Switch { cases, default, .. } => {
+				for case in cases.iter() {
+					case.next.count_leaves(counts);
+				}
+				if let Some(d) = default {
+					d.count_leaves(counts);
+				}
+			}
+		}
+	}
+}
+
+/// Replace the given column of each row with the sub-patterns given by `f`, removing the rows
+/// for which `f` returns `None`
+fn expand<'a, T: Marker>(
+	rows: Vec<Row<'a, T>>,
+	column: usize,
+	arity: usize,
+	f: impl Fn(Pat<'a, T>) -> Option<Vec<Pat<'a, T>>>,
+) -> Vec<Row<'a, T>> {
+	rows.into_iter()
+		.filter_map(|mut row| {
+			let pattern = row.patterns.remove(column);
+			let sub = match pattern {
+				Pat::Wildcard => vec![Pat::Wildcard; arity],
+				p => f(p)?,
+			};
+			row.patterns.splice(column..column, sub);
+			Some(row)
+		})
+		.collect()
+}
+
+/// Replace the given column of the occurrences with the new occurrences
+fn splice<T: Marker>(
+	occurrences: &[Expression<T>],
+	column: usize,
+	new: Vec<Expression<T>>,
+) -> Vec<Expression<T>> {
+	occurrences[..column]
+		.iter()
+		.cloned()
+		.chain(new)
+		.chain(occurrences[column + 1..].iter().cloned())
+		.collect()
+}
+
+/// Whether the expression is cheap enough to duplicate in multiple leaves
+fn is_simple<T: Marker>(expression: &Expression<T>) -> bool {
+	matches!(
+		&**expression,
+		ExpressionData::Absent
+			| ExpressionData::BooleanLiteral(_)
+			| ExpressionData::IntegerLiteral(_)
+			| ExpressionData::FloatLiteral(_)
+			| ExpressionData::StringLiteral(_)
+			| ExpressionData::Infinity
+			| ExpressionData::Identifier(_)
+	)
+}
+
+/// The results of the arms of a `case` expression
+struct Arms<T: Marker> {
+	/// The type of the `case` expression
+	ty: Ty,
+	/// The result of each arm taken by the decision tree
+	results: Vec<Option<Expression<T>>>,
+}
+
+struct CaseCompiler<Dst: Marker, Src: Marker = ()> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst, Src>,
+}
+
+impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for CaseCompiler<Dst, Src> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst, Src> {
+		&mut self.replacement_map
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		expression: &Expression<Src>,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			if let ExpressionData::Case(c) = &**expression {
+				if let Some(mut e) = self.compile_case(db, model, c, expression.ty()) {
+					e.annotations_mut().extend(
+						expression
+							.annotations()
+							.iter()
+							.map(|ann| self.fold_expression(db, model, ann)),
+					);
+					return e;
+				}
+			}
+			fold_expression(self, db, model, expression)
+		})
+	}
+}
+
+impl<Dst: Marker, Src: Marker> CaseCompiler<Dst, Src> {
+	fn compile_case(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		c: &Case<Src>,
+		ty: Ty,
+	) -> Option<Expression<Dst>> {
+		let rows = c
+			.branches
+			.iter()
+			.enumerate()
+			.map(|(i, b)| {
+				Some(Row {
+					patterns: vec![Pat::new(&b.pattern)?],
+					branch: i,
+				})
+			})
+			.collect::<Option<Vec<_>>>()?;
+		let decision = Decision::new(model, rows);
+		let mut counts = vec![0; c.branches.len()];
+		decision.count_leaves(&mut counts);
+
+		let origin = c.scrutinee.origin();
+		let mut items = Vec::new();
+		let scrutinee = self.fold_expression(db, model, &c.scrutinee);
+		let occurrence = if matches!(&*scrutinee, ExpressionData::Identifier(_)) {
+			scrutinee
+		} else {
+			let (decl, occurrence) = self.introduce(db, scrutinee);
+			items.push(LetItem::Declaration(decl));
+			occurrence
+		};
+		// Bind arm bodies taken by more than one leaf so they are only generated once
+		let results = c
+			.branches
+			.iter()
+			.zip(counts)
+			.map(|(b, count)| {
+				if count == 0 {
+					return None;
+				}
+				let result = self.fold_expression(db, model, &b.result);
+				if count > 1 && !is_simple(&result) {
+					let (decl, occurrence) = self.introduce(db, result);
+					items.push(LetItem::Declaration(decl));
+					Some(occurrence)
+				} else {
+					Some(result)
+				}
+			})
+			.collect::<Vec<_>>();
+		let arms = Arms { ty, results };
+		let tree = self.compile(db, model, origin, &decision, vec![occurrence], &arms);
+		let compiled = if items.is_empty() {
+			tree
+		} else {
+			Expression::new(
+				db,
+				&self.model,
+				origin,
+				Let {
+					items,
+					in_expression: Box::new(tree),
+				},
+			)
+		};
+		Some(add_coercion(db, &mut self.model, ty, compiled))
+	}
+
+	/// Declare a variable holding the given value
+	fn introduce(
+		&mut self,
+		db: &dyn Thir,
+		definition: Expression<Dst>,
+	) -> (DeclarationId<Dst>, Expression<Dst>) {
+		let origin = definition.origin();
+		let mut declaration =
+			Declaration::new(false, Domain::unbounded(db, origin, definition.ty()));
+		declaration.set_definition(definition);
+		let idx = self.model.add_declaration(Item::new(declaration, origin));
+		(idx, Expression::new(db, &self.model, origin, idx))
+	}
+
+	/// Generate the code for the decision tree where each column corresponds to the given
+	/// occurrence
+	fn compile(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		origin: Origin,
+		decision: &Decision<Src>,
+		occurrences: Vec<Expression<Dst>>,
+		arms: &Arms<Dst>,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| match decision {
+			Decision::Leaf(arm) => arms.results[*arm]
+				.clone()
+				.expect("Result of arm taken by decision tree not generated"),
+			Decision::Fail => Expression::new(db, &self.model, origin, DummyValue(arms.ty)),
+			Decision::Tuple {
+				column,
+				arity,
+				next,
+			} => {
+				let fields = (1..=*arity)
+					.map(|i| {
+						Expression::new(
+							db,
+							&self.model,
+							origin,
+							TupleAccess {
+								tuple: Box::new(occurrences[*column].clone()),
+								field: IntegerLiteral(i as i64),
+							},
+						)
+					})
+					.collect();
+				let occurrences = splice(&occurrences, *column, fields);
+				self.compile(db, model, origin, next, occurrences, arms)
+			}
+			Decision::Record {
+				column,
+				fields,
+				next,
+			} => {
+				let fields = fields
+					.iter()
+					.map(|i| {
+						Expression::new(
+							db,
+							&self.model,
+							origin,
+							RecordAccess {
+								record: Box::new(occurrences[*column].clone()),
+								field: *i,
+							},
+						)
+					})
+					.collect();
+				let occurrences = splice(&occurrences, *column, fields);
+				self.compile(db, model, origin, next, occurrences, arms)
+			}
+			Decision::Switch {
+				column,
+				cases,
+				default,
+			} => {
+				let occurrence = occurrences[*column].clone();
+				let mut branches = Vec::with_capacity(cases.len());
+				for case in cases.iter() {
+					let condition = self.test(db, model, &case.head, occurrence.clone());
+					let result = match case.head {
+						Head::Constructor(m) if case.destructure => {
+							let member = self.fold_enum_member_id(db, model, m);
+							let destructed = Expression::new(
+								db,
+								&self.model,
+								origin,
+								Call {
+									function: Callable::EnumDestructor(member),
+									arguments: vec![occurrence.clone()],
+								},
+							);
+							let (decl, value) = self.introduce(db, destructed);
+							let args = if case.arity == 1 {
+								vec![value]
+							} else {
+								(1..=case.arity)
+									.map(|i| {
+										Expression::new(
+											db,
+											&self.model,
+											origin,
+											TupleAccess {
+												tuple: Box::new(value.clone()),
+												field: IntegerLiteral(i as i64),
+											},
+										)
+									})
+									.collect()
+							};
+							let occurrences = splice(&occurrences, *column, args);
+							let inner =
+								self.compile(db, model, origin, &case.next, occurrences, arms);
+							Expression::new(
+								db,
+								&self.model,
+								origin,
+								Let {
+									items: vec![LetItem::Declaration(decl)],
+									in_expression: Box::new(inner),
+								},
+							)
+						}
+						_ => {
+							let occurrences = splice(&occurrences, *column, Vec::new());
+							self.compile(db, model, origin, &case.next, occurrences, arms)
+						}
+					};
+					branches.push(Branch::new(condition, result));
+				}
+				let else_result = match default {
+					Some(d) => {
+						let occurrences = splice(&occurrences, *column, Vec::new());
+						self.compile(db, model, origin, d, occurrences, arms)
+					}
+					None => branches.pop().unwrap().result,
+				};
+				if branches.is_empty() {
+					return else_result;
+				}
+				Expression::new(
+					db,
+					&self.model,
+					origin,
+					IfThenElse {
+						branches,
+						else_result: Box::new(else_result),
+					},
+				)
+			}
+		})
+	}
+
+	/// Create the test for whether the occurrence matches the given head
+	fn test(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		head: &Head<Src>,
+		occurrence: Expression<Dst>,
+	) -> Expression<Dst> {
+		let ids = db.identifier_registry();
+		let origin = occurrence.origin();
+		let (function, value) = match head {
+			Head::Literal(e) => {
+				if let ExpressionData::BooleanLiteral(BooleanLiteral(true)) = &***e {
+					return occurrence;
+				}
+				(ids.eq, self.fold_expression(db, model, e))
+			}
+			Head::Constructor(m) => {
+				let member = self.fold_enum_member_id(db, model, *m);
+				match &model[*m].parameters {
+					None => (ids.eq, Expression::new(db, &self.model, origin, member)),
+					Some(params) => {
+						// Test membership of the set of all values from this constructor
+						let arguments = params
+							.iter()
+							.map(|p| match &**model[*p].domain() {
+								DomainData::Bounded(e) => self.fold_expression(db, model, e),
+								_ => unreachable!("Unbounded enum constructor parameter"),
+							})
+							.collect();
+						let values = Expression::new(
+							db,
+							&self.model,
+							origin,
+							Call {
+								function: Callable::EnumConstructor(member),
+								arguments,
+							},
+						);
+						(ids.in_, values)
+					}
+				}
+			}
+		};
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: function.into(),
+				arguments: vec![occurrence, value],
+			},
+		)
+	}
+}
+
+/// Compile `case` expressions into decision trees of simple tests
+pub fn compile_case(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Compiling case expressions");
+	let mut c = CaseCompiler {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+	};
+	c.add_model(db, &model);
+	Ok(c.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::compile_case;
+	use crate::thir::transform::test::check;
+
+	#[test]
+	fn test_compile_case_tuple() {
+		check(
+			compile_case,
+			r#"
+				var 1..3: x;
+				var bool: y;
+				var int: z = case (x, y) of
+					(1, true) => 10,
+					(1, _) => 11,
+					(2, true) => 12,
+					_ => 13
+				endcase;
+			"#,
+			expect!([r#"
+    var '..'(1, 3): x;
+    var bool: y;
+    var int: z = let {
+      tuple(var int, var bool): _DECL_1 = (x, y);
+    } in if '='(_DECL_1.1, 1) then if _DECL_1.2 then 10 else 11 endif elseif '='(_DECL_1.1, 2) then if _DECL_1.2 then 12 else 13 endif else 13 endif;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_compile_case_enum() {
+		check(
+			compile_case,
+			r#"
+				enum E = A(1..3) ++ {B, C};
+				var E: e;
+				var int: w = case e of A(1) => 1, A(_) => 2, B => 3, _ => 4 endcase;
+			"#,
+			expect!([r#"
+    enum E = A('..'(1, 3)) ++ { B } ++ { C };
+    var E: e;
+    var int: w = let {
+      var E: _DECL_1 = e;
+    } in if 'in'(_DECL_1, A('..'(1, 3))) then let {
+      var int: _DECL_2 = A⁻¹(_DECL_1);
+    } in if '='(_DECL_2, 1) then 1 else 2 endif elseif '='(_DECL_1, B) then 3 else 4 endif;
+"#]),
+		)
+	}
+
+	#[test]
+	fn test_compile_case_shared_arm() {
+		check(
+			compile_case,
+			r#"
+				var 1..3: x;
+				var bool: y;
+				var int: w;
+				var int: z = case (x, y) of
+					(1, true) => 10,
+					(2, false) => 11,
+					_ => w * 2
+				endcase;
+			"#,
+			expect![[r#"
+    var '..'(1, 3): x;
+    var bool: y;
+    var int: w;
+    var int: z = let {
+      tuple(var int, var bool): _DECL_1 = (x, y);
+    } in let {
+      var int: _DECL_2 = '*'(w, 2);
+    } in if '='(_DECL_1.1, 1) then if _DECL_1.2 then 10 else _DECL_2 endif elseif '='(_DECL_1.1, 2) then if '='(_DECL_1.2, false) then 11 else _DECL_2 endif else _DECL_2 endif;
+"#]],
+		)
+	}
+}
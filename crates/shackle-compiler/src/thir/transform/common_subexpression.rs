@@ -0,0 +1,545 @@
+//! Eliminates common subexpressions.
+//!
+//! Calls of type `var int` or `var float` which occur multiple times are replaced by a new
+//! top-level declaration. Expressions are compared structurally (ignoring their origins),
+//! with comprehension generator declarations compared by position.
+//!
+//! Only expressions which refer to top-level declarations or let-bound locals are shared, and
+//! only if at least one occurrence is in a position where the expression being undefined would
+//! make the model unsatisfiable (e.g. an argument to a top-level constraint). This ensures that
+//! sharing a partial expression does not change the meaning of the model. Expressions which
+//! refer to let-bound locals are declared in the innermost let expression binding one of them.
+
+use std::{
+	mem::{discriminant, Discriminant},
+	sync::Arc,
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+	constants::{IdentifierRegistry, TypeRegistry},
+	hir::Identifier,
+	thir::{
+		db::Thir,
+		traverse::{fold_expression, visit_expression, Folder, ReplacementMap, Visitor},
+		Callable, Declaration, DeclarationId, DomainData, Expression, ExpressionData, FunctionId,
+		FunctionName, Generator, Item, ItemId, Let, LetItem, Marker, Model, ResolvedIdentifier,
+	},
+	ty::Ty,
+	utils::maybe_grow_stack,
+	Result,
+};
+
+/// Part of the structural representation of an expression
+#[derive(Clone, Hash, PartialEq, Eq)]
+enum Token {
+	/// The start of an expression
+	Node(Discriminant<ExpressionData>, Ty),
+	/// A literal value
+	Literal(ExpressionData),
+	/// A reference to a top-level item or let-bound local
+	Identifier(ResolvedIdentifier),
+	/// A reference to a declaration introduced inside the expression
+	Bound(usize),
+	/// A declaration introduced inside the expression
+	Binder(Ty),
+	/// A constraint introduced inside the expression
+	Constraint,
+	/// The function being called
+	Callable(Callable),
+	/// A tuple field
+	Index(i64),
+	/// A record field
+	Field(Identifier),
+	/// The number of children which follow
+	Length(usize),
+}
+
+/// Structural representation of an expression
+type Key = Vec<Token>;
+
+/// Builds the structural representation of an expression
+struct KeyBuilder<'a> {
+	model: &'a Model,
+	locals: &'a FxHashSet<DeclarationId>,
+	tokens: Key,
+	bound: FxHashMap<DeclarationId, usize>,
+}
+
+impl<'a> KeyBuilder<'a> {
+	/// Get the key for an expression, or `None` if it cannot be shared
+	fn build(
+		model: &'a Model,
+		locals: &'a FxHashSet<DeclarationId>,
+		expression: &Expression,
+	) -> Option<Key> {
+		let mut builder = KeyBuilder {
+			model,
+			locals,
+			tokens: Vec::new(),
+			bound: FxHashMap::default(),
+		};
+		builder.push(expression)?;
+		Some(builder.tokens)
+	}
+
+	fn push_all<'e>(
+		&mut self,
+		expressions: impl ExactSizeIterator<Item = &'e Expression>,
+	) -> Option<()> {
+		self.tokens.push(Token::Length(expressions.len()));
+		for e in expressions {
+			self.push(e)?;
+		}
+		Some(())
+	}
+
+	fn push(&mut self, expression: &Expression) -> Option<()> {
+		if !expression.annotations().is_empty() {
+			return None;
+		}
+		self.tokens
+			.push(Token::Node(discriminant(&**expression), expression.ty()));
+		maybe_grow_stack(|| match &**expression {
+			ExpressionData::Absent | ExpressionData::Infinity => Some(()),
+			ExpressionData::BooleanLiteral(_)
+			| ExpressionData::IntegerLiteral(_)
+			| ExpressionData::FloatLiteral(_)
+			| ExpressionData::StringLiteral(_) => {
+				self.tokens.push(Token::Literal((**expression).clone()));
+				Some(())
+			}
+			ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => {
+				if let Some(i) = self.bound.get(d) {
+					self.tokens.push(Token::Bound(*i));
+				} else if self.model[*d].top_level() || self.locals.contains(d) {
+					self.tokens
+						.push(Token::Identifier(ResolvedIdentifier::Declaration(*d)));
+				} else {
+					return None;
+				}
+				Some(())
+			}
+			ExpressionData::Identifier(i) => {
+				self.tokens.push(Token::Identifier(i.clone()));
+				Some(())
+			}
+			ExpressionData::ArrayLiteral(al) => self.push_all(al.iter()),
+			ExpressionData::SetLiteral(sl) => self.push_all(sl.iter()),
+			ExpressionData::TupleLiteral(tl) => self.push_all(tl.iter()),
+			ExpressionData::RecordLiteral(rl) => {
+				self.tokens.push(Token::Length(rl.len()));
+				for (field, e) in rl.iter() {
+					self.tokens.push(Token::Field(*field));
+					self.push(e)?;
+				}
+				Some(())
+			}
+			ExpressionData::ArrayComprehension(c) => {
+				self.push_generators(&c.generators)?;
+				self.push_all(c.indices.iter().map(|i| &**i))?;
+				self.push(&c.template)
+			}
+			ExpressionData::SetComprehension(c) => {
+				self.push_generators(&c.generators)?;
+				self.push(&c.template)
+			}
+			ExpressionData::ArrayAccess(aa) => {
+				self.push(&aa.collection)?;
+				self.push(&aa.indices)
+			}
+			ExpressionData::TupleAccess(ta) => {
+				self.tokens.push(Token::Index(ta.field.0));
+				self.push(&ta.tuple)
+			}
+			ExpressionData::RecordAccess(ra) => {
+				self.tokens.push(Token::Field(ra.field));
+				self.push(&ra.record)
+			}
+			ExpressionData::IfThenElse(ite) => {
+				self.tokens.push(Token::Length(ite.branches.len()));
+				for branch in ite.branches.iter() {
+					self.push(&branch.condition)?;
+					self.push(&branch.result)?;
+				}
+				self.push(&ite.else_result)
+			}
+			ExpressionData::Call(c) => {
+				if let Callable::Expression(_) = &c.function {
+					return None;
+				}
+				self.tokens.push(Token::Callable(c.function.clone()));
+				self.push_all(c.arguments.iter())
+			}
+			ExpressionData::Let(l) => {
+				self.tokens.push(Token::Length(l.items.len()));
+				for item in l.items.iter() {
+					match item {
+						LetItem::Constraint(c) => {
+							let constraint = &self.model[*c];
+							if !constraint.annotations().is_empty() {
+								return None;
+							}
+							self.tokens.push(Token::Constraint);
+							self.push(constraint.expression())?;
+						}
+						LetItem::Declaration(d) => {
+							// Only declarations which are fully defined by their definition can
+							// be shared, as others introduce new variables
+							let declaration = &self.model[*d];
+							if !declaration.annotations().is_empty()
+								|| !matches!(&**declaration.domain(), DomainData::Unbounded)
+							{
+								return None;
+							}
+							self.push(declaration.definition()?)?;
+							self.bind(*d);
+						}
+					}
+				}
+				self.push(&l.in_expression)
+			}
+			ExpressionData::Case(_) | ExpressionData::Lambda(_) => None,
+		})
+	}
+
+	fn push_generators(&mut self, generators: &[Generator]) -> Option<()> {
+		self.tokens.push(Token::Length(generators.len()));
+		for generator in generators {
+			match generator {
+				Generator::Iterator {
+					declarations,
+					collection,
+					where_clause,
+				} => {
+					self.push(collection)?;
+					self.tokens.push(Token::Length(declarations.len()));
+					for d in declarations {
+						self.bind(*d);
+					}
+					self.push_all(where_clause.iter())?;
+				}
+				Generator::Assignment {
+					assignment,
+					where_clause,
+				} => {
+					self.push(self.model[*assignment].definition()?)?;
+					self.bind(*assignment);
+					self.push_all(where_clause.iter())?;
+				}
+			}
+		}
+		Some(())
+	}
+
+	fn bind(&mut self, declaration: DeclarationId) {
+		self.tokens
+			.push(Token::Binder(self.model[declaration].ty()));
+		let index = self.bound.len();
+		self.bound.insert(declaration, index);
+	}
+}
+
+/// Whether an expression is worth sharing
+fn is_candidate(types: &TypeRegistry, expression: &Expression) -> bool {
+	matches!(&**expression, ExpressionData::Call(c) if matches!(c.function, Callable::Function(_)))
+		&& (expression.ty() == types.var_int || expression.ty() == types.var_float)
+		&& expression.annotations().is_empty()
+}
+
+/// Counts the occurrences of candidate expressions
+struct Occurrences<'a> {
+	types: Arc<TypeRegistry>,
+	ids: Arc<IdentifierRegistry>,
+	/// The number of occurrences of each expression, and whether any are in a total position
+	counts: FxHashMap<Key, (usize, bool)>,
+	/// The declarations bound by let expressions
+	locals: FxHashSet<DeclarationId>,
+	/// Whether the expression being visited is in a total position if it is not boolean
+	total: bool,
+	/// Whether the expression being visited is in a total position if it is boolean
+	root: bool,
+	model: &'a Model,
+}
+
+impl<'a> Occurrences<'a> {
+	fn run(&mut self) {
+		for item in self.model.top_level_items() {
+			let root = matches!(item, ItemId::Constraint(_) | ItemId::Declaration(_));
+			self.total = root;
+			self.root = root;
+			self.visit_item(self.model, item);
+		}
+		for (_, function) in self.model.all_functions() {
+			if let Some(body) = function.body() {
+				self.total = false;
+				self.root = false;
+				self.visit_expression(self.model, body);
+			}
+		}
+	}
+}
+
+impl<'a> Visitor<'a> for Occurrences<'a> {
+	fn visit_function(&mut self, _model: &'a Model, _function: FunctionId) {
+		// Function bodies are visited separately
+	}
+
+	fn visit_expression(&mut self, model: &'a Model, expression: &'a Expression) {
+		maybe_grow_stack(|| {
+			let is_bool =
+				expression.ty() == self.types.par_bool || expression.ty() == self.types.var_bool;
+			let total = if is_bool { self.root } else { self.total };
+			if is_candidate(&self.types, expression) {
+				if let Some(key) = KeyBuilder::build(model, &self.locals, expression) {
+					let entry = self.counts.entry(key).or_default();
+					entry.0 += 1;
+					entry.1 |= total;
+				}
+			}
+			if let ExpressionData::Let(l) = &**expression {
+				// Undefined local definitions and false local constraints make the let
+				// expression undefined
+				let (prev_total, prev_root) = (self.total, self.root);
+				for item in l.items.iter() {
+					self.total = total;
+					self.root = total;
+					match item {
+						LetItem::Constraint(c) => self.visit_item(model, (*c).into()),
+						LetItem::Declaration(d) => {
+							self.locals.insert(*d);
+							self.visit_item(model, (*d).into())
+						}
+					}
+				}
+				self.total = prev_total;
+				self.root = prev_root;
+				self.visit_expression(model, &l.in_expression);
+				return;
+			}
+			let (prev_total, prev_root) = (self.total, self.root);
+			self.total = total
+				&& matches!(
+					&**expression,
+					ExpressionData::Call(_)
+						| ExpressionData::ArrayAccess(_)
+						| ExpressionData::TupleAccess(_)
+						| ExpressionData::RecordAccess(_)
+						| ExpressionData::ArrayLiteral(_)
+						| ExpressionData::SetLiteral(_)
+						| ExpressionData::TupleLiteral(_)
+						| ExpressionData::RecordLiteral(_)
+				);
+			self.root = total
+				&& matches!(
+					&**expression,
+					ExpressionData::Call(c) if matches!(
+						c.function,
+						Callable::Function(f) if model[f].name() == FunctionName::new(self.ids.conj)
+					)
+				);
+			visit_expression(self, model, expression);
+			self.total = prev_total;
+			self.root = prev_root;
+		})
+	}
+}
+
+/// A let expression which is being folded
+struct LetScope<Dst: Marker> {
+	/// The declarations bound so far
+	locals: FxHashSet<DeclarationId>,
+	/// The folded items so far
+	items: Vec<LetItem<Dst>>,
+}
+
+struct CommonSubexpressions<Dst: Marker> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst>,
+	types: Arc<TypeRegistry>,
+	locals: FxHashSet<DeclarationId>,
+	shared: FxHashSet<Key>,
+	introduced: FxHashMap<Key, DeclarationId<Dst>>,
+	scopes: Vec<LetScope<Dst>>,
+}
+
+impl<Dst: Marker> Folder<'_, Dst> for CommonSubexpressions<Dst> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst> {
+		&mut self.replacement_map
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model,
+		expression: &Expression,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			if !is_candidate(&self.types, expression) {
+				return fold_expression(self, db, model, expression);
+			}
+			let key = match KeyBuilder::build(model, &self.locals, expression) {
+				Some(key) if self.shared.contains(&key) => key,
+				_ => return fold_expression(self, db, model, expression),
+			};
+			let origin = expression.origin();
+			let idx = match self.introduced.get(&key) {
+				Some(idx) => *idx,
+				None => {
+					let folded = fold_expression(self, db, model, expression);
+					// Declare in the innermost let expression binding a local used by the expression
+					let scope = self.scopes.iter().rposition(|scope| {
+						key.iter().any(|t| {
+							matches!(
+								t,
+								Token::Identifier(ResolvedIdentifier::Declaration(d))
+									if scope.locals.contains(d)
+							)
+						})
+					});
+					let declaration = Declaration::from_expression(db, scope.is_none(), folded);
+					let idx = self.model.add_declaration(Item::new(declaration, origin));
+					if let Some(i) = scope {
+						self.scopes[i].items.push(LetItem::Declaration(idx));
+					}
+					self.introduced.insert(key, idx);
+					idx
+				}
+			};
+			Expression::new(db, &self.model, origin, idx)
+		})
+	}
+
+	fn fold_let(&mut self, db: &dyn Thir, model: &Model, l: &Let) -> Let<Dst> {
+		// Items are collected in the scope so that shared expressions can be declared
+		// between them
+		self.scopes.push(LetScope {
+			locals: FxHashSet::default(),
+			items: Vec::new(),
+		});
+		for item in l.items.iter() {
+			let folded = match item {
+				LetItem::Constraint(c) => {
+					self.add_constraint(db, model, *c);
+					LetItem::Constraint(self.fold_constraint_id(db, model, *c))
+				}
+				LetItem::Declaration(d) => {
+					self.scopes.last_mut().unwrap().locals.insert(*d);
+					self.add_variable_declaration(db, model, *d);
+					LetItem::Declaration(self.fold_declaration_id(db, model, *d))
+				}
+			};
+			self.scopes.last_mut().unwrap().items.push(folded);
+		}
+		let in_expression = Box::new(self.fold_expression(db, model, &l.in_expression));
+		let scope = self.scopes.pop().unwrap();
+		Let {
+			items: scope.items,
+			in_expression,
+		}
+	}
+}
+
+/// Replace repeated `var int` and `var float` calls with shared top-level declarations
+pub fn eliminate_common_subexpressions(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Eliminating common subexpressions");
+	let mut occurrences = Occurrences {
+		types: db.type_registry(),
+		ids: db.identifier_registry(),
+		counts: FxHashMap::default(),
+		locals: FxHashSet::default(),
+		total: false,
+		root: false,
+		model: &model,
+	};
+	occurrences.run();
+	let shared = occurrences
+		.counts
+		.into_iter()
+		.filter(|(_, (count, total))| *count > 1 && *total)
+		.map(|(key, _)| key)
+		.collect::<FxHashSet<_>>();
+	if shared.is_empty() {
+		return Ok(model);
+	}
+	let mut cse = CommonSubexpressions {
+		replacement_map: ReplacementMap::default(),
+		model: Model::with_capacities(&model.entity_counts()),
+		types: db.type_registry(),
+		locals: occurrences.locals,
+		shared,
+		introduced: FxHashMap::default(),
+		scopes: Vec::new(),
+	};
+	cse.add_model(db, &model);
+	Ok(cse.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::eliminate_common_subexpressions;
+	use crate::thir::transform::test::check;
+
+	#[test]
+	fn test_common_subexpressions() {
+		check(
+			eliminate_common_subexpressions,
+			r#"
+				array [1..3] of var 1..3: x;
+				var 0..1: y;
+				constraint sum (i in 1..3) (x[i]) <= 5;
+				constraint sum (j in 1..3) (x[j]) >= y;
+				constraint y = 1 \/ sum (k in 1..3) (x[k]) > 2;
+				constraint y = 0 \/ max(x) = 2;
+				constraint max(x) < 3 \/ y = 1;
+			"#,
+			expect![[r#"
+    array ['..'(1, 3)] of var '..'(1, 3): x;
+    var '..'(0, 1): y;
+    var int: _DECL_1 = sum([(x)[i] | i in '..'(1, 3)]);
+    constraint '<='(_DECL_1, 5);
+    constraint '>='(_DECL_1, y);
+    constraint '\/'('='(y, 1), '>'(_DECL_1, 2));
+    constraint '\/'('='(y, 0), '='(max(x), 2));
+    constraint '\/'('<'(max(x), 3), '='(y, 1));
+"#]],
+		)
+	}
+
+	#[test]
+	fn test_common_subexpressions_let() {
+		check(
+			eliminate_common_subexpressions,
+			r#"
+				var 1..3: x;
+				var 1..3: y;
+				constraint let {
+					var int: a = x * y;
+				} in max(a, x) <= 5 /\ max(a, x) >= 2;
+				constraint max(let { var int: b = x + 1 } in b, y) <= 4;
+				constraint max(let { var int: c = x + 1 } in c, y) >= 2;
+			"#,
+			expect![[r#"
+    var '..'(1, 3): x;
+    var '..'(1, 3): y;
+    constraint let {
+      var int: a = '*'(x, y);
+      var int: _DECL_1 = max(a, x);
+    } in '/\'('<='(_DECL_1, 5), '>='(_DECL_1, 2));
+    var int: _DECL_2 = '+'(x, 1);
+    var int: _DECL_3 = max(let {
+      var int: b = _DECL_2;
+    } in b, y);
+    constraint '<='(_DECL_3, 4);
+    constraint '>='(_DECL_3, 2);
+"#]],
+		)
+	}
+}
@@ -0,0 +1,299 @@
+//! Removes functions, annotations and enums which are not reachable from the model.
+//!
+//! Reachability starts from the constraints, solve item, output items and top-level
+//! declarations. Functions are tracked by name rather than by overload, since later
+//! transforms may resolve calls to a different overload of the same function. Functions
+//! with names in the identifier registry are always kept, as later transforms may
+//! introduce calls to them.
+//!
+//! The MiniZinc flattener which runs the compiled program also introduces calls to builtins
+//! (such as `int_lin_le`) and their solver redefinitions, and emits library annotations
+//! (such as `output_var`). So functions without bodies, functions defined in
+//! `redefinitions*.mzn` files and annotations declared outside the input models are kept too.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{
+			add_annotation, add_enumeration, add_function, fold_function_body, visit_callable,
+			visit_domain, visit_expression, visit_pattern, Folder, ReplacementMap, Visitor,
+		},
+		AnnotationId, Callable, Domain, EnumerationId, Expression, FunctionId, FunctionName,
+		ItemId, Marker, Model, Pattern, PatternData, ResolvedIdentifier,
+	},
+	ty::{EnumRef, Ty},
+	utils::maybe_grow_stack,
+	Result,
+};
+
+/// Computes the items reachable from the roots of the model
+struct Reachability<'a> {
+	db: &'a dyn Thir,
+	overloads: FxHashMap<FunctionName, Vec<FunctionId>>,
+	enumerations: FxHashMap<EnumRef, EnumerationId>,
+	functions: FxHashSet<FunctionName>,
+	annotations: FxHashSet<AnnotationId>,
+	enums: FxHashSet<EnumRef>,
+	todo: Vec<ItemId>,
+}
+
+impl<'a> Reachability<'a> {
+	fn new(db: &'a dyn Thir, model: &Model) -> Self {
+		let mut overloads: FxHashMap<_, Vec<_>> = FxHashMap::default();
+		for (idx, function) in model.top_level_functions() {
+			overloads.entry(function.name()).or_default().push(idx);
+		}
+		Self {
+			db,
+			overloads,
+			enumerations: model
+				.enumerations()
+				.map(|(idx, e)| (e.enum_type(), idx))
+				.collect(),
+			functions: FxHashSet::default(),
+			annotations: FxHashSet::default(),
+			enums: FxHashSet::default(),
+			todo: Vec::new(),
+		}
+	}
+
+	fn run(&mut self, model: &'a Model) {
+		let registry = self.db.identifier_registry().all();
+		for ident in registry.iter() {
+			self.add_function_name(FunctionName::new(*ident));
+		}
+		for (_, function) in model.top_level_functions() {
+			if function.body().is_none() || self.is_redefinition(function.origin()) {
+				self.add_function_name(function.name());
+			}
+		}
+		for (idx, annotation) in model.annotations() {
+			if annotation
+				.name
+				.map(|n| registry.contains(&n))
+				.unwrap_or(false)
+				|| !annotation.origin().is_from_input(self.db)
+			{
+				self.add_annotation(idx);
+			}
+		}
+		for item in model.top_level_items() {
+			match item {
+				ItemId::Constraint(_)
+				| ItemId::Declaration(_)
+				| ItemId::Output(_)
+				| ItemId::Solve => self.visit_item(model, item),
+				_ => (),
+			}
+		}
+		while let Some(item) = self.todo.pop() {
+			match item {
+				ItemId::Enumeration(e) => {
+					self.visit_enumeration(model, e);
+					for constructor in model[e].definition().into_iter().flatten() {
+						for param in constructor.parameters.iter().flatten() {
+							self.visit_declaration(model, *param);
+						}
+					}
+				}
+				_ => self.visit_item(model, item),
+			}
+		}
+	}
+
+	/// Whether the origin is in a file containing redefinitions of builtins for the solver
+	fn is_redefinition(&self, origin: Origin) -> bool {
+		origin
+			.model_ref(self.db)
+			.and_then(|m| m.path(self.db.upcast()))
+			.and_then(|p| {
+				p.file_name()
+					.and_then(|n| n.to_str())
+					.map(|n| n.starts_with("redefinitions"))
+			})
+			.unwrap_or(false)
+	}
+
+	fn add_function_name(&mut self, name: FunctionName) {
+		if self.functions.insert(name) {
+			if let Some(fs) = self.overloads.get(&name) {
+				self.todo.extend(fs.iter().map(|f| ItemId::Function(*f)));
+			}
+		}
+	}
+
+	fn add_annotation(&mut self, annotation: AnnotationId) {
+		if self.annotations.insert(annotation) {
+			self.todo.push(ItemId::Annotation(annotation));
+		}
+	}
+
+	fn add_ty(&mut self, ty: Ty) {
+		let db = self.db.upcast();
+		for t in ty.walk(db) {
+			if let Some(e) = t.enum_ty(db) {
+				if self.enums.insert(e) {
+					if let Some(idx) = self.enumerations.get(&e) {
+						self.todo.push(ItemId::Enumeration(*idx));
+					}
+				}
+			}
+		}
+	}
+
+	fn add_enumeration(&mut self, model: &Model, enumeration: EnumerationId) {
+		if self.enums.insert(model[enumeration].enum_type()) {
+			self.todo.push(ItemId::Enumeration(enumeration));
+		}
+	}
+}
+
+impl<'a> Visitor<'a> for Reachability<'a> {
+	fn visit_expression(&mut self, model: &'a Model, expression: &'a Expression) {
+		maybe_grow_stack(|| {
+			self.add_ty(expression.ty());
+			visit_expression(self, model, expression);
+		})
+	}
+
+	fn visit_domain(&mut self, model: &'a Model, domain: &'a Domain) {
+		self.add_ty(domain.ty());
+		visit_domain(self, model, domain);
+	}
+
+	fn visit_identifier(&mut self, model: &'a Model, identifier: &'a ResolvedIdentifier) {
+		match identifier {
+			ResolvedIdentifier::Annotation(a) => self.add_annotation(*a),
+			ResolvedIdentifier::Enumeration(e) => self.add_enumeration(model, *e),
+			ResolvedIdentifier::EnumerationMember(m) => {
+				self.add_enumeration(model, m.enumeration_id())
+			}
+			ResolvedIdentifier::Declaration(_) => (),
+		}
+	}
+
+	fn visit_callable(&mut self, model: &'a Model, callable: &'a Callable) {
+		match callable {
+			Callable::Function(f) => self.add_function_name(model[*f].name()),
+			Callable::Annotation(a) | Callable::AnnotationDestructure(a) => self.add_annotation(*a),
+			Callable::EnumConstructor(m) | Callable::EnumDestructor(m) => {
+				self.add_enumeration(model, m.enumeration_id())
+			}
+			Callable::Expression(_) => (),
+		}
+		visit_callable(self, model, callable);
+	}
+
+	fn visit_pattern(&mut self, model: &'a Model, pattern: &'a Pattern) {
+		match &**pattern {
+			PatternData::EnumConstructor { member, .. } => {
+				self.add_enumeration(model, member.enumeration_id())
+			}
+			PatternData::AnnotationConstructor { item, .. } => self.add_annotation(*item),
+			_ => (),
+		}
+		visit_pattern(self, model, pattern);
+	}
+}
+
+/// Copies the reachable items into a new model
+struct DeadCodeEliminator<Dst: Marker> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst>,
+	functions: FxHashSet<FunctionName>,
+	annotations: FxHashSet<AnnotationId>,
+	enums: FxHashSet<EnumRef>,
+}
+
+impl<Dst: Marker> Folder<'_, Dst> for DeadCodeEliminator<Dst> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst> {
+		&mut self.replacement_map
+	}
+
+	fn add_annotation(&mut self, db: &dyn Thir, model: &Model, a: AnnotationId) {
+		if self.annotations.contains(&a) {
+			add_annotation(self, db, model, a);
+		}
+	}
+
+	fn add_enumeration(&mut self, db: &dyn Thir, model: &Model, e: EnumerationId) {
+		if self.enums.contains(&model[e].enum_type()) {
+			add_enumeration(self, db, model, e);
+		}
+	}
+
+	fn add_function(&mut self, db: &dyn Thir, model: &Model, f: FunctionId) {
+		if self.functions.contains(&model[f].name()) {
+			add_function(self, db, model, f);
+		}
+	}
+
+	fn fold_function_body(&mut self, db: &dyn Thir, model: &Model, f: FunctionId) {
+		if self.functions.contains(&model[f].name()) {
+			fold_function_body(self, db, model, f);
+		}
+	}
+}
+
+/// Remove functions, annotations and enums which are not reachable from the constraints,
+/// solve item, outputs or top-level declarations of the model.
+pub fn eliminate_dead_code(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Eliminating dead code");
+	let mut reachability = Reachability::new(db, &model);
+	reachability.run(&model);
+	let mut eliminator = DeadCodeEliminator {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+		functions: reachability.functions,
+		annotations: reachability.annotations,
+		enums: reachability.enums,
+	};
+	eliminator.add_model(db, &model);
+	Ok(eliminator.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::eliminate_dead_code;
+	use crate::thir::transform::test::check_no_stdlib;
+
+	#[test]
+	fn test_dead_code_elimination() {
+		check_no_stdlib(
+			eliminate_dead_code,
+			r#"
+				enum Foo = {A, B};
+				enum Bar = {C, D};
+				annotation used;
+				annotation unused;
+				function int: foo(int: x) = bar(x);
+				function int: foo(float: x) = 1;
+				function int: bar(int: x) = x;
+				function int: baz(Bar: x) = 1;
+				predicate builtin(var int: x);
+				int: y :: used = foo(1);
+				var Foo: z;
+			"#,
+			expect!([r#"
+    enum Foo = { A } ++ { B };
+    annotation used;
+    function int: foo(int: x) = bar(x);
+    function int: foo(float: x) = 1;
+    function int: bar(int: x) = x;
+    function var bool: builtin(var int: x);
+    int: y :: (used) = foo(1);
+    var Foo: z;
+    solve satisfy;
+"#]),
+		)
+	}
+}
@@ -19,9 +19,10 @@ use crate::{
 			add_function, add_item, fold_call, fold_domain, fold_expression, fold_function,
 			fold_function_body, fold_identifier, Folder, ReplacementMap,
 		},
-		ArrayLiteral, Call, Callable, Declaration, DeclarationId, Domain, DomainData, EnumMemberId,
-		EnumerationId, EnumerationItem, Expression, ExpressionData, Function, FunctionId,
-		FunctionName, Item, ItemId, LookupCall, Marker, Model, ResolvedIdentifier, TupleLiteral,
+		ArrayAccess, ArrayLiteral, Call, Callable, Declaration, DeclarationId, Domain, DomainData,
+		EnumMemberId, EnumerationId, EnumerationItem, Expression, ExpressionData, Function,
+		FunctionId, FunctionName, Item, ItemId, Let, LetItem, LookupCall, Marker, Model,
+		ResolvedIdentifier, TupleLiteral,
 	},
 	ty::EnumRef,
 	utils::{arena::ArenaMap, maybe_grow_stack},
@@ -153,22 +154,6 @@ impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for EnumEraser<Dst, Src> {
 				.resolve(db, &self.model)
 				.0
 			}
-			Callable::EnumDestructor(e) => {
-				let mzn_enum = self.mzn_enum_for_item[e.enumeration_id()];
-				let member_id = e.member_index() as i64 + 1;
-				let origin = model[e.enumeration_id()].origin();
-				let arguments = vec![
-					Expression::new(db, &self.model, origin, mzn_enum),
-					Expression::new(db, &self.model, origin, IntegerLiteral(member_id)),
-					self.fold_expression(db, model, &call.arguments[0]),
-				];
-				LookupCall {
-					function: self.ids.mzn_destruct_enum.into(),
-					arguments,
-				}
-				.resolve(db, &self.model)
-				.0
-			}
 			Callable::Function(f)
 				if model[*f].name() == self.ids.to_enum && model[*f].body().is_none() =>
 			{
@@ -195,10 +180,14 @@ impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for EnumEraser<Dst, Src> {
 	) -> Expression<Dst> {
 		maybe_grow_stack(|| {
 			if let ExpressionData::Call(c) = &**expression {
-				if let Callable::Function(f) = &c.function {
-					if model[*f].name() == self.ids.erase_enum {
+				match &c.function {
+					Callable::Function(f) if model[*f].name() == self.ids.erase_enum => {
 						return self.fold_expression(db, model, &c.arguments[0]);
 					}
+					Callable::EnumDestructor(e) => {
+						return self.erase_destructor(db, model, *e, &c.arguments[0]);
+					}
+					_ => (),
 				}
 			}
 			fold_expression(self, db, model, expression)
@@ -227,6 +216,73 @@ impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for EnumEraser<Dst, Src> {
 }
 
 impl<Src: Marker, Dst: Marker> EnumEraser<Dst, Src> {
+	/// Erase enum destructor into a function call returning the array of arguments,
+	/// which is then accessed to give the argument (or tuple of arguments)
+	fn erase_destructor(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		e: EnumMemberId<Src>,
+		argument: &Expression<Src>,
+	) -> Expression<Dst> {
+		let mzn_enum = self.mzn_enum_for_item[e.enumeration_id()];
+		let member_id = e.member_index() as i64 + 1;
+		let origin = model[e.enumeration_id()].origin();
+		let arguments = vec![
+			Expression::new(db, &self.model, origin, mzn_enum),
+			Expression::new(db, &self.model, origin, IntegerLiteral(member_id)),
+			self.fold_expression(db, model, argument),
+		];
+		let destructed = Expression::new(
+			db,
+			&self.model,
+			origin,
+			LookupCall {
+				function: self.ids.mzn_destruct_enum.into(),
+				arguments,
+			},
+		);
+		let access = |model: &Model<Dst>, array: Expression<Dst>, i: i64| {
+			Expression::new(
+				db,
+				model,
+				origin,
+				ArrayAccess {
+					collection: Box::new(array),
+					indices: Box::new(Expression::new(db, model, origin, IntegerLiteral(i))),
+				},
+			)
+		};
+		let params = model[e].parameters.as_ref().unwrap().len();
+		if params == 1 {
+			return access(&self.model, destructed, 1);
+		}
+		let mut declaration =
+			Declaration::new(false, Domain::unbounded(db, origin, destructed.ty()));
+		declaration.set_definition(destructed);
+		let idx = self.model.add_declaration(Item::new(declaration, origin));
+		let fields = (1..=params as i64)
+			.map(|i| {
+				let array = Expression::new(db, &self.model, origin, idx);
+				access(&self.model, array, i)
+			})
+			.collect();
+		Expression::new(
+			db,
+			&self.model,
+			origin,
+			Let {
+				items: vec![LetItem::Declaration(idx)],
+				in_expression: Box::new(Expression::new(
+					db,
+					&self.model,
+					origin,
+					TupleLiteral(fields),
+				)),
+			},
+		)
+	}
+
 	fn erase_enum(&mut self, db: &dyn Thir, model: &Model<Src>, idx: EnumerationId<Src>) {
 		let enumeration = &model[idx];
 		let origin = enumeration.origin();
@@ -419,7 +475,9 @@ mod test {
 	use expect_test::expect;
 
 	use super::erase_enum;
-	use crate::thir::transform::{test::check, transformer, type_specialise::type_specialise};
+	use crate::thir::transform::{
+		case_compile::compile_case, test::check, transformer, type_specialise::type_specialise,
+	};
 
 	#[test]
 	fn test_enum_type_erasure() {
@@ -457,6 +515,34 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn test_enum_destructor_erasure() {
+		check(
+			transformer(vec![compile_case, type_specialise, erase_enum]),
+			r#"
+				enum Foo = D(1..2) ++ G(1..2, 1..3);
+				Foo: x;
+				int: y = case x of D(a) => a, G(a, b) => a + b endcase;
+			"#,
+			expect!([r#"
+    tuple(int, array [int] of tuple(string, array [int] of tuple(int, set of int), int)): _DECL_1 = mzn_get_enum([("D", [(0, '..'(1, 2))]), ("G", [(0, '..'(1, 2)), (0, '..'(1, 3))])]);
+    set of int: Foo = mzn_defining_set(_DECL_1);
+    Foo: x;
+    int: y = let {
+      int: _DECL_2 = x;
+    } in if 'in'(_DECL_2, mzn_construct_enum(_DECL_1, 1, ['..'(1, 2)])) then let {
+      int: a = (mzn_destruct_enum(_DECL_1, 1, _DECL_2))[1];
+    } in a else let {
+      tuple(int, int): _DECL_3 = let {
+      array [int] of int: _DECL_4 = mzn_destruct_enum(_DECL_1, 2, _DECL_2);
+    } in ((_DECL_4)[1], (_DECL_4)[2]);
+      int: a = _DECL_3.1;
+      int: b = _DECL_3.2;
+    } in '+'(a, b) endif;
+"#]),
+		);
+	}
+
 	#[test]
 	fn test_enum_show() {
 		check(
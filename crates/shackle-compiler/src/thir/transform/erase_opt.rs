@@ -3,6 +3,7 @@
 //! - Replace `<>` with `(false, ⊥)`
 //! - Replace `opt T` with `tuple(bool, T)`
 //! - Make `occurs(x)` return `x.1` and `deopt(x)` return `x.2`
+//! - Replace access `x.i` of an optional tuple `x` with `(x.1, x.2.i)`
 //!
 //! Does not handle records, so records must be erased into tuples first
 
@@ -19,9 +20,9 @@ use crate::{
 		traverse::{
 			add_function, fold_domain, fold_expression, fold_function_body, Folder, ReplacementMap,
 		},
-		ArrayComprehension, Call, Callable, Constraint, Declaration, Domain, DomainData,
-		DummyValue, Expression, ExpressionData, FunctionId, Generator, Item, Let, LetItem,
-		LookupCall, Marker, Model, TupleAccess, TupleLiteral,
+		ArrayComprehension, Call, Callable, Constraint, Declaration, DeclarationId, Domain,
+		DomainData, DummyValue, Expression, ExpressionData, FunctionId, Generator, Item, Let,
+		LetItem, LookupCall, Marker, Model, TupleAccess, TupleLiteral,
 	},
 	ty::{Ty, TyData},
 	utils::maybe_grow_stack,
@@ -128,15 +129,27 @@ impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for OptEraser<Dst, Src> {
 				} else {
 					self.tys.par_bool
 				};
-				let deopt = domain.ty().make_occurs(db.upcast());
+				let deopt = if let DomainData::Tuple(fields) = &**domain {
+					// Optional tuple fields must also be erased
+					Domain::tuple(
+						db,
+						origin,
+						OptType::NonOpt,
+						fields.iter().map(|f| self.fold_domain(db, model, f)),
+					)
+				} else {
+					// Keep only the type, erasing any optional tuple fields within it
+					Domain::unbounded(
+						db,
+						origin,
+						self.erase_ty(db, domain.ty().make_occurs(db.upcast())),
+					)
+				};
 				return Domain::tuple(
 					db,
 					origin,
 					OptType::NonOpt,
-					[
-						Domain::unbounded(db, origin, occurs),
-						Domain::unbounded(db, origin, deopt),
-					],
+					[Domain::unbounded(db, origin, occurs), deopt],
 				);
 			}
 			fold_domain(self, db, model, domain)
@@ -177,6 +190,18 @@ impl<Dst: Marker, Src: Marker> Folder<'_, Dst, Src> for OptEraser<Dst, Src> {
 					}
 				}
 			}
+			if let ExpressionData::TupleAccess(ta) = &**expression {
+				if ta.tuple.ty().opt(db.upcast()) == Some(OptType::Opt) {
+					let mut e = self.erase_opt_tuple_access(db, model, ta, expression.origin());
+					e.annotations_mut().extend(
+						expression
+							.annotations()
+							.iter()
+							.map(|ann| self.fold_expression(db, model, ann)),
+					);
+					return e;
+				}
+			}
 			fold_expression(self, db, model, expression)
 		})
 	}
@@ -228,7 +253,7 @@ impl<Src: Marker, Dst: Marker> OptEraser<Dst, Src> {
 				db,
 				&self.model,
 				origin,
-				DummyValue(top_down_ty.with_opt(db.upcast(), OptType::NonOpt)),
+				DummyValue(self.erase_ty(db, top_down_ty.with_opt(db.upcast(), OptType::NonOpt))),
 			);
 			let mut tl = Expression::new(
 				db,
@@ -242,15 +267,7 @@ impl<Src: Marker, Dst: Marker> OptEraser<Dst, Src> {
 
 		if self.needs_opt_erase(db, top_down_ty, bottom_up_ty) {
 			// Needs to be reconstructed to erase optionality
-			let (decl, ident) = if matches!(&*e, ExpressionData::Identifier(_)) {
-				(None, e)
-			} else {
-				let mut declaration =
-					Declaration::new(false, Domain::unbounded(db, origin, e.ty()));
-				declaration.set_definition(e);
-				let idx = self.model.add_declaration(Item::new(declaration, origin));
-				(Some(idx), Expression::new(db, &self.model, origin, idx))
-			};
+			let (decl, ident) = self.bind(db, origin, e);
 
 			let erased = match bottom_up_ty.lookup(db.upcast()) {
 				TyData::Array { .. } => {
@@ -324,25 +341,123 @@ impl<Src: Marker, Dst: Marker> OptEraser<Dst, Src> {
 				_ => unreachable!(),
 			};
 
-			if let Some(decl) = decl {
-				return Expression::new(
-					db,
-					&self.model,
-					origin,
-					Let {
-						items: vec![LetItem::Declaration(decl)],
-						in_expression: Box::new(erased),
-					},
-				);
-			} else {
-				return erased;
-			}
+			return self.wrap_let(db, origin, decl, erased);
 		}
 
 		// No need to do anything
 		e
 	}
 
+	/// Get the type of a tuple or scalar after erasing option types
+	fn erase_ty(&self, db: &dyn Thir, ty: Ty) -> Ty {
+		let erased = match ty.lookup(db.upcast()) {
+			TyData::Tuple(_, fs) => {
+				Ty::tuple(db.upcast(), fs.iter().map(|f| self.erase_ty(db, *f)))
+			}
+			_ => ty.with_opt(db.upcast(), OptType::NonOpt),
+		};
+		if ty.opt(db.upcast()) == Some(OptType::Opt) {
+			let occurs = if let Some(VarType::Var) = ty.inst(db.upcast()) {
+				self.tys.var_bool
+			} else {
+				self.tys.par_bool
+			};
+			Ty::tuple(db.upcast(), [occurs, erased])
+		} else {
+			erased
+		}
+	}
+
+	/// Transform `x.i` where `x` is an optional tuple into `(x.1, x.2.i)`.
+	///
+	/// If the field is itself optional, then it becomes `(x.1 /\ x.2.i.1, x.2.i.2)`.
+	fn erase_opt_tuple_access(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model<Src>,
+		ta: &TupleAccess<Src>,
+		origin: Origin,
+	) -> Expression<Dst> {
+		let field_ty = ta.tuple.ty().fields(db.upcast()).unwrap()[ta.field.0 as usize - 1];
+		let tuple = self.fold_expression(db, model, &ta.tuple);
+		let (decl, ident) = self.bind(db, origin, tuple);
+		let access = |eraser: &mut Self, tuple: Expression<Dst>, field: i64| {
+			Expression::new(
+				db,
+				&eraser.model,
+				origin,
+				TupleAccess {
+					tuple: Box::new(tuple),
+					field: IntegerLiteral(field),
+				},
+			)
+		};
+		let occurs = access(self, ident.clone(), 1);
+		let deopt = access(self, ident, 2);
+		let field = access(self, deopt, ta.field.0);
+		let erased = if field_ty.opt(db.upcast()) == Some(OptType::Opt) {
+			let field_occurs = access(self, field.clone(), 1);
+			let field_deopt = access(self, field, 2);
+			let occurs = Expression::new(
+				db,
+				&self.model,
+				origin,
+				LookupCall {
+					function: self.ids.conj.into(),
+					arguments: vec![occurs, field_occurs],
+				},
+			);
+			Expression::new(
+				db,
+				&self.model,
+				origin,
+				TupleLiteral(vec![occurs, field_deopt]),
+			)
+		} else {
+			Expression::new(db, &self.model, origin, TupleLiteral(vec![occurs, field]))
+		};
+		self.wrap_let(db, origin, decl, erased)
+	}
+
+	/// Bind the given expression to a new declaration unless it is already an identifier
+	fn bind(
+		&mut self,
+		db: &dyn Thir,
+		origin: Origin,
+		e: Expression<Dst>,
+	) -> (Option<DeclarationId<Dst>>, Expression<Dst>) {
+		if matches!(&*e, ExpressionData::Identifier(_)) {
+			(None, e)
+		} else {
+			let mut declaration = Declaration::new(false, Domain::unbounded(db, origin, e.ty()));
+			declaration.set_definition(e);
+			let idx = self.model.add_declaration(Item::new(declaration, origin));
+			(Some(idx), Expression::new(db, &self.model, origin, idx))
+		}
+	}
+
+	/// Wrap the given expression in a let expression if a declaration was introduced by `bind`
+	fn wrap_let(
+		&mut self,
+		db: &dyn Thir,
+		origin: Origin,
+		decl: Option<DeclarationId<Dst>>,
+		e: Expression<Dst>,
+	) -> Expression<Dst> {
+		match decl {
+			Some(decl) => Expression::new(
+				db,
+				&self.model,
+				origin,
+				Let {
+					items: vec![LetItem::Declaration(decl)],
+					in_expression: Box::new(e),
+				},
+			),
+			None => e,
+		}
+	}
+
 	fn create_opt_var(
 		&mut self,
 		db: &dyn Thir,
@@ -523,6 +638,31 @@ mod test {
     } in _DECL_10)): r;
     } in r;
     solve satisfy;
+"#]),
+		);
+	}
+
+	#[test]
+	fn test_option_tuple_erasure() {
+		check_no_stdlib(
+			transformer(vec![top_down_type, erase_opt]),
+			r#"
+			function bool: '/\'(bool: a, bool: b);
+			type P = tuple(int, opt bool);
+			opt P: x;
+			opt int: y = x.1;
+			opt bool: z = x.2;
+			opt P: w = <>;
+			any: v = x;
+			"#,
+			expect!([r#"
+    function bool: '/\'(bool: a, bool: b);
+    tuple(bool, tuple(int, tuple(bool, bool))): x;
+    tuple(bool, int): y = (x.1, x.2.1);
+    tuple(bool, bool): z = ('/\'(x.1, x.2.2.1), x.2.2.2);
+    tuple(bool, tuple(int, tuple(bool, bool))): w = (false, (0, (false, false)));
+    tuple(bool, tuple(int, tuple(bool, bool))): v = x;
+    solve satisfy;
 "#]),
 		);
 	}
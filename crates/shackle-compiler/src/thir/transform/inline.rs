@@ -0,0 +1,282 @@
+//! Inlines small user-defined functions.
+//!
+//! A function is inlined if it is defined in one of the input models, has a body with at
+//! most `INLINE_SIZE_LIMIT` expressions, has no annotations and is not (mutually) recursive.
+//! - Call arguments which are not identifiers or literals are bound using a let expression
+//!   to preserve call-by-value semantics
+//! - Declarations inside inlined bodies are renamed to avoid capturing names at the call site,
+//!   as are local declarations which shadow top-level declarations referenced by inlined bodies
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::top_down_type::add_coercion;
+use crate::{
+	hir::Identifier,
+	thir::{
+		db::Thir,
+		traverse::{
+			fold_declaration, fold_expression, visit_callable, visit_expression, Folder,
+			ReplacementMap, Visitor,
+		},
+		Callable, Declaration, DeclarationId, Expression, ExpressionData, FunctionId, Item, Let,
+		LetItem, Marker, Model, ResolvedIdentifier,
+	},
+	utils::maybe_grow_stack,
+	Result,
+};
+
+/// The maximum number of expressions in the body of an inlined function
+pub const INLINE_SIZE_LIMIT: usize = 30;
+
+/// Collects the size, callees and referenced top-level declarations of a function body
+#[derive(Default)]
+struct BodyInfo {
+	size: usize,
+	callees: FxHashSet<FunctionId>,
+	globals: FxHashSet<Identifier>,
+}
+
+impl<'a> Visitor<'a> for BodyInfo {
+	fn visit_expression(&mut self, model: &'a Model, expression: &'a Expression) {
+		maybe_grow_stack(|| {
+			self.size += 1;
+			visit_expression(self, model, expression);
+		})
+	}
+
+	fn visit_callable(&mut self, model: &'a Model, callable: &'a Callable) {
+		if let Callable::Function(f) = callable {
+			self.callees.insert(*f);
+		}
+		visit_callable(self, model, callable);
+	}
+
+	fn visit_identifier(&mut self, model: &'a Model, identifier: &'a ResolvedIdentifier) {
+		if let ResolvedIdentifier::Declaration(d) = identifier {
+			if model[*d].top_level() {
+				self.globals.extend(model[*d].name());
+			}
+		}
+	}
+}
+
+/// Find the functions which can be inlined, and the names of the top-level declarations
+/// referenced by their bodies
+fn inlinable_functions(
+	db: &dyn Thir,
+	model: &Model,
+) -> (FxHashSet<FunctionId>, FxHashSet<Identifier>) {
+	let mut info = FxHashMap::default();
+	for (idx, function) in model.top_level_functions() {
+		if let Some(body) = function.body() {
+			let mut body_info = BodyInfo::default();
+			body_info.visit_expression(model, body);
+			info.insert(idx, body_info);
+		}
+	}
+	let reaches_self = |f: FunctionId| {
+		let mut todo = info[&f].callees.iter().copied().collect::<Vec<_>>();
+		let mut seen = FxHashSet::default();
+		while let Some(g) = todo.pop() {
+			if g == f {
+				return true;
+			}
+			if seen.insert(g) {
+				todo.extend(info.get(&g).into_iter().flat_map(|i| i.callees.iter()));
+			}
+		}
+		false
+	};
+	let functions = info
+		.iter()
+		.filter(|(f, body_info)| {
+			let function = &model[**f];
+			body_info.size <= INLINE_SIZE_LIMIT
+				&& function.annotations().is_empty()
+				&& !function.is_polymorphic()
+				&& model[**f].origin().is_from_input(db)
+				&& !reaches_self(**f)
+		})
+		.map(|(f, _)| *f)
+		.collect::<FxHashSet<_>>();
+	let globals = functions
+		.iter()
+		.flat_map(|f| info[f].globals.iter().copied())
+		.collect();
+	(functions, globals)
+}
+
+struct Inliner<Dst: Marker> {
+	model: Model<Dst>,
+	replacement_map: ReplacementMap<Dst>,
+	functions: FxHashSet<FunctionId>,
+	globals: FxHashSet<Identifier>,
+	map: FxHashMap<DeclarationId, Expression<Dst>>,
+	depth: usize,
+}
+
+impl<Dst: Marker> Folder<'_, Dst> for Inliner<Dst> {
+	fn model(&mut self) -> &mut Model<Dst> {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<Dst> {
+		&mut self.replacement_map
+	}
+
+	fn fold_declaration(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model,
+		d: &Declaration,
+	) -> Declaration<Dst> {
+		let mut folded = fold_declaration(self, db, model, d);
+		if !d.top_level() && (self.depth > 0 || d.name().is_some_and(|n| self.globals.contains(&n)))
+		{
+			// Alpha rename for safety when inlining
+			folded.remove_name();
+		}
+		folded
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model,
+		expression: &Expression,
+	) -> Expression<Dst> {
+		maybe_grow_stack(|| {
+			match &**expression {
+				ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => {
+					if let Some(e) = self.map.get(d) {
+						return e.clone();
+					}
+				}
+				ExpressionData::Call(c) => {
+					if let Callable::Function(f) = &c.function {
+						if self.functions.contains(f) {
+							return self.inline(db, model, expression, *f, &c.arguments);
+						}
+					}
+				}
+				_ => (),
+			}
+			fold_expression(self, db, model, expression)
+		})
+	}
+}
+
+impl<Dst: Marker> Inliner<Dst> {
+	/// Replace a call to the function `f` with its body
+	fn inline(
+		&mut self,
+		db: &dyn Thir,
+		model: &Model,
+		call: &Expression,
+		f: FunctionId,
+		arguments: &[Expression],
+	) -> Expression<Dst> {
+		log::debug!("Inlining {}", model[f].name().pretty_print(db));
+		let origin = call.origin();
+		let mut items = Vec::new();
+		let mut restore = Vec::with_capacity(arguments.len());
+		for (param, arg) in model[f].parameters().iter().zip(arguments) {
+			let folded = self.fold_expression(db, model, arg);
+			let value = match &*folded {
+				ExpressionData::Identifier(_)
+				| ExpressionData::BooleanLiteral(_)
+				| ExpressionData::IntegerLiteral(_)
+				| ExpressionData::FloatLiteral(_)
+				| ExpressionData::StringLiteral(_)
+				| ExpressionData::Absent
+				| ExpressionData::Infinity => folded,
+				_ => {
+					let declaration = Declaration::from_expression(db, false, folded);
+					let idx = self.model.add_declaration(Item::new(declaration, origin));
+					items.push(LetItem::Declaration(idx));
+					Expression::new(db, &self.model, origin, idx)
+				}
+			};
+			restore.push(self.map.insert(*param, value));
+		}
+		self.depth += 1;
+		let body = self.fold_expression(db, model, model[f].body().unwrap());
+		self.depth -= 1;
+		for (param, prev) in model[f].parameters().iter().zip(restore) {
+			if let Some(prev) = prev {
+				self.map.insert(*param, prev);
+			} else {
+				self.map.remove(param);
+			}
+		}
+		let inlined = if items.is_empty() {
+			body
+		} else {
+			Expression::new(
+				db,
+				&self.model,
+				origin,
+				Let {
+					items,
+					in_expression: Box::new(body),
+				},
+			)
+		};
+		add_coercion(db, &mut self.model, call.ty(), inlined)
+	}
+}
+
+/// Inline calls to small, non-recursive functions defined in the input models
+pub fn inline_functions(db: &dyn Thir, model: Model) -> Result<Model> {
+	log::info!("Inlining small functions");
+	let (functions, globals) = inlinable_functions(db, &model);
+	let mut inliner = Inliner {
+		replacement_map: ReplacementMap::default(),
+		model: Model::with_capacities(&model.entity_counts()),
+		functions,
+		globals,
+		map: FxHashMap::default(),
+		depth: 0,
+	};
+	inliner.add_model(db, &model);
+	Ok(inliner.model)
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::inline_functions;
+	use crate::thir::transform::test::check;
+
+	#[test]
+	fn test_inline_functions() {
+		check(
+			inline_functions,
+			r#"
+				function var int: double(var int: a) = a + a;
+				function var int: plus_n(var int: a) = a + n;
+				function int: fact(int: i) = if i <= 1 then 1 else i * fact(i - 1) endif;
+				var 1..3: x;
+				var 1..3: y;
+				int: n = 3;
+				constraint double(x) < double(x + y);
+				constraint let { int: n = 2; } in plus_n(n) > fact(n);
+			"#,
+			expect![[r#"
+    function var int: double(var int: a) = '+'(a, a);
+    int: n = 3;
+    function var int: plus_n(var int: a) = '+'(a, n);
+    function int: fact(int: i) = if '<='(i, 1) then 1 else '*'(i, fact('-'(i, 1))) endif;
+    var '..'(1, 3): x;
+    var '..'(1, 3): y;
+    constraint '<'('+'(x, x), let {
+      var int: _DECL_1 = '+'(x, y);
+    } in '+'(_DECL_1, _DECL_1));
+    constraint let {
+      int: _DECL_2 = 2;
+    } in '>'('+'(_DECL_2, n), fact(_DECL_2));
+"#]],
+		)
+	}
+}
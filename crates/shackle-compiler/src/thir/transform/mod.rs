@@ -4,26 +4,46 @@
 //! The `crate::thir::Visitor` and `crate::thir::Folder` traits are useful for implementing these.
 //! It is the responsibility of implementors to know what constructs are expected to be present at the stage they run.
 
+use std::{
+	fmt,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use miette::Diagnostic;
+use rustc_hash::FxHashSet;
+
 use self::{
-	call_by_name::inline_call_by_name, capturing_fn::decapture_model,
-	comprehension::desugar_comprehension, domain_constraint::rewrite_domains,
-	erase_enum::erase_enum, erase_opt::erase_opt, erase_record::erase_record,
-	function_dispatch::function_dispatch, name_mangle::mangle_names, output::generate_output,
-	top_down_type::top_down_type, type_specialise::type_specialise,
+	bounds::infer_bounds, call_by_name::inline_call_by_name, capturing_fn::decapture_model,
+	case_compile::compile_case, common_subexpression::eliminate_common_subexpressions,
+	comprehension::desugar_comprehension, dead_code::eliminate_dead_code,
+	domain_constraint::rewrite_domains, erase_enum::erase_enum, erase_opt::erase_opt,
+	erase_record::erase_record, function_dispatch::function_dispatch, inline::inline_functions,
+	name_mangle::mangle_names, output::generate_output, top_down_type::top_down_type,
+	type_specialise::type_specialise,
+};
+use super::{db::Thir, sanity_check::sanity_check_model, Model};
+use crate::{
+	diagnostics::{DuplicatePass, UnknownPass},
+	Error, Result,
 };
-use super::{db::Thir, Model};
-use crate::Result;
 
+pub mod bounds;
 pub mod call_by_name;
 pub mod capturing_fn;
+pub mod case_compile;
+pub mod common_subexpression;
 pub mod comprehension;
+pub mod dead_code;
 pub mod domain_constraint;
 pub mod erase_enum;
 pub mod erase_opt;
 pub mod erase_record;
 pub mod function_dispatch;
+pub mod inline;
 pub mod name_mangle;
 pub mod output;
+pub mod partial_evaluation;
 pub mod top_down_type;
 pub mod type_specialise;
 
@@ -39,22 +59,272 @@ pub fn transformer(transforms: Vec<TransformFn>) -> impl FnMut(&dyn Thir, Model)
 	}
 }
 
+/// A THIR transform which may capture state
+pub type DynTransformFn = Arc<dyn Fn(&dyn Thir, Model) -> Result<Model> + Send + Sync>;
+
+/// A THIR transform which is part of the default pipeline
+#[derive(Clone, Copy, Debug)]
+pub struct BuiltinPass {
+	/// The name used to refer to the pass (e.g. from the command line)
+	pub name: &'static str,
+	/// The transform function
+	pub transform: TransformFn,
+}
+
+/// A named THIR transform
+#[derive(Clone)]
+pub struct Pass {
+	/// The name used to refer to the pass (e.g. from the command line)
+	pub name: String,
+	/// The transform function
+	pub transform: DynTransformFn,
+}
+
+impl Pass {
+	/// Create a pass with the given name which runs the given transform
+	pub fn new(
+		name: impl Into<String>,
+		transform: impl Fn(&dyn Thir, Model) -> Result<Model> + Send + Sync + 'static,
+	) -> Self {
+		Self {
+			name: name.into(),
+			transform: Arc::new(transform),
+		}
+	}
+}
+
+impl fmt::Debug for Pass {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Pass")
+			.field("name", &self.name)
+			.finish_non_exhaustive()
+	}
+}
+
+impl From<&BuiltinPass> for Pass {
+	fn from(pass: &BuiltinPass) -> Self {
+		Pass::new(pass.name, pass.transform)
+	}
+}
+
+macro_rules! passes {
+	($($pass:ident),* $(,)?) => {
+		&[$(BuiltinPass { name: stringify!($pass), transform: $pass }),*]
+	};
+}
+
+/// The THIR transforms in the order they are run by default
+pub const THIR_PASSES: &[BuiltinPass] = passes![
+	eliminate_dead_code,
+	generate_output,
+	infer_bounds,
+	rewrite_domains,
+	top_down_type,
+	compile_case,
+	type_specialise,
+	function_dispatch,
+	mangle_names,
+	erase_record,
+	erase_enum,
+	desugar_comprehension,
+	erase_opt,
+	inline_functions,
+	eliminate_common_subexpressions,
+	inline_call_by_name,
+	decapture_model,
+];
+
+/// Find the pass with the given name in the default pipeline
+pub fn find_pass(name: &str) -> Option<&'static BuiltinPass> {
+	THIR_PASSES.iter().find(|p| p.name == name)
+}
+
 /// Get the default THIR transformer
 pub fn thir_transforms() -> impl FnMut(&dyn Thir, Model) -> Result<Model> {
-	transformer(vec![
-		generate_output,
-		rewrite_domains,
-		top_down_type,
-		type_specialise,
-		function_dispatch,
-		mangle_names,
-		erase_record,
-		erase_enum,
-		desugar_comprehension,
-		erase_opt,
-		inline_call_by_name,
-		decapture_model,
-	])
+	transformer(THIR_PASSES.iter().map(|p| p.transform).collect())
+}
+
+/// A position in the pipeline, relative to a named pass
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InsertionPoint {
+	/// Immediately before the named pass
+	Before(String),
+	/// Immediately after the named pass
+	After(String),
+}
+
+/// Get the passes of the default pipeline with the given extra passes inserted.
+///
+/// Passes are inserted in order, so may be positioned relative to previously inserted passes.
+/// Passes inserted at the same point run in the order they are given. The name of an inserted
+/// pass must not be used by any other pass.
+pub fn pipeline_passes(extra: &[(InsertionPoint, Pass)]) -> Result<Vec<Pass>> {
+	let mut passes = THIR_PASSES
+		.iter()
+		.map(|p| (Pass::from(p), None))
+		.collect::<Vec<(Pass, Option<&str>)>>();
+	for (point, pass) in extra.iter() {
+		if passes.iter().any(|(p, _)| p.name == pass.name) {
+			return Err(DuplicatePass {
+				name: pass.name.clone(),
+			}
+			.into());
+		}
+		let name = match point {
+			InsertionPoint::Before(name) | InsertionPoint::After(name) => name.as_str(),
+		};
+		let idx = passes
+			.iter()
+			.position(|(p, _)| p.name == name)
+			.ok_or_else(|| unknown_pass(name, passes.iter().map(|(p, _)| p)))?;
+		match point {
+			InsertionPoint::Before(_) => passes.insert(idx, (pass.clone(), None)),
+			InsertionPoint::After(_) => {
+				let offset = passes[idx + 1..]
+					.iter()
+					.take_while(|(_, after)| *after == Some(name))
+					.count();
+				passes.insert(idx + 1 + offset, (pass.clone(), Some(name)));
+			}
+		}
+	}
+	Ok(passes.into_iter().map(|(p, _)| p).collect())
+}
+
+fn unknown_pass<'a>(name: &str, passes: impl Iterator<Item = &'a Pass>) -> Error {
+	UnknownPass {
+		name: name.to_owned(),
+		available: passes
+			.map(|p| p.name.as_str())
+			.collect::<Vec<_>>()
+			.join(", "),
+	}
+	.into()
+}
+
+/// Options for inspecting the THIR pipeline
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineOptions {
+	/// The names of the passes after which the model should be dumped
+	pub dump_after: Vec<String>,
+	/// The name of the pass after which to stop running the pipeline
+	pub stop_after: Option<String>,
+	/// Whether to sanity check the model after every pass
+	pub sanity_check: bool,
+}
+
+impl PipelineOptions {
+	/// Whether these options change the behaviour of the default pipeline
+	pub fn is_default(&self) -> bool {
+		self == &Self::default()
+	}
+
+	/// Get the names given in these options which do not refer to a pass in the default pipeline
+	pub fn unknown_passes(&self) -> impl '_ + Iterator<Item = &str> {
+		self.dump_after
+			.iter()
+			.chain(self.stop_after.iter())
+			.map(|n| n.as_str())
+			.filter(|n| find_pass(n).is_none())
+	}
+
+	/// Get the names given in these options which do not refer to one of the given passes
+	pub fn unknown_passes_in<'a>(
+		&'a self,
+		passes: &'a [Pass],
+	) -> impl 'a + Iterator<Item = &'a str> {
+		self.dump_after
+			.iter()
+			.chain(self.stop_after.iter())
+			.map(|n| n.as_str())
+			.filter(|n| !passes.iter().any(|p| p.name == *n))
+	}
+}
+
+/// Time taken to run a THIR pass
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassTiming {
+	/// The name of the pass
+	pub name: String,
+	/// The time spent running the pass
+	pub duration: Duration,
+}
+
+/// Run the given THIR passes using the given inspection options.
+///
+/// The `dump` function is called with the name of the pass and the model after each
+/// pass listed in `dump_after`. The time taken by each pass which was run is added
+/// to `timings`.
+///
+/// When sanity checking, a warning is logged for each pass which introduces errors into
+/// the pretty printed model.
+pub fn run_pipeline(
+	db: &dyn Thir,
+	model: Model,
+	passes: &[Pass],
+	options: &PipelineOptions,
+	mut dump: impl FnMut(&str, &Model),
+	timings: &mut Vec<PassTiming>,
+) -> Result<Model> {
+	if let Some(name) = options.unknown_passes_in(passes).next() {
+		return Err(unknown_pass(name, passes.iter()));
+	}
+	let mut model = model;
+	let mut known_errors = if options.sanity_check {
+		sanity_errors(db, &model)
+	} else {
+		FxHashSet::default()
+	};
+	for pass in passes {
+		log::debug!("Running THIR pass {}", pass.name);
+		let start = Instant::now();
+		model = (pass.transform)(db, model)?;
+		timings.push(PassTiming {
+			name: pass.name.clone(),
+			duration: start.elapsed(),
+		});
+		if options.sanity_check {
+			let errors = sanity_errors(db, &model);
+			let mut introduced = errors
+				.iter()
+				.filter(|e| !known_errors.contains(*e))
+				.map(|e| e.as_str())
+				.collect::<Vec<_>>();
+			introduced.sort_unstable();
+			if !introduced.is_empty() {
+				log::warn!(
+					"Model failed sanity check after pass {}:\n{}",
+					pass.name,
+					introduced.join("\n")
+				);
+			}
+			known_errors = errors;
+		}
+		if options.dump_after.contains(&pass.name) {
+			dump(&pass.name, &model);
+		}
+		if options.stop_after.as_ref() == Some(&pass.name) {
+			break;
+		}
+	}
+	Ok(model)
+}
+
+/// Get the messages of the errors found by sanity checking the given model
+fn sanity_errors(db: &dyn Thir, model: &Model) -> FxHashSet<String> {
+	sanity_check_model(db, model)
+		.iter()
+		.map(|e| {
+			let labels = e
+				.labels()
+				.into_iter()
+				.flatten()
+				.filter_map(|l| l.label().map(|l| l.to_owned()))
+				.collect::<Vec<_>>()
+				.join(", ");
+			format!("{}: {}", e, labels)
+		})
+		.collect()
 }
 
 #[cfg(test)]
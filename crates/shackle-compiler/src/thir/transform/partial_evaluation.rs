@@ -0,0 +1,946 @@
+//! Compile-time evaluation of par expressions once the instance data is known.
+//!
+//! Top-level par expressions which only depend on literals, data and other par declarations
+//! are evaluated and replaced by their values. Comprehensions over known collections, `assert`
+//! calls and conditions of if-then-else expressions are evaluated too, and assertions which fail
+//! are reported as errors located at the `assert` call.
+//!
+//! Anything which cannot be evaluated (e.g. because it uses a function without a known
+//! implementation) is left untouched for the solver to handle. Unlike the other transforms,
+//! this is not part of the default pipeline since it requires the data.
+
+use std::{cmp::Ordering, ops::RangeInclusive, slice, sync::Arc};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+	diagnostics::AssertionFailure,
+	hir::{BooleanLiteral, FloatLiteral, Identifier, IntegerLiteral, StringLiteral},
+	thir::{
+		db::Thir,
+		source::Origin,
+		traverse::{fold_expression, fold_function_body, Folder, ReplacementMap},
+		ArrayComprehension, ArrayLiteral, Call, Callable, ConstraintId, DeclarationId, Expression,
+		ExpressionData, FunctionId, FunctionName, Generator, Item, Let, LetItem, Model,
+		ResolvedIdentifier, SetLiteral, TupleLiteral,
+	},
+	utils::maybe_grow_stack,
+	Error, Result,
+};
+
+/// Maximum number of evaluation steps for a single top-level expression
+const STEP_LIMIT: usize = 1_000_000;
+
+/// Maximum cardinality of a set which gets folded into a set literal
+const SET_LITERAL_LIMIT: i64 = 64;
+
+/// A par value known at compile time
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParValue {
+	/// Boolean value
+	Boolean(bool),
+	/// Integer value
+	Integer(i64),
+	/// Float value
+	Float(f64),
+	/// String value
+	String(Arc<str>),
+	/// Set of integers as a list of ranges
+	IntegerSet(Vec<RangeInclusive<i64>>),
+	/// Array with the index set of each dimension, and the elements in row-major order
+	Array(Vec<RangeInclusive<i64>>, Vec<ParValue>),
+	/// Tuple value
+	Tuple(Vec<ParValue>),
+}
+
+impl ParValue {
+	/// Create a set of integers from the given ranges (which may overlap or be empty)
+	pub fn integer_set(ranges: impl IntoIterator<Item = RangeInclusive<i64>>) -> Self {
+		let mut ranges = ranges
+			.into_iter()
+			.filter(|r| !r.is_empty())
+			.collect::<Vec<_>>();
+		ranges.sort_by_key(|r| *r.start());
+		let mut result: Vec<RangeInclusive<i64>> = Vec::with_capacity(ranges.len());
+		for r in ranges {
+			match result.last_mut() {
+				Some(last) if *r.start() <= last.end().saturating_add(1) => {
+					*last = *last.start()..=*last.end().max(r.end());
+				}
+				_ => result.push(r),
+			}
+		}
+		ParValue::IntegerSet(result)
+	}
+
+	/// Create a one dimensional array indexed from 1
+	pub fn array(elements: Vec<ParValue>) -> Self {
+		ParValue::Array(vec![1..=elements.len() as i64], elements)
+	}
+
+	/// Create a literal expression for this value
+	fn to_expression(&self, db: &dyn Thir, model: &Model, origin: Origin) -> Option<Expression> {
+		Some(match self {
+			ParValue::Boolean(b) => Expression::new(db, model, origin, BooleanLiteral(*b)),
+			ParValue::Integer(i) => Expression::new(db, model, origin, IntegerLiteral(*i)),
+			ParValue::Float(f) => Expression::new(db, model, origin, FloatLiteral::new(*f)),
+			ParValue::String(s) => Expression::new(
+				db,
+				model,
+				origin,
+				StringLiteral::new(s.as_ref(), db.upcast()),
+			),
+			ParValue::IntegerSet(rs) => {
+				if rs.is_empty() || cardinality(rs)? > SET_LITERAL_LIMIT {
+					return None;
+				}
+				Expression::new(
+					db,
+					model,
+					origin,
+					SetLiteral(
+						rs.iter()
+							.flat_map(|r| r.clone())
+							.map(|i| Expression::new(db, model, origin, IntegerLiteral(i)))
+							.collect(),
+					),
+				)
+			}
+			ParValue::Array(index_sets, elements) => {
+				if index_sets.len() != 1 || *index_sets[0].start() != 1 || elements.is_empty() {
+					return None;
+				}
+				Expression::new(
+					db,
+					model,
+					origin,
+					ArrayLiteral(
+						elements
+							.iter()
+							.map(|e| e.to_expression(db, model, origin))
+							.collect::<Option<_>>()?,
+					),
+				)
+			}
+			ParValue::Tuple(fields) => Expression::new(
+				db,
+				model,
+				origin,
+				TupleLiteral(
+					fields
+						.iter()
+						.map(|f| f.to_expression(db, model, origin))
+						.collect::<Option<_>>()?,
+				),
+			),
+		})
+	}
+}
+
+/// Reason why an expression could not be evaluated
+enum EvaluationError {
+	/// The value of the expression is not known at compile time
+	Unknown,
+	/// An assertion failed
+	Assertion(Origin, String),
+}
+
+type Evaluation<T> = std::result::Result<T, EvaluationError>;
+
+/// Evaluates par expressions of a model
+struct Evaluator<'a> {
+	db: &'a dyn Thir,
+	model: &'a Model,
+	data: &'a FxHashMap<Identifier, ParValue>,
+	globals: FxHashMap<DeclarationId, Option<ParValue>>,
+	in_progress: FxHashSet<DeclarationId>,
+	locals: FxHashMap<DeclarationId, ParValue>,
+	steps: usize,
+}
+
+impl<'a> Evaluator<'a> {
+	fn tick(&mut self) -> Evaluation<()> {
+		self.steps += 1;
+		if self.steps > STEP_LIMIT {
+			return Err(EvaluationError::Unknown);
+		}
+		Ok(())
+	}
+
+	fn bind(&mut self, declaration: DeclarationId, value: ParValue) -> Option<ParValue> {
+		self.locals.insert(declaration, value)
+	}
+
+	fn unbind(&mut self, declaration: DeclarationId, previous: Option<ParValue>) {
+		match previous {
+			Some(v) => self.locals.insert(declaration, v),
+			None => self.locals.remove(&declaration),
+		};
+	}
+
+	fn eval(&mut self, expression: &'a Expression) -> Evaluation<ParValue> {
+		maybe_grow_stack(|| {
+			self.tick()?;
+			if !expression.ty().known_par(self.db.upcast()) {
+				return Err(EvaluationError::Unknown);
+			}
+			match &**expression {
+				ExpressionData::BooleanLiteral(b) => Ok(ParValue::Boolean(b.0)),
+				ExpressionData::IntegerLiteral(i) => Ok(ParValue::Integer(i.0)),
+				ExpressionData::FloatLiteral(f) => Ok(ParValue::Float(f.value())),
+				ExpressionData::StringLiteral(s) => {
+					Ok(ParValue::String(s.value(self.db.upcast()).into()))
+				}
+				ExpressionData::Identifier(ResolvedIdentifier::Declaration(d)) => {
+					self.declaration(*d)
+				}
+				ExpressionData::ArrayLiteral(al) => Ok(ParValue::array(
+					al.iter().map(|e| self.eval(e)).collect::<Evaluation<_>>()?,
+				)),
+				ExpressionData::SetLiteral(sl) => {
+					let mut members = Vec::with_capacity(sl.len());
+					for e in sl.iter() {
+						match self.eval(e)? {
+							ParValue::Integer(i) => members.push(i..=i),
+							_ => return Err(EvaluationError::Unknown),
+						}
+					}
+					Ok(ParValue::integer_set(members))
+				}
+				ExpressionData::TupleLiteral(tl) => Ok(ParValue::Tuple(
+					tl.iter().map(|e| self.eval(e)).collect::<Evaluation<_>>()?,
+				)),
+				ExpressionData::ArrayComprehension(c) => self.array_comprehension(c),
+				ExpressionData::SetComprehension(c) => {
+					let mut members = Vec::new();
+					self.generate(&c.generators, &mut |s| {
+						match s.eval(&c.template)? {
+							ParValue::Integer(i) => members.push(i..=i),
+							_ => return Err(EvaluationError::Unknown),
+						}
+						Ok(())
+					})?;
+					Ok(ParValue::integer_set(members))
+				}
+				ExpressionData::ArrayAccess(aa) => {
+					let collection = self.eval(&aa.collection)?;
+					let indices = match self.eval(&aa.indices)? {
+						ParValue::Integer(i) => vec![i],
+						ParValue::Tuple(fs) => fs
+							.into_iter()
+							.map(|f| match f {
+								ParValue::Integer(i) => Ok(i),
+								_ => Err(EvaluationError::Unknown),
+							})
+							.collect::<Evaluation<_>>()?,
+						_ => return Err(EvaluationError::Unknown),
+					};
+					array_access(collection, &indices).ok_or(EvaluationError::Unknown)
+				}
+				ExpressionData::TupleAccess(ta) => match self.eval(&ta.tuple)? {
+					ParValue::Tuple(mut fs) => {
+						let i = usize::try_from(ta.field.0 - 1)
+							.map_err(|_| EvaluationError::Unknown)?;
+						if i < fs.len() {
+							Ok(fs.swap_remove(i))
+						} else {
+							Err(EvaluationError::Unknown)
+						}
+					}
+					_ => Err(EvaluationError::Unknown),
+				},
+				ExpressionData::IfThenElse(ite) => {
+					for branch in ite.branches.iter() {
+						match self.eval(&branch.condition)? {
+							ParValue::Boolean(true) => return self.eval(&branch.result),
+							ParValue::Boolean(false) => (),
+							_ => return Err(EvaluationError::Unknown),
+						}
+					}
+					self.eval(&ite.else_result)
+				}
+				ExpressionData::Call(c) => self.call(expression, c),
+				ExpressionData::Let(l) => {
+					let mut bound = Vec::new();
+					let result = self.eval_let(l, &mut bound);
+					for (d, previous) in bound.into_iter().rev() {
+						self.unbind(d, previous);
+					}
+					result
+				}
+				_ => Err(EvaluationError::Unknown),
+			}
+		})
+	}
+
+	fn declaration(&mut self, d: DeclarationId) -> Evaluation<ParValue> {
+		if let Some(value) = self.locals.get(&d) {
+			return Ok(value.clone());
+		}
+		let declaration = &self.model[d];
+		if !declaration.top_level() {
+			return Err(EvaluationError::Unknown);
+		}
+		if let Some(value) = self.globals.get(&d) {
+			return value.clone().ok_or(EvaluationError::Unknown);
+		}
+		if !declaration.ty().known_par(self.db.upcast()) || !self.in_progress.insert(d) {
+			return Err(EvaluationError::Unknown);
+		}
+		let result = match declaration.definition() {
+			Some(definition) => self.eval(definition),
+			None => declaration
+				.name()
+				.and_then(|name| self.data.get(&name))
+				.cloned()
+				.ok_or(EvaluationError::Unknown),
+		};
+		self.in_progress.remove(&d);
+		if self.steps <= STEP_LIMIT {
+			self.globals.insert(d, result.as_ref().ok().cloned());
+		}
+		result
+	}
+
+	fn eval_let(
+		&mut self,
+		l: &'a Let,
+		bound: &mut Vec<(DeclarationId, Option<ParValue>)>,
+	) -> Evaluation<ParValue> {
+		for item in l.items.iter() {
+			match item {
+				LetItem::Declaration(d) => {
+					let definition = self.model[*d]
+						.definition()
+						.ok_or(EvaluationError::Unknown)?;
+					let value = self.eval(definition)?;
+					bound.push((*d, self.bind(*d, value)));
+				}
+				LetItem::Constraint(c) => self.constraint(*c)?,
+			}
+		}
+		self.eval(&l.in_expression)
+	}
+
+	fn constraint(&mut self, c: ConstraintId) -> Evaluation<()> {
+		match self.eval(self.model[c].expression())? {
+			ParValue::Boolean(true) => Ok(()),
+			_ => Err(EvaluationError::Unknown),
+		}
+	}
+
+	fn array_comprehension(&mut self, c: &'a ArrayComprehension) -> Evaluation<ParValue> {
+		if c.indices.is_some() {
+			return Err(EvaluationError::Unknown);
+		}
+		let mut elements = Vec::new();
+		self.generate(&c.generators, &mut |s| {
+			elements.push(s.eval(&c.template)?);
+			Ok(())
+		})?;
+		Ok(ParValue::array(elements))
+	}
+
+	/// Call `f` for every assignment of the generators
+	fn generate(
+		&mut self,
+		generators: &'a [Generator],
+		f: &mut dyn FnMut(&mut Self) -> Evaluation<()>,
+	) -> Evaluation<()> {
+		let (generator, rest) = match generators.split_first() {
+			Some(split) => split,
+			None => return f(self),
+		};
+		match generator {
+			Generator::Iterator {
+				declarations,
+				collection,
+				where_clause,
+			} => {
+				let values = match self.eval(collection)? {
+					ParValue::IntegerSet(rs) => {
+						if !matches!(cardinality(&rs), Some(c) if c <= STEP_LIMIT as i64) {
+							return Err(EvaluationError::Unknown);
+						}
+						rs.into_iter()
+							.flatten()
+							.map(ParValue::Integer)
+							.collect::<Vec<_>>()
+					}
+					ParValue::Array(_, elements) => elements,
+					_ => return Err(EvaluationError::Unknown),
+				};
+				self.iterate(declarations, &values, where_clause.as_ref(), rest, f)
+			}
+			Generator::Assignment {
+				assignment,
+				where_clause,
+			} => {
+				let definition = self.model[*assignment]
+					.definition()
+					.ok_or(EvaluationError::Unknown)?;
+				let value = self.eval(definition)?;
+				let previous = self.bind(*assignment, value);
+				let result = self.filter(where_clause.as_ref(), rest, f);
+				self.unbind(*assignment, previous);
+				result
+			}
+		}
+	}
+
+	fn iterate(
+		&mut self,
+		declarations: &'a [DeclarationId],
+		values: &[ParValue],
+		where_clause: Option<&'a Expression>,
+		rest: &'a [Generator],
+		f: &mut dyn FnMut(&mut Self) -> Evaluation<()>,
+	) -> Evaluation<()> {
+		let (d, ds) = match declarations.split_first() {
+			Some(split) => split,
+			None => return self.filter(where_clause, rest, f),
+		};
+		let previous = self.locals.get(d).cloned();
+		let mut result = Ok(());
+		for value in values {
+			result = self.tick().and_then(|_| {
+				self.bind(*d, value.clone());
+				self.iterate(ds, values, where_clause, rest, f)
+			});
+			if result.is_err() {
+				break;
+			}
+		}
+		self.unbind(*d, previous);
+		result
+	}
+
+	fn filter(
+		&mut self,
+		where_clause: Option<&'a Expression>,
+		rest: &'a [Generator],
+		f: &mut dyn FnMut(&mut Self) -> Evaluation<()>,
+	) -> Evaluation<()> {
+		if let Some(w) = where_clause {
+			match self.eval(w)? {
+				ParValue::Boolean(true) => (),
+				ParValue::Boolean(false) => return Ok(()),
+				_ => return Err(EvaluationError::Unknown),
+			}
+		}
+		self.generate(rest, f)
+	}
+
+	fn call(&mut self, expression: &'a Expression, call: &'a Call) -> Evaluation<ParValue> {
+		let function = match &call.function {
+			Callable::Function(f) => *f,
+			_ => return Err(EvaluationError::Unknown),
+		};
+		let name = match self.model[function].name() {
+			FunctionName::Named(name) => name.lookup(self.db.upcast()),
+			FunctionName::Anonymous(_) => return Err(EvaluationError::Unknown),
+		};
+		let args = &call.arguments;
+		match (name.as_str(), args.len()) {
+			("/\\", 2) | ("\\/", 2) | ("->", 2) => {
+				let short_circuit = match (name.as_str(), self.eval(&args[0])?) {
+					("/\\", ParValue::Boolean(false)) => Some(false),
+					("\\/", ParValue::Boolean(true)) | ("->", ParValue::Boolean(false)) => {
+						Some(true)
+					}
+					(_, ParValue::Boolean(_)) => None,
+					_ => return Err(EvaluationError::Unknown),
+				};
+				if let Some(b) = short_circuit {
+					return Ok(ParValue::Boolean(b));
+				}
+				return match self.eval(&args[1])? {
+					ParValue::Boolean(b) => Ok(ParValue::Boolean(b)),
+					_ => Err(EvaluationError::Unknown),
+				};
+			}
+			("assert", 2) | ("assert", 3) => {
+				return match self.eval(&args[0])? {
+					ParValue::Boolean(true) if args.len() == 3 => self.eval(&args[2]),
+					ParValue::Boolean(true) => Ok(ParValue::Boolean(true)),
+					ParValue::Boolean(false) => {
+						let msg = match self.eval(&args[1]) {
+							Ok(ParValue::String(s)) => s.to_string(),
+							_ => "Assertion failed".to_owned(),
+						};
+						Err(EvaluationError::Assertion(expression.origin(), msg))
+					}
+					_ => Err(EvaluationError::Unknown),
+				};
+			}
+			_ => (),
+		}
+		let values = args
+			.iter()
+			.map(|arg| self.eval(arg))
+			.collect::<Evaluation<Vec<_>>>()?;
+		if let Some(value) = builtin(&name, &values) {
+			return Ok(value);
+		}
+		self.user_function(function, values)
+	}
+
+	fn user_function(&mut self, f: FunctionId, args: Vec<ParValue>) -> Evaluation<ParValue> {
+		let function = &self.model[f];
+		let body = function.body().ok_or(EvaluationError::Unknown)?;
+		if function.parameters().len() != args.len() {
+			return Err(EvaluationError::Unknown);
+		}
+		let bound = function
+			.parameters()
+			.iter()
+			.zip(args)
+			.map(|(p, v)| (*p, self.bind(*p, v)))
+			.collect::<Vec<_>>();
+		let result = self.eval(body);
+		for (p, previous) in bound.into_iter().rev() {
+			self.unbind(p, previous);
+		}
+		result
+	}
+}
+
+/// Get the number of elements in a set of integers
+fn cardinality(ranges: &[RangeInclusive<i64>]) -> Option<i64> {
+	ranges.iter().try_fold(0i64, |acc, r| {
+		r.end()
+			.checked_sub(*r.start())?
+			.checked_add(1)
+			.and_then(|n| acc.checked_add(n))
+	})
+}
+
+/// Lookup an element of an array
+fn array_access(collection: ParValue, indices: &[i64]) -> Option<ParValue> {
+	let (index_sets, mut elements) = match collection {
+		ParValue::Array(index_sets, elements) => (index_sets, elements),
+		_ => return None,
+	};
+	if index_sets.len() != indices.len() {
+		return None;
+	}
+	let mut offset = 0usize;
+	for (index_set, i) in index_sets.iter().zip(indices) {
+		if !index_set.contains(i) {
+			return None;
+		}
+		let len = usize::try_from(cardinality(slice::from_ref(index_set))?).ok()?;
+		offset = offset * len + usize::try_from(i - index_set.start()).ok()?;
+	}
+	if offset < elements.len() {
+		Some(elements.swap_remove(offset))
+	} else {
+		None
+	}
+}
+
+/// Compare two scalar values of the same kind
+fn compare(a: &ParValue, b: &ParValue) -> Option<Ordering> {
+	match (a, b) {
+		(ParValue::Boolean(a), ParValue::Boolean(b)) => Some(a.cmp(b)),
+		(ParValue::Integer(a), ParValue::Integer(b)) => Some(a.cmp(b)),
+		(ParValue::Float(a), ParValue::Float(b)) => a.partial_cmp(b),
+		(ParValue::String(a), ParValue::String(b)) => Some(a.cmp(b)),
+		_ => None,
+	}
+}
+
+/// Create a float value if it is finite
+fn float(f: f64) -> Option<ParValue> {
+	if f.is_finite() {
+		Some(ParValue::Float(f))
+	} else {
+		None
+	}
+}
+
+/// Evaluate a call to a builtin function given its argument values
+fn builtin(name: &str, args: &[ParValue]) -> Option<ParValue> {
+	use ParValue::{Array, Boolean, Float, Integer, IntegerSet};
+	match (name, args) {
+		("+", [Integer(a), Integer(b)]) => a.checked_add(*b).map(Integer),
+		("-", [Integer(a), Integer(b)]) => a.checked_sub(*b).map(Integer),
+		("*", [Integer(a), Integer(b)]) => a.checked_mul(*b).map(Integer),
+		("div", [Integer(a), Integer(b)]) => a.checked_div(*b).map(Integer),
+		("mod", [Integer(a), Integer(b)]) => a.checked_rem(*b).map(Integer),
+		("-", [Integer(a)]) => a.checked_neg().map(Integer),
+		("abs", [Integer(a)]) => a.checked_abs().map(Integer),
+		("+", [Float(a), Float(b)]) => float(a + b),
+		("-", [Float(a), Float(b)]) => float(a - b),
+		("*", [Float(a), Float(b)]) => float(a * b),
+		("/", [Float(a), Float(b)]) => float(a / b),
+		("-", [Float(a)]) => float(-a),
+		("abs", [Float(a)]) => float(a.abs()),
+		("int2float", [Integer(a)]) => float(*a as f64),
+		("bool2int", [Boolean(a)]) => Some(Integer(*a as i64)),
+		("not", [Boolean(a)]) => Some(Boolean(!a)),
+		("/\\", [Boolean(a), Boolean(b)]) => Some(Boolean(*a && *b)),
+		("\\/", [Boolean(a), Boolean(b)]) => Some(Boolean(*a || *b)),
+		("->", [Boolean(a), Boolean(b)]) => Some(Boolean(!a || *b)),
+		("<-", [Boolean(a), Boolean(b)]) => Some(Boolean(*a || !b)),
+		("<->", [Boolean(a), Boolean(b)]) => Some(Boolean(a == b)),
+		("xor", [Boolean(a), Boolean(b)]) => Some(Boolean(a != b)),
+		("=" | "!=", [a, b]) if std::mem::discriminant(a) == std::mem::discriminant(b) => {
+			Some(Boolean((a == b) == (name == "=")))
+		}
+		("<" | "<=" | ">" | ">=", [a, b]) => {
+			let ordering = compare(a, b)?;
+			Some(Boolean(match name {
+				"<" => ordering.is_lt(),
+				"<=" => ordering.is_le(),
+				">" => ordering.is_gt(),
+				_ => ordering.is_ge(),
+			}))
+		}
+		("min" | "max", [a, b]) => {
+			let ordering = compare(a, b)?;
+			Some(if (ordering.is_le()) == (name == "min") {
+				a.clone()
+			} else {
+				b.clone()
+			})
+		}
+		("..", [Integer(a), Integer(b)]) => Some(ParValue::integer_set([*a..=*b])),
+		("in", [Integer(a), IntegerSet(s)]) => Some(Boolean(s.iter().any(|r| r.contains(a)))),
+		("card", [IntegerSet(s)]) => cardinality(s).map(Integer),
+		("union", [IntegerSet(a), IntegerSet(b)]) => {
+			Some(ParValue::integer_set(a.iter().chain(b.iter()).cloned()))
+		}
+		("intersect", [IntegerSet(a), IntegerSet(b)]) => Some(ParValue::integer_set(
+			a.iter()
+				.flat_map(|x| {
+					b.iter()
+						.map(move |y| *x.start().max(y.start())..=*x.end().min(y.end()))
+				})
+				.collect::<Vec<_>>(),
+		)),
+		("diff", [IntegerSet(a), IntegerSet(b)]) => {
+			let mut result = a.clone();
+			for y in b.iter() {
+				result = result
+					.into_iter()
+					.flat_map(|x| {
+						[
+							*x.start()..=(*x.end()).min(y.start().saturating_sub(1)),
+							(*x.start()).max(y.end().saturating_add(1))..=*x.end(),
+						]
+					})
+					.filter(|r| !r.is_empty())
+					.collect();
+			}
+			Some(ParValue::integer_set(result))
+		}
+		("min", [IntegerSet(s)]) => s.first().map(|r| Integer(*r.start())),
+		("max", [IntegerSet(s)]) => s.last().map(|r| Integer(*r.end())),
+		("index_set", [Array(index_sets, _)]) if index_sets.len() == 1 => {
+			Some(ParValue::integer_set([index_sets[0].clone()]))
+		}
+		("length", [Array(_, es)]) => Some(Integer(es.len() as i64)),
+		("array1d", [Array(_, es)]) => Some(ParValue::array(es.clone())),
+		("sum", [Array(_, es)]) => match es.first() {
+			Some(Float(_)) => es.iter().try_fold(Float(0.0), |acc, e| match (acc, e) {
+				(Float(a), Float(b)) => float(a + b),
+				_ => None,
+			}),
+			_ => es.iter().try_fold(Integer(0), |acc, e| match (acc, e) {
+				(Integer(a), Integer(b)) => a.checked_add(*b).map(Integer),
+				_ => None,
+			}),
+		},
+		("product", [Array(_, es)]) => match es.first() {
+			Some(Float(_)) => es.iter().try_fold(Float(1.0), |acc, e| match (acc, e) {
+				(Float(a), Float(b)) => float(a * b),
+				_ => None,
+			}),
+			_ => es.iter().try_fold(Integer(1), |acc, e| match (acc, e) {
+				(Integer(a), Integer(b)) => a.checked_mul(*b).map(Integer),
+				_ => None,
+			}),
+		},
+		("forall" | "exists", [Array(_, es)]) => {
+			let forall = name == "forall";
+			let mut result = forall;
+			for e in es.iter() {
+				match e {
+					Boolean(b) if *b != forall => result = *b,
+					Boolean(_) => (),
+					_ => return None,
+				}
+			}
+			Some(Boolean(result))
+		}
+		("min" | "max", [Array(_, es)]) => {
+			let mut iter = es.iter();
+			let first = iter.next()?.clone();
+			iter.try_fold(first, |acc, e| builtin(name, &[acc, e.clone()]))
+		}
+		("++", [ParValue::String(a), ParValue::String(b)]) => {
+			Some(ParValue::String(format!("{}{}", a, b).into()))
+		}
+		("++", [Array(ia, a), Array(ib, b)]) if ia.len() == 1 && ib.len() == 1 => {
+			Some(ParValue::array(a.iter().chain(b.iter()).cloned().collect()))
+		}
+		("concat", [Array(_, es)]) => {
+			let mut result = String::new();
+			for e in es.iter() {
+				match e {
+					ParValue::String(s) => result.push_str(s),
+					_ => return None,
+				}
+			}
+			Some(ParValue::String(result.into()))
+		}
+		("show", [Integer(i)]) => Some(ParValue::String(i.to_string().into())),
+		("show", [Boolean(b)]) => Some(ParValue::String(b.to_string().into())),
+		("show", [IntegerSet(s)]) => match s.as_slice() {
+			[] => Some(ParValue::String("{}".into())),
+			[r] => Some(ParValue::String(
+				format!("{}..{}", r.start(), r.end()).into(),
+			)),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Folds evaluated expressions into literals
+struct PartialEvaluator<'a> {
+	model: Model,
+	replacement_map: ReplacementMap<()>,
+	evaluator: Evaluator<'a>,
+	enabled: bool,
+	failures: Vec<(Origin, String)>,
+}
+
+impl<'a> PartialEvaluator<'a> {
+	/// Evaluate the expression, returning a literal if it should be replaced by its value
+	fn evaluate(&mut self, db: &'a dyn Thir, expression: &'a Expression) -> Option<Expression> {
+		self.evaluator.steps = 0;
+		match self.evaluator.eval(expression) {
+			Ok(value) => {
+				if let ExpressionData::Call(Call {
+					function: Callable::Function(f),
+					..
+				}) = &**expression
+				{
+					// Keep ranges as they are (but with folded bounds)
+					if self.evaluator.model[*f].name()
+						== FunctionName::new(db.identifier_registry().dot_dot)
+					{
+						return None;
+					}
+				}
+				value
+					.to_expression(db, &self.model, expression.origin())
+					.filter(|e| e.ty() == expression.ty())
+			}
+			Err(EvaluationError::Assertion(origin, msg)) => {
+				if !self.failures.contains(&(origin, msg.clone())) {
+					self.failures.push((origin, msg));
+				}
+				None
+			}
+			Err(EvaluationError::Unknown) => None,
+		}
+	}
+}
+
+impl<'a> Folder<'a, ()> for PartialEvaluator<'a> {
+	fn model(&mut self) -> &mut Model {
+		&mut self.model
+	}
+
+	fn replacement_map(&mut self) -> &mut ReplacementMap<()> {
+		&mut self.replacement_map
+	}
+
+	fn add_constraint(&mut self, db: &'a dyn Thir, model: &'a Model, c: ConstraintId) {
+		let constraint = self.fold_constraint(db, model, &model[c]);
+		if constraint.top_level()
+			&& constraint.annotations().is_empty()
+			&& matches!(
+				&**constraint.expression(),
+				ExpressionData::BooleanLiteral(BooleanLiteral(true))
+			) {
+			// Constraint is trivially satisfied
+			return;
+		}
+		let idx = self
+			.model
+			.add_constraint(Item::new(constraint, model[c].origin()));
+		self.replacement_map.insert_constraint(c, idx);
+	}
+
+	fn fold_function_body(&mut self, db: &'a dyn Thir, model: &'a Model, f: FunctionId) {
+		let enabled = std::mem::replace(&mut self.enabled, false);
+		fold_function_body(self, db, model, f);
+		self.enabled = enabled;
+	}
+
+	fn fold_expression(
+		&mut self,
+		db: &'a dyn Thir,
+		model: &'a Model,
+		expression: &'a Expression,
+	) -> Expression {
+		maybe_grow_stack(|| {
+			if !self.enabled || !expression.annotations().is_empty() {
+				return fold_expression(self, db, model, expression);
+			}
+			let is_literal = matches!(
+				&**expression,
+				ExpressionData::BooleanLiteral(_)
+					| ExpressionData::IntegerLiteral(_)
+					| ExpressionData::FloatLiteral(_)
+					| ExpressionData::StringLiteral(_)
+			);
+			if !is_literal && expression.ty().known_par(db.upcast()) {
+				let failures = self.failures.len();
+				if let Some(literal) = self.evaluate(db, expression) {
+					return literal;
+				}
+				if self.failures.len() > failures {
+					// Don't report the same failure again for subexpressions
+					self.enabled = false;
+					let folded = fold_expression(self, db, model, expression);
+					self.enabled = true;
+					return folded;
+				}
+			}
+			if let ExpressionData::IfThenElse(ite) = &**expression {
+				// Select the branch if the conditions are known
+				self.evaluator.steps = 0;
+				let mut selected = Some(&*ite.else_result);
+				for branch in ite.branches.iter() {
+					match self.evaluator.eval(&branch.condition) {
+						Ok(ParValue::Boolean(true)) => {
+							selected = Some(&branch.result);
+							break;
+						}
+						Ok(ParValue::Boolean(false)) => (),
+						_ => {
+							selected = None;
+							break;
+						}
+					}
+				}
+				if let Some(result) = selected {
+					if result.ty() == expression.ty() {
+						return self.fold_expression(db, model, result);
+					}
+				}
+			}
+			fold_expression(self, db, model, expression)
+		})
+	}
+}
+
+/// Evaluate the par expressions of the model which are known given the data for its
+/// undefined top-level declarations, replacing them with literals.
+///
+/// Fails if any assertions are found to be violated.
+pub fn partially_evaluate(
+	db: &dyn Thir,
+	model: &Model,
+	data: &FxHashMap<Identifier, ParValue>,
+) -> Result<Model> {
+	log::info!("Evaluating par expressions");
+	let mut evaluator = PartialEvaluator {
+		model: Model::with_capacities(&model.entity_counts()),
+		replacement_map: ReplacementMap::default(),
+		evaluator: Evaluator {
+			db,
+			model,
+			data,
+			globals: FxHashMap::default(),
+			in_progress: FxHashSet::default(),
+			locals: FxHashMap::default(),
+			steps: 0,
+		},
+		enabled: true,
+		failures: Vec::new(),
+	};
+	evaluator.add_model(db, model);
+	let errors = evaluator
+		.failures
+		.into_iter()
+		.map(|(origin, msg)| {
+			let (src, span) = origin.source_span(db);
+			AssertionFailure { src, msg, span }.into()
+		})
+		.collect::<Vec<Error>>();
+	match Error::try_from(errors) {
+		Ok(e) => Err(e),
+		Err(_) => Ok(evaluator.model),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+	use rustc_hash::FxHashMap;
+
+	use super::{partially_evaluate, ParValue};
+	use crate::{hir::Identifier, thir::transform::test::check};
+
+	#[test]
+	fn test_partial_evaluation() {
+		check(
+			|db, model| {
+				let mut data = FxHashMap::default();
+				data.insert(Identifier::new("n", db.upcast()), ParValue::Integer(3));
+				data.insert(
+					Identifier::new("w", db.upcast()),
+					ParValue::array(vec![
+						ParValue::Integer(2),
+						ParValue::Integer(4),
+						ParValue::Integer(6),
+					]),
+				);
+				partially_evaluate(db, &model, &data)
+			},
+			r#"
+				int: n;
+				array [1..n] of int: w;
+				int: total = sum(i in 1..n)(w[i] * 2);
+				constraint assert(n > 0, "n must be positive");
+				set of int: S = {i | i in 1..n where i != 2};
+				var 0..total: x;
+				var 1..n: y;
+				constraint if n > 2 then x > y else x < y endif;
+				constraint x != total div n;
+			"#,
+			expect!([r#"
+    int: n;
+    array ['..'(1, 3)] of int: w;
+    int: total = 24;
+    set of int: S = {1, 3};
+    var '..'(0, 24): x;
+    var '..'(1, 3): y;
+    constraint '>'(x, y);
+    constraint '!='(x, 8);
+"#]),
+		);
+	}
+
+	#[test]
+	fn test_partial_evaluation_assertion() {
+		check(
+			|db, model| {
+				let mut data = FxHashMap::default();
+				data.insert(Identifier::new("n", db.upcast()), ParValue::Integer(0));
+				partially_evaluate(db, &model, &data)
+			},
+			r#"
+				int: n;
+				constraint assert(n > 0, "n must be positive");
+			"#,
+			expect!["Assertion failed"],
+		);
+	}
+}
@@ -1,5 +1,6 @@
 /// Function overloading and instantiation
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 
 use super::{OptType, Ty, TyData, TyVarRef, VarType};
 use crate::{
@@ -515,7 +516,7 @@ impl OverloadedFunction {
 /// Type of a function expression.
 ///
 /// Function expressions can't be generic, so there are no unbound type-inst variables.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FunctionType {
 	/// Return type
 	pub return_type: Ty,
@@ -702,7 +703,23 @@ impl PolymorphicFunctionType {
 		arg: Ty,
 		param: Ty,
 	) -> bool {
-		maybe_grow_stack(|| Self::collect_instantiations_inner(db, add_instantiation, arg, param))
+		Self::collect_instantiations_variance(db, add_instantiation, arg, param, false)
+	}
+
+	/// Collects the types to instantiate unbound type-inst variables with.
+	///
+	/// If `contravariant` is true, then `param` must be a subtype of `arg` instead of the other way
+	/// around (e.g. for the parameters of a function type).
+	fn collect_instantiations_variance(
+		db: &dyn Interner,
+		add_instantiation: &mut impl FnMut(TyVarRef, Ty) -> bool,
+		arg: Ty,
+		param: Ty,
+		contravariant: bool,
+	) -> bool {
+		maybe_grow_stack(|| {
+			Self::collect_instantiations_inner(db, add_instantiation, arg, param, contravariant)
+		})
 	}
 
 	fn collect_instantiations_inner(
@@ -710,7 +727,10 @@ impl PolymorphicFunctionType {
 		add_instantiation: &mut impl FnMut(TyVarRef, Ty) -> bool,
 		arg: Ty,
 		param: Ty,
+		contravariant: bool,
 	) -> bool {
+		// Whether the optionality/inst of the argument is compatible with that of the parameter
+		let compatible = |a, p, top| a == p || if contravariant { p == top } else { a == top };
 		match (arg.lookup(db), param.lookup(db)) {
 			(
 				TyData::Array {
@@ -724,64 +744,82 @@ impl PolymorphicFunctionType {
 					element: e2,
 				},
 			) => {
-				(o1 == o2 || o1 == OptType::NonOpt)
-					&& PolymorphicFunctionType::collect_instantiations(
+				compatible(o1, o2, OptType::NonOpt)
+					&& PolymorphicFunctionType::collect_instantiations_variance(
 						db,
 						add_instantiation,
 						d1,
 						d2,
-					) && PolymorphicFunctionType::collect_instantiations(db, add_instantiation, e1, e2)
+						contravariant,
+					) && PolymorphicFunctionType::collect_instantiations_variance(
+					db,
+					add_instantiation,
+					e1,
+					e2,
+					contravariant,
+				)
 			}
 			(TyData::Set(i1, o1, e1), TyData::Set(i2, o2, e2)) => {
-				(i1 == i2 || i1 == VarType::Par)
-					&& (o1 == o2 || o1 == OptType::NonOpt)
-					&& PolymorphicFunctionType::collect_instantiations(
+				(i1 == i2
+					|| if contravariant {
+						i2 == VarType::Par
+					} else {
+						i1 == VarType::Par
+					}) && compatible(o1, o2, OptType::NonOpt)
+					&& PolymorphicFunctionType::collect_instantiations_variance(
 						db,
 						add_instantiation,
 						e1,
 						e2,
+						contravariant,
 					)
 			}
 			(TyData::Tuple(o1, f1), TyData::Tuple(o2, f2)) => {
-				(o1 == o2 || o1 == OptType::NonOpt)
+				compatible(o1, o2, OptType::NonOpt)
 					&& f1.len() == f2.len()
 					&& f1.iter().zip(f2.iter()).all(|(t1, t2)| {
-						PolymorphicFunctionType::collect_instantiations(
+						PolymorphicFunctionType::collect_instantiations_variance(
 							db,
 							add_instantiation,
 							*t1,
 							*t2,
+							contravariant,
 						)
 					})
 			}
 			(TyData::Record(o1, f1), TyData::Record(o2, f2)) => {
-				(o1 == o2 || o1 == OptType::NonOpt)
+				compatible(o1, o2, OptType::NonOpt)
 					&& f2.iter().all(|(i2, t2)| {
 						f1.iter().any(|(i1, t1)| {
 							i1 == i2
-								&& PolymorphicFunctionType::collect_instantiations(
+								&& PolymorphicFunctionType::collect_instantiations_variance(
 									db,
 									add_instantiation,
 									*t1,
 									*t2,
+									contravariant,
 								)
 						})
 					})
 			}
 			(TyData::Function(o1, f1), TyData::Function(o2, f2)) => {
-				(o1 == OptType::NonOpt || o1 == o2)
-					&& PolymorphicFunctionType::collect_instantiations(
+				// Function parameters are contravariant, so the argument function must accept
+				// (at least) the parameter types of the expected function
+				compatible(o1, o2, OptType::NonOpt)
+					&& PolymorphicFunctionType::collect_instantiations_variance(
 						db,
 						add_instantiation,
 						f1.return_type,
 						f2.return_type,
+						contravariant,
 					) && f1.params.len() == f2.params.len()
 					&& f1.params.iter().zip(f2.params.iter()).all(|(t1, t2)| {
-						PolymorphicFunctionType::collect_instantiations(
+						PolymorphicFunctionType::collect_instantiations_variance(
 							db,
 							add_instantiation,
-							*t2,
 							*t1,
+							*t2,
+							!contravariant,
 						)
 					})
 			}
@@ -801,7 +839,13 @@ impl PolymorphicFunctionType {
 				}
 				add_instantiation(t.ty_var, arg)
 			}
-			_ => arg.is_subtype_of(db, param),
+			_ => {
+				if contravariant {
+					param.is_subtype_of(db, arg)
+				} else {
+					arg.is_subtype_of(db, param)
+				}
+			}
 		}
 	}
 
@@ -13,6 +13,7 @@
 use std::{collections::hash_map::Entry, sync::atomic::AtomicU32};
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 
 use crate::{
 	db::{InternedString, Interner},
@@ -1065,10 +1066,8 @@ impl Ty {
 				}
 				(TyData::Record(o1, f1), TyData::Record(o2, f2)) => {
 					if o1 != OptType::NonOpt && o1 != o2
-						|| f1.len() != f2.len() || !f1
-						.iter()
-						.zip(f2.iter())
-						.all(|((i1, _), (i2, _))| i1 == i2)
+						|| f1.len() != f2.len()
+						|| !f1.iter().zip(f2.iter()).all(|((i1, _), (i2, _))| i1 == i2)
 					{
 						return false;
 					}
@@ -1145,6 +1144,17 @@ impl Ty {
 					.map(|(i, f)| (*i, f.instantiate_ty_vars(db, ty_vars)))
 					.collect(),
 			)),
+			TyData::Function(o, f) => db.intern_ty(TyData::Function(
+				o,
+				FunctionType {
+					return_type: f.return_type.instantiate_ty_vars(db, ty_vars),
+					params: f
+						.params
+						.iter()
+						.map(|p| p.instantiate_ty_vars(db, ty_vars))
+						.collect(),
+				},
+			)),
 			_ => *self,
 		}
 	}
@@ -1284,7 +1294,7 @@ impl Ty {
 }
 
 /// A type used in the type-system (as opposed to the type that is declared by the user and used in the `hir` module).
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TyData {
 	/// Boolean scalar
 	Boolean(VarType, OptType),
@@ -1362,21 +1372,21 @@ impl NewType {
 	}
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum NewTypeKind {
 	Pattern(PatternRef),
 	Introduced(u32),
 }
 
 /// A new type (e.g. enums, type-inst vars)
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NewTypeData {
 	kind: NewTypeKind,
 	name: InternedString,
 }
 
 /// The type of an enum value
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EnumRef(NewType);
 
 impl EnumRef {
@@ -1405,7 +1415,7 @@ impl EnumRef {
 }
 
 /// The type of a reference to a type-inst var
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TyVarRef(NewType);
 
 impl TyVarRef {
@@ -1439,7 +1449,7 @@ impl TyVarRef {
 }
 
 /// The type of a type-inst variable
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TyVar {
 	/// The newtype for this type-inst var
 	pub ty_var: TyVarRef,
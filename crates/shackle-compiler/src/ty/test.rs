@@ -807,3 +807,65 @@ fn test_most_general_subtype() {
 		Some(types.par_bool)
 	);
 }
+
+#[test]
+fn test_instantiate_function_parameter() {
+	let types = Types::new();
+	let db = &types.db;
+	let ty_var = match types.ty_var.lookup(db) {
+		TyData::TyVar(_, _, t) => t.ty_var,
+		_ => unreachable!(),
+	};
+
+	// function $T: apply($T, op($T: ($T)))
+	let apply = PolymorphicFunctionType {
+		return_type: types.ty_var,
+		ty_params: Box::new([ty_var]),
+		params: Box::new([
+			types.ty_var,
+			Ty::function(
+				db,
+				FunctionType {
+					params: Box::new([types.ty_var]),
+					return_type: types.ty_var,
+				},
+			),
+		]),
+	};
+	let int_to_int = Ty::function(
+		db,
+		FunctionType {
+			params: Box::new([types.par_int]),
+			return_type: types.par_int,
+		},
+	);
+	let (instantiations, ft) = apply
+		.instantiate_ty_params(db, &[types.par_int, int_to_int])
+		.unwrap();
+	assert_eq!(instantiations.get(&ty_var), Some(&types.par_int));
+	assert_eq!(ft.return_type, types.par_int);
+
+	// A function accepting a more general argument is also accepted
+	let float_to_int = Ty::function(
+		db,
+		FunctionType {
+			params: Box::new([types.var_float]),
+			return_type: types.par_int,
+		},
+	);
+	assert!(apply
+		.instantiate_ty_params(db, &[types.par_int, float_to_int])
+		.is_ok());
+
+	// But a function accepting a less general argument is not
+	let par_to_int = Ty::function(
+		db,
+		FunctionType {
+			params: Box::new([types.par_int]),
+			return_type: types.par_int,
+		},
+	);
+	assert!(apply
+		.instantiate_ty_params(db, &[types.var_int, par_to_int])
+		.is_err());
+}
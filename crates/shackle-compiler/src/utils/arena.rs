@@ -9,6 +9,8 @@ use std::{
 	ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo},
 };
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 /// Index of a member in an Arena
 pub struct ArenaIndex<T> {
 	index: NonZeroU32,
@@ -66,6 +68,21 @@ impl<T> Ord for ArenaIndex<T> {
 	}
 }
 
+impl<T> Serialize for ArenaIndex<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.index.serialize(serializer)
+	}
+}
+
+impl<'de, T> Deserialize<'de> for ArenaIndex<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Ok(Self {
+			index: NonZeroU32::deserialize(deserializer)?,
+			phantom: PhantomData,
+		})
+	}
+}
+
 impl<T> ArenaIndex<T> {
 	fn new(raw: u32) -> Self {
 		Self {
@@ -82,7 +99,7 @@ impl<T> From<ArenaIndex<T>> for u32 {
 }
 
 /// A vector-based single-type arena
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Arena<T> {
 	items: Vec<T>,
 }
@@ -197,6 +197,67 @@ pub fn pretty_print_identifier(name: &str) -> String {
 	name.to_owned()
 }
 
+/// Compute the edit distance between two strings.
+///
+/// This is the optimal string alignment distance, which counts insertions,
+/// deletions, substitutions and transpositions of adjacent characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+	let a = a.chars().collect::<Vec<_>>();
+	let b = b.chars().collect::<Vec<_>>();
+	// Rows for i - 2, i - 1 and i
+	let mut prev_prev = vec![0; b.len() + 1];
+	let mut prev = (0..=b.len()).collect::<Vec<_>>();
+	let mut current = vec![0; b.len() + 1];
+	for i in 1..=a.len() {
+		current[0] = i;
+		for j in 1..=b.len() {
+			let cost = usize::from(a[i - 1] != b[j - 1]);
+			current[j] = (prev[j] + 1)
+				.min(current[j - 1] + 1)
+				.min(prev[j - 1] + cost);
+			if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+				current[j] = current[j].min(prev_prev[j - 2] + 1);
+			}
+		}
+		std::mem::swap(&mut prev_prev, &mut prev);
+		std::mem::swap(&mut prev, &mut current);
+	}
+	prev[b.len()]
+}
+
+/// Find the candidates which are closest to the given name (for "did you mean" suggestions).
+///
+/// Only candidates within an edit distance of a third of the length of the name are considered,
+/// and at most `limit` candidates are returned (closest first).
+pub fn closest_matches<T: AsRef<str>>(
+	name: &str,
+	candidates: impl IntoIterator<Item = T>,
+	limit: usize,
+) -> Vec<T> {
+	let max_distance = (name.chars().count() / 3).max(1);
+	let mut matches = candidates
+		.into_iter()
+		.filter_map(|c| {
+			if c.as_ref() == name {
+				return None;
+			}
+			let distance = if c.as_ref().to_lowercase() == name.to_lowercase() {
+				// Only differs in case
+				0
+			} else {
+				edit_distance(name, c.as_ref())
+			};
+			if distance <= max_distance {
+				Some((distance, c))
+			} else {
+				None
+			}
+		})
+		.collect::<Vec<_>>();
+	matches.sort_by(|(d1, c1), (d2, c2)| d1.cmp(d2).then_with(|| c1.as_ref().cmp(c2.as_ref())));
+	matches.into_iter().take(limit).map(|(_, c)| c).collect()
+}
+
 /// Grow the stack if necessary to run the given function.
 ///
 /// Useful for recursive calls which may overrun the stack otherwise.
@@ -207,7 +268,7 @@ pub fn maybe_grow_stack<R>(f: impl FnOnce() -> R) -> R {
 
 #[cfg(test)]
 mod test {
-	use super::pretty_print_identifier;
+	use super::{closest_matches, edit_distance, pretty_print_identifier};
 
 	#[test]
 	fn pretty_print_ident() {
@@ -218,4 +279,29 @@ mod test {
 		assert_eq!(pretty_print_identifier("123"), "'123'");
 		assert_eq!(pretty_print_identifier("1E24"), "'1E24'");
 	}
+
+	#[test]
+	fn test_edit_distance() {
+		assert_eq!(edit_distance("", ""), 0);
+		assert_eq!(edit_distance("abc", ""), 3);
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(edit_distance("alldifferent", "all_different"), 1);
+		assert_eq!(edit_distance("cumulaitve", "cumulative"), 1);
+	}
+
+	#[test]
+	fn test_closest_matches() {
+		let candidates = [
+			"all_different",
+			"all_equal",
+			"alldifferent_except_0",
+			"among",
+		];
+		assert_eq!(
+			closest_matches("alldifferent", candidates, 3),
+			vec!["all_different"]
+		);
+		assert_eq!(closest_matches("amnog", candidates, 3), vec!["among"]);
+		assert!(closest_matches("foo", candidates, 3).is_empty());
+	}
 }
@@ -1,11 +1,18 @@
-use std::{ops::Deref, path::Path, sync::Arc};
+use std::{
+	collections::HashMap,
+	iter,
+	ops::Deref,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
 
 use crossbeam_channel::{SendError, Sender};
 use lsp_server::{Connection, ErrorCode, Message, ResponseError};
-use lsp_types::{TextDocumentIdentifier, Url};
+use lsp_types::{TextDocumentContentChangeEvent, TextDocumentIdentifier, Url};
 use shackle_compiler::{
 	db::{CompilerDatabase, FileReader, HasFileHandler, Inputs},
-	file::{InputFile, ModelRef},
+	file::{FileRef, InputFile, ModelRef, SourceFile},
+	project::{ProjectConfig, PROJECT_FILE_NAME},
 };
 
 use crate::{diagnostics, vfs::Vfs};
@@ -20,27 +27,72 @@ pub trait LanguageServerContext: Deref<Target = CompilerDatabase> {
 
 	/// Get the workspace URI
 	fn get_workspace_uri(&self) -> Option<&Url>;
+
+	/// Get the paths of the models which are part of the workspace
+	fn workspace_models(&self) -> Vec<PathBuf>;
 }
 
 pub struct LanguageServerDatabase {
 	vfs: Vfs,
 	pool: threadpool::ThreadPool,
 	sender: Sender<Message>,
-	db: CompilerDatabase,
+	/// Database for the models of the project
+	project_db: CompilerDatabase,
+	/// Databases for the open files which are not part of the project, each compiled separately
+	file_dbs: HashMap<PathBuf, CompilerDatabase>,
+	/// The file whose database is used for the current request (the project if `None`)
+	active: Option<PathBuf>,
 	workspace: Option<Url>,
+	project_file: Option<PathBuf>,
+	project: Option<ProjectConfig>,
 }
 
 impl LanguageServerDatabase {
 	pub fn new(connection: &Connection, workspace: Option<Url>) -> Self {
+		// Use the project file for the workspace, or watch for one being created
+		let project_file = workspace
+			.as_ref()
+			.and_then(|w| w.to_file_path().ok())
+			.map(|dir| ProjectConfig::find(&dir).unwrap_or_else(|| dir.join(PROJECT_FILE_NAME)));
 		let fs = Vfs::new();
-		let db = CompilerDatabase::with_file_handler(Box::new(fs.clone()));
-		Self {
+		let project_db = CompilerDatabase::with_file_handler(Box::new(fs.clone()));
+		let mut db = Self {
 			vfs: fs,
 			pool: threadpool::Builder::new().build(),
 			sender: connection.sender.clone(),
-			db,
+			project_db,
+			file_dbs: HashMap::new(),
+			active: None,
 			workspace,
+			project_file: project_file.clone(),
+			project: None,
+		};
+		if let Some(path) = project_file.filter(|p| p.is_file()) {
+			db.load_project(&path, ProjectConfig::from_file(&path));
 		}
+		db
+	}
+
+	fn load_project(&mut self, path: &Path, config: shackle_compiler::Result<ProjectConfig>) {
+		match config {
+			Ok(config) => {
+				log::info!("loaded project file {:?}", path);
+				for db in iter::once(&mut self.project_db).chain(self.file_dbs.values_mut()) {
+					config.apply(db, None);
+				}
+				self.project = Some(config);
+				self.update_inputs();
+			}
+			Err(e) => log::warn!("failed to load project file {:?}: {:?}", path, e),
+		}
+	}
+
+	fn is_project_file(&self, file: &Path) -> bool {
+		self.project_file.as_deref() == Some(file)
+	}
+
+	fn in_project(&self, file: &Path) -> bool {
+		self.project.as_ref().is_some_and(|p| p.contains(file))
 	}
 
 	pub fn send(&self, message: Message) -> Result<(), SendError<Message>> {
@@ -51,7 +103,14 @@ impl LanguageServerDatabase {
 	where
 		F: FnOnce(&CompilerDatabase, Sender<Message>) + Send + 'static,
 	{
-		let db = self.db.snapshot();
+		self.execute_async_with(self, f);
+	}
+
+	fn execute_async_with<F>(&self, db: &CompilerDatabase, f: F)
+	where
+		F: FnOnce(&CompilerDatabase, Sender<Message>) + Send + 'static,
+	{
+		let db = db.snapshot();
 		let sender = self.sender.clone();
 		self.pool.execute(move || {
 			f(&db, sender);
@@ -59,35 +118,148 @@ impl LanguageServerDatabase {
 	}
 
 	pub fn manage_file(&mut self, file: &Path, contents: &str) {
+		log::info!("detected file opened for file {:?}", file);
+		let contents = self.vfs.manage_file(file, contents);
+		if !self.on_contents_change(file, contents) {
+			self.update_inputs();
+		}
+		self.publish_diagnostics();
+	}
+
+	pub fn change_file(&mut self, file: &Path, changes: &[TextDocumentContentChangeEvent]) {
 		log::info!("detected file changed for file {:?}", file);
-		self.vfs.manage_file(file, contents);
-		self.db.on_file_change(file);
-		self.set_active_file(file);
+		match self.vfs.change_file(file, changes) {
+			Some(contents) => {
+				self.on_contents_change(file, contents);
+				self.publish_diagnostics();
+			}
+			None => log::warn!("received changes for unopened file {:?}", file),
+		}
 	}
 
 	pub fn unmanage_file(&mut self, file: &Path) {
+		log::info!("detected file closed for file {:?}", file);
 		self.vfs.unmanage_file(file);
-		log::info!("detected file changed for file {:?}", file);
-		self.db.on_file_change(file);
-	}
-
-	pub fn set_active_file(&mut self, path: &Path) {
-		self.db
-			.set_input_files(Arc::new(vec![InputFile::Path(path.to_owned())]));
-		let path_filter = path.to_owned();
-		self.execute_async(move |db, sender| {
-			let notification = diagnostics::diagnostics_notification(db, path_filter.as_path());
-			sender
-				.send(Message::Notification(notification))
-				.expect("Failed to send diagnostics");
+		if self.is_project_file(file) {
+			self.load_project(file, ProjectConfig::from_file(file));
+		} else {
+			self.on_file_change(file);
+			self.update_inputs();
+		}
+		self.publish_diagnostics();
+	}
+
+	/// Update the contents of an open file.
+	///
+	/// Returns whether the file was the project file, in which case the project is reloaded.
+	fn on_contents_change(&mut self, file: &Path, contents: Arc<String>) -> bool {
+		if self.is_project_file(file) {
+			let config = ProjectConfig::parse(&SourceFile::with_path(file.to_owned(), contents));
+			self.load_project(file, config);
+			true
+		} else {
+			self.on_file_change(file);
+			false
+		}
+	}
+
+	/// Invalidate the contents of the given file in every database
+	fn on_file_change(&mut self, file: &Path) {
+		for db in iter::once(&mut self.project_db).chain(self.file_dbs.values_mut()) {
+			db.on_file_change(file);
+		}
+	}
+
+	/// Compile the project models together, and each other open model separately.
+	///
+	/// The inputs only change when files are opened or closed, so that salsa
+	/// can reuse the results for items unaffected by edits.
+	fn update_inputs(&mut self) {
+		let models = self
+			.project
+			.as_ref()
+			.map(|p| p.models.iter().cloned().map(InputFile::Path).collect())
+			.unwrap_or_default();
+		if *self.project_db.input_files() != models {
+			self.project_db.set_input_files(Arc::new(models));
+		}
+		let open = self
+			.vfs
+			.paths()
+			.into_iter()
+			.filter(|p| p.extension().is_some_and(|ext| ext == "mzn"))
+			.filter(|p| !self.in_project(p))
+			.collect::<Vec<_>>();
+		self.file_dbs.retain(|p, _| open.contains(p));
+		for path in open {
+			self.file_db(&path);
+		}
+		if self
+			.active
+			.as_ref()
+			.is_some_and(|p| !self.file_dbs.contains_key(p))
+		{
+			self.active = None;
+		}
+	}
+
+	/// Get the database which compiles the given file by itself, creating it if necessary
+	fn file_db(&mut self, path: &Path) -> &mut CompilerDatabase {
+		self.file_dbs.entry(path.to_owned()).or_insert_with(|| {
+			let mut db = CompilerDatabase::with_file_handler(Box::new(self.vfs.clone()));
+			if let Some(config) = &self.project {
+				config.apply(&mut db, None);
+			}
+			db.set_input_files(Arc::new(vec![InputFile::Path(path.to_owned())]));
+			db
+		})
+	}
+
+	/// Publish the diagnostics for the open files and the project data files
+	fn publish_diagnostics(&self) {
+		let data = self
+			.project
+			.as_ref()
+			.map(|p| p.data.clone())
+			.unwrap_or_default();
+		let mut paths = self.vfs.paths();
+		paths.retain(|p| self.in_project(p));
+		paths.extend(data.iter().cloned());
+		paths.sort();
+		paths.dedup();
+		self.execute_async_with(&self.project_db, move |db, sender| {
+			send_diagnostics(db, &paths, &data, &sender);
 		});
+		for (path, db) in self.file_dbs.iter() {
+			let paths = [path.clone()];
+			self.execute_async_with(db, move |db, sender| {
+				send_diagnostics(db, &paths, &[], &sender);
+			});
+		}
+	}
+}
+
+/// Send the diagnostics notifications for the given files
+fn send_diagnostics(
+	db: &CompilerDatabase,
+	paths: &[PathBuf],
+	data: &[PathBuf],
+	sender: &Sender<Message>,
+) {
+	for notification in diagnostics::diagnostics_notifications(db, paths, data) {
+		sender
+			.send(Message::Notification(notification))
+			.expect("Failed to send diagnostics");
 	}
 }
 
 impl Deref for LanguageServerDatabase {
 	type Target = CompilerDatabase;
 	fn deref(&self) -> &Self::Target {
-		&self.db
+		self.active
+			.as_ref()
+			.and_then(|p| self.file_dbs.get(p))
+			.unwrap_or(&self.project_db)
 	}
 }
 
@@ -101,11 +273,64 @@ impl LanguageServerContext for LanguageServerDatabase {
 			data: None,
 			message: "Failed to convert URI to file path".to_owned(),
 		})?;
-		self.set_active_file(&requested_path);
-		Ok(self.input_models()[0])
+		// Use the project if the file is part of it, otherwise compile the file by itself
+		if self.in_project(&requested_path) {
+			self.active = None;
+		} else {
+			self.file_db(&requested_path);
+			self.active = Some(requested_path.clone());
+		}
+		let db: &CompilerDatabase = self;
+		let model = db
+			.input_models()
+			.iter()
+			.copied()
+			.find(|m| m.path(db).as_deref() == Some(requested_path.as_path()))
+			.unwrap_or_else(|| FileRef::new(&requested_path, db).into());
+		Ok(model)
 	}
 
 	fn get_workspace_uri(&self) -> Option<&Url> {
 		self.workspace.as_ref()
 	}
+
+	fn workspace_models(&self) -> Vec<PathBuf> {
+		let root = self.workspace.as_ref().and_then(|w| w.to_file_path().ok());
+		let mut models = Vec::new();
+		if let Some(root) = &root {
+			find_models(root, &mut models);
+		}
+		if let Some(project) = &self.project {
+			models.extend(project.models.iter().cloned());
+		}
+		// Open documents are only included if they are inside the workspace
+		models.extend(
+			self.vfs
+				.paths()
+				.into_iter()
+				.filter(|p| p.extension().is_some_and(|ext| ext == "mzn"))
+				.filter(|p| root.iter().all(|r| p.starts_with(r))),
+		);
+		models.sort();
+		models.dedup();
+		models
+	}
+}
+
+/// Recursively find the model files in the given directory, skipping hidden directories
+fn find_models(dir: &Path, models: &mut Vec<PathBuf>) {
+	let Ok(entries) = std::fs::read_dir(dir) else {
+		return;
+	};
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if entry.file_name().to_string_lossy().starts_with('.') {
+			continue;
+		}
+		if path.is_dir() {
+			find_models(&path, models);
+		} else if path.extension().is_some_and(|ext| ext == "mzn") {
+			models.push(path);
+		}
+	}
 }
@@ -5,27 +5,47 @@ use std::{
 
 use lsp_types::{notification::Notification, Url};
 use miette::{Diagnostic, Severity};
-use shackle_compiler::hir::db::Hir;
+use shackle::check_data_files;
+use shackle_compiler::thir::db::Thir;
 
 use crate::utils::span_contents_to_range;
 
-pub fn diagnostics_notification(db: &dyn Hir, path: &Path) -> lsp_server::Notification {
-	let mut diagnostics = Vec::new();
-	for d in db.all_errors().iter() {
-		collect_diagnostic(path, d, &mut diagnostics);
-	}
-	for d in db.all_warnings().iter() {
-		collect_diagnostic(path, d, &mut diagnostics);
-	}
-	lsp_server::Notification {
-		method: lsp_types::notification::PublishDiagnostics::METHOD.to_owned(),
-		params: serde_json::to_value(lsp_types::PublishDiagnosticsParams {
-			uri: Url::from_file_path(path).unwrap(),
-			diagnostics,
-			version: None,
+/// Create the diagnostics notifications for the given files.
+///
+/// The data files are checked against the model if there are no errors in the model.
+pub fn diagnostics_notifications(
+	db: &dyn Thir,
+	paths: &[PathBuf],
+	data: &[PathBuf],
+) -> Vec<lsp_server::Notification> {
+	let errors = db.all_errors();
+	let data_errors = if errors.is_empty() {
+		check_data_files(db, data)
+	} else {
+		Vec::new()
+	};
+	let warnings = db.all_warnings();
+	paths
+		.iter()
+		.map(|path| {
+			let mut diagnostics = Vec::new();
+			for d in errors.iter().chain(data_errors.iter()) {
+				collect_diagnostic(path, d, &mut diagnostics);
+			}
+			for d in warnings.iter() {
+				collect_diagnostic(path, d, &mut diagnostics);
+			}
+			lsp_server::Notification {
+				method: lsp_types::notification::PublishDiagnostics::METHOD.to_owned(),
+				params: serde_json::to_value(lsp_types::PublishDiagnosticsParams {
+					uri: Url::from_file_path(path).unwrap(),
+					diagnostics,
+					version: None,
+				})
+				.unwrap(),
+			}
 		})
-		.unwrap(),
-	}
+		.collect()
 }
 
 fn collect_diagnostic(
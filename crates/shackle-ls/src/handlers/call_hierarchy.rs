@@ -0,0 +1,446 @@
+use lsp_server::{ErrorCode, ResponseError};
+use lsp_types::{
+	request::{CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare},
+	CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+	CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams, Range,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{ItemRef, LocalItemRef, NodeRef},
+		source::Point,
+	},
+};
+
+use super::{item_symbols, pattern_at};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, path_to_uri, position_to_point},
+};
+
+#[derive(Debug)]
+pub struct CallHierarchyPrepareHandler;
+
+impl RequestHandler<CallHierarchyPrepare, (ModelRef, Point)> for CallHierarchyPrepareHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: CallHierarchyPrepareParams,
+	) -> Result<(ModelRef, Point), ResponseError> {
+		let model_ref =
+			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
+		let position = params.text_document_position_params.position;
+		Ok((model_ref, position_to_point(db, *model_ref, position)))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, point): (ModelRef, Point),
+	) -> Result<Option<Vec<CallHierarchyItem>>, ResponseError> {
+		Ok(function_at(db, model_ref, point)
+			.and_then(|item| call_hierarchy_item(db, item))
+			.map(|item| vec![item]))
+	}
+}
+
+#[derive(Debug)]
+pub struct CallHierarchyIncomingCallsHandler;
+
+impl RequestHandler<CallHierarchyIncomingCalls, CallHierarchyItem>
+	for CallHierarchyIncomingCallsHandler
+{
+	fn prepare(
+		_db: &mut impl LanguageServerContext,
+		params: CallHierarchyIncomingCallsParams,
+	) -> Result<CallHierarchyItem, ResponseError> {
+		Ok(params.item)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		item: CallHierarchyItem,
+	) -> Result<Option<Vec<CallHierarchyIncomingCall>>, ResponseError> {
+		let function = resolve_item(db, &item)?;
+		let graph = db.call_graph();
+		let calls = group_calls(
+			db,
+			graph
+				.incoming_calls(function)
+				.iter()
+				.map(|c| (c.caller(), NodeRef::from(c.expression.into_entity(db)))),
+		);
+		Ok(Some(
+			calls
+				.into_iter()
+				.map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+				.collect(),
+		))
+	}
+}
+
+#[derive(Debug)]
+pub struct CallHierarchyOutgoingCallsHandler;
+
+impl RequestHandler<CallHierarchyOutgoingCalls, CallHierarchyItem>
+	for CallHierarchyOutgoingCallsHandler
+{
+	fn prepare(
+		_db: &mut impl LanguageServerContext,
+		params: CallHierarchyOutgoingCallsParams,
+	) -> Result<CallHierarchyItem, ResponseError> {
+		Ok(params.item)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		item: CallHierarchyItem,
+	) -> Result<Option<Vec<CallHierarchyOutgoingCall>>, ResponseError> {
+		let function = resolve_item(db, &item)?;
+		let calls = group_calls(
+			db,
+			db.lookup_item_calls(function)
+				.iter()
+				.map(|c| (c.callee, NodeRef::from(c.expression.into_entity(db)))),
+		);
+		Ok(Some(
+			calls
+				.into_iter()
+				.map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+				.collect(),
+		))
+	}
+}
+
+/// Get the function item declared or called at the given point
+pub fn function_at(db: &CompilerDatabase, model_ref: ModelRef, point: Point) -> Option<ItemRef> {
+	let pattern = pattern_at(db, model_ref, point).ok()??;
+	let item = pattern.item();
+	let model = item.model(db);
+	match item.local_item_ref(db) {
+		LocalItemRef::Function(f) if model[f].pattern == pattern.pattern() => Some(item),
+		_ => None,
+	}
+}
+
+/// Find the item for a call hierarchy item given by the client
+fn resolve_item(db: &CompilerDatabase, item: &CallHierarchyItem) -> Result<ItemRef, ResponseError> {
+	let not_found = || ResponseError {
+		code: ErrorCode::InvalidParams as i32,
+		message: format!("Could not find item '{}'", item.name),
+		data: None,
+	};
+	let models = db.resolve_includes().map_err(|_| not_found())?;
+	let model_ref = models
+		.iter()
+		.find(|m| m.path(db).and_then(|p| path_to_uri(&p)).as_ref() == Some(&item.uri))
+		.ok_or_else(not_found)?;
+	let point = position_to_point(db, **model_ref, item.selection_range.start);
+	function_at(db, *model_ref, point).ok_or_else(not_found)
+}
+
+/// Get the call hierarchy item for an item
+fn call_hierarchy_item(db: &CompilerDatabase, item: ItemRef) -> Option<CallHierarchyItem> {
+	let symbol = item_symbols(db, item).into_iter().next()?;
+	let path = item.model_ref(db).path(db)?;
+	Some(CallHierarchyItem {
+		name: symbol.name,
+		kind: symbol.kind,
+		tags: None,
+		detail: symbol.detail,
+		uri: path_to_uri(&path)?,
+		range: symbol.range,
+		selection_range: symbol.selection_range,
+		data: None,
+	})
+}
+
+/// Group calls by the item at the other end of the call, giving the ranges of the calls
+fn group_calls(
+	db: &CompilerDatabase,
+	calls: impl Iterator<Item = (ItemRef, NodeRef)>,
+) -> Vec<(CallHierarchyItem, Vec<Range>)> {
+	let mut groups: Vec<(ItemRef, Vec<Range>)> = Vec::new();
+	for (item, node) in calls {
+		let Some(location) = node_ref_to_location(db, node) else {
+			continue;
+		};
+		match groups.iter_mut().find(|(i, _)| *i == item) {
+			Some((_, ranges)) => ranges.push(location.range),
+			None => groups.push((item, vec![location.range])),
+		}
+	}
+	groups
+		.into_iter()
+		.filter_map(|(item, ranges)| Some((call_hierarchy_item(db, item)?, ranges)))
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::{expect, Expect};
+	use lsp_types::{
+		CallHierarchyIncomingCallsParams, CallHierarchyItem, CallHierarchyOutgoingCallsParams,
+		CallHierarchyPrepareParams, Position, TextDocumentIdentifier, TextDocumentPositionParams,
+		Url,
+	};
+
+	use super::{
+		CallHierarchyIncomingCallsHandler, CallHierarchyOutgoingCallsHandler,
+		CallHierarchyPrepareHandler,
+	};
+	use crate::handlers::test::{run_handler, test_handler};
+
+	const MODEL: &str = r#"
+function int: foo(int: x) = x;
+function float: foo(float: x) = x;
+function int: bar(int: x) = foo(foo(x));
+int: y = bar(foo(1));
+"#;
+
+	fn prepare(line: u32, character: u32) -> CallHierarchyItem {
+		run_handler::<CallHierarchyPrepareHandler, _, _>(
+			MODEL,
+			true,
+			CallHierarchyPrepareParams {
+				text_document_position_params: TextDocumentPositionParams {
+					text_document: TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: Position { line, character },
+				},
+				work_done_progress_params: Default::default(),
+			},
+		)
+		.unwrap()
+		.unwrap()
+		.remove(0)
+	}
+
+	fn check_incoming(line: u32, character: u32, expected: Expect) {
+		test_handler::<CallHierarchyIncomingCallsHandler, _, _>(
+			MODEL,
+			true,
+			CallHierarchyIncomingCallsParams {
+				item: prepare(line, character),
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expected,
+		)
+	}
+
+	fn check_outgoing(line: u32, character: u32, expected: Expect) {
+		test_handler::<CallHierarchyOutgoingCallsHandler, _, _>(
+			MODEL,
+			true,
+			CallHierarchyOutgoingCallsParams {
+				item: prepare(line, character),
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expected,
+		)
+	}
+
+	#[test]
+	fn test_call_hierarchy_prepare() {
+		expect![[r#"
+    {
+      "name": "bar",
+      "kind": 12,
+      "detail": "function int: bar(int)",
+      "uri": "file:///test.mzn",
+      "range": {
+        "start": {
+          "line": 3,
+          "character": 0
+        },
+        "end": {
+          "line": 3,
+          "character": 39
+        }
+      },
+      "selectionRange": {
+        "start": {
+          "line": 3,
+          "character": 14
+        },
+        "end": {
+          "line": 3,
+          "character": 17
+        }
+      }
+    }"#]]
+		.assert_eq(&serde_json::to_string_pretty(&prepare(4, 10)).unwrap());
+	}
+
+	#[test]
+	fn test_call_hierarchy_incoming() {
+		check_incoming(
+			1,
+			15,
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "from": {
+            "name": "bar",
+            "kind": 12,
+            "detail": "function int: bar(int)",
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 3,
+                "character": 0
+              },
+              "end": {
+                "line": 3,
+                "character": 39
+              }
+            },
+            "selectionRange": {
+              "start": {
+                "line": 3,
+                "character": 14
+              },
+              "end": {
+                "line": 3,
+                "character": 17
+              }
+            }
+          },
+          "fromRanges": [
+            {
+              "start": {
+                "line": 3,
+                "character": 32
+              },
+              "end": {
+                "line": 3,
+                "character": 35
+              }
+            },
+            {
+              "start": {
+                "line": 3,
+                "character": 28
+              },
+              "end": {
+                "line": 3,
+                "character": 31
+              }
+            }
+          ]
+        },
+        {
+          "from": {
+            "name": "y",
+            "kind": 13,
+            "detail": "int",
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 4,
+                "character": 0
+              },
+              "end": {
+                "line": 4,
+                "character": 20
+              }
+            },
+            "selectionRange": {
+              "start": {
+                "line": 4,
+                "character": 5
+              },
+              "end": {
+                "line": 4,
+                "character": 6
+              }
+            }
+          },
+          "fromRanges": [
+            {
+              "start": {
+                "line": 4,
+                "character": 13
+              },
+              "end": {
+                "line": 4,
+                "character": 16
+              }
+            }
+          ]
+        }
+      ]
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_call_hierarchy_outgoing() {
+		check_outgoing(
+			3,
+			15,
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "to": {
+            "name": "foo",
+            "kind": 12,
+            "detail": "function int: foo(int)",
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 1,
+                "character": 0
+              },
+              "end": {
+                "line": 1,
+                "character": 29
+              }
+            },
+            "selectionRange": {
+              "start": {
+                "line": 1,
+                "character": 14
+              },
+              "end": {
+                "line": 1,
+                "character": 17
+              }
+            }
+          },
+          "fromRanges": [
+            {
+              "start": {
+                "line": 3,
+                "character": 32
+              },
+              "end": {
+                "line": 3,
+                "character": 35
+              }
+            },
+            {
+              "start": {
+                "line": 3,
+                "character": 28
+              },
+              "end": {
+                "line": 3,
+                "character": 31
+              }
+            }
+          ]
+        }
+      ]
+    }"#]],
+		);
+	}
+}
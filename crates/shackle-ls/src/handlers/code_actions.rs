@@ -0,0 +1,467 @@
+use std::{
+	collections::{HashMap, HashSet},
+	path::{Path, PathBuf},
+};
+
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::CodeActionRequest, CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+	Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use miette::{Diagnostic, SourceSpan};
+use shackle_compiler::{
+	db::{CompilerDatabase, CompilerSettings},
+	diagnostics::{Error, FixIt, Warning},
+	file::{FileRef, ModelRef},
+	hir::{db::Hir, source::Point, Identifier},
+	syntax::{
+		ast::{AstNode, Item},
+		db::SourceParser,
+	},
+};
+
+use super::{pattern_at, rename_pattern};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{offset_to_position, position_to_offset},
+};
+
+#[derive(Debug)]
+pub struct CodeActionHandler;
+
+#[derive(Debug)]
+pub struct CodeActionData {
+	model_ref: ModelRef,
+	uri: Url,
+	range: Range,
+}
+
+impl RequestHandler<CodeActionRequest, CodeActionData> for CodeActionHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: CodeActionParams,
+	) -> Result<CodeActionData, ResponseError> {
+		let model_ref = db.set_active_file_from_document(&params.text_document)?;
+		Ok(CodeActionData {
+			model_ref,
+			uri: params.text_document.uri,
+			range: params.range,
+		})
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		data: CodeActionData,
+	) -> Result<Option<Vec<CodeActionOrCommand>>, ResponseError> {
+		let Ok(cst) = db.cst(*data.model_ref) else {
+			return Ok(None);
+		};
+		let text = cst.text();
+		let start = position_to_offset(text, data.range.start);
+		let end = position_to_offset(text, data.range.end);
+		let in_range = |d: &dyn Diagnostic| {
+			primary_span(d)
+				.map(|s| s.offset() <= end && start <= s.offset() + s.len())
+				.unwrap_or(false)
+		};
+
+		let mut errors = Vec::new();
+		let mut warnings = Vec::new();
+		for item in db.lookup_items(data.model_ref).iter() {
+			errors.extend(db.lookup_item_type_errors(*item).iter().cloned());
+			errors.extend(db.lookup_case_exhaustiveness_errors(*item).iter().cloned());
+			warnings.extend(db.lookup_item_scope_warnings(*item).iter().cloned());
+			warnings.extend(
+				db.lookup_case_exhaustiveness_warnings(*item)
+					.iter()
+					.cloned(),
+			);
+		}
+
+		let action = |title: String, edits: Vec<TextEdit>| {
+			CodeActionOrCommand::CodeAction(CodeAction {
+				title,
+				kind: Some(CodeActionKind::QUICKFIX),
+				edit: Some(WorkspaceEdit::new(HashMap::from([(
+					data.uri.clone(),
+					edits,
+				)]))),
+				..Default::default()
+			})
+		};
+		let fix_it = |f: &FixIt| {
+			let edit = TextEdit {
+				range: Range {
+					start: offset_to_position(text, f.span.offset()),
+					end: offset_to_position(text, f.span.offset() + f.span.len()),
+				},
+				new_text: f.replacement.clone(),
+			};
+			action(f.message.clone(), vec![edit])
+		};
+
+		let mut actions = Vec::new();
+		let mut globals = None;
+		for e in errors.iter().filter(|e| in_range(*e)) {
+			actions.extend(e.fix_its().iter().map(fix_it));
+			if let Error::NoMatchingFunction(e) = e {
+				// Suggest including globals.mzn if it defines this function
+				let name = &text[e.span.offset()..e.span.offset() + e.span.len()];
+				let globals = globals.get_or_insert_with(|| globals_functions(db));
+				if globals.contains(name)
+					&& db
+						.lookup_global_function(Identifier::new(name, db))
+						.is_empty()
+				{
+					let edit = TextEdit {
+						range: Range {
+							start: include_position(db, data.model_ref),
+							end: include_position(db, data.model_ref),
+						},
+						new_text: "include \"globals.mzn\";\n".to_owned(),
+					};
+					actions.push(action("Add include \"globals.mzn\"".to_owned(), vec![edit]));
+				}
+			}
+		}
+		for w in warnings.iter().filter(|w| in_range(*w)) {
+			actions.extend(w.fix_its().iter().map(fix_it));
+			if let Warning::IdentifierShadowing(w) = w {
+				// Rename the shadowing variable to a fresh name
+				let before = &text[..w.span.offset()];
+				let point = Point {
+					row: before.matches('\n').count(),
+					column: before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0),
+				};
+				if let Ok(Some(pattern)) = pattern_at(db, data.model_ref, point) {
+					let new_name = fresh_name(text, &w.name);
+					let edit = rename_pattern(db, pattern, &new_name);
+					actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+						title: format!("Rename '{}' to '{}'", w.name, new_name),
+						kind: Some(CodeActionKind::QUICKFIX),
+						edit: Some(edit),
+						..Default::default()
+					}));
+				}
+			}
+		}
+		Ok(Some(actions))
+	}
+}
+
+/// Get the span of the primary label of a diagnostic
+fn primary_span(d: &dyn Diagnostic) -> Option<SourceSpan> {
+	d.labels()?.next().map(|l| *l.inner())
+}
+
+/// Get the names of the functions declared by `globals.mzn` and the files it includes
+fn globals_functions(db: &CompilerDatabase) -> HashSet<String> {
+	let search_dirs = db.include_search_dirs();
+	let mut names = HashSet::new();
+	let mut todo = search_dirs
+		.iter()
+		.map(|d| d.join("globals.mzn"))
+		.filter(|p| p.exists())
+		.take(1)
+		.collect::<Vec<_>>();
+	let mut seen = HashSet::new();
+	while let Some(path) = todo.pop() {
+		if !seen.insert(path.clone()) {
+			continue;
+		}
+		let Ok(model) = db.ast(FileRef::new(&path, db)) else {
+			continue;
+		};
+		for item in model.items() {
+			match item {
+				Item::Include(i) => {
+					let value = i.file().value();
+					let included = Path::new(&value);
+					let resolved = search_dirs
+						.iter()
+						.map(PathBuf::as_path)
+						.chain(path.parent())
+						.map(|d| d.join(included))
+						.find(|p| p.exists());
+					todo.extend(resolved);
+				}
+				Item::Function(f) => {
+					names.insert(f.id().name().into_owned());
+				}
+				Item::Predicate(p) => {
+					names.insert(p.id().name().into_owned());
+				}
+				_ => (),
+			}
+		}
+	}
+	names
+}
+
+/// Get the position to insert a new include item (after the last existing include)
+fn include_position(db: &CompilerDatabase, model_ref: ModelRef) -> Position {
+	let Ok(model) = db.ast(*model_ref) else {
+		return Position::default();
+	};
+	model
+		.items()
+		.filter_map(|item| match item {
+			Item::Include(i) => Some(Position {
+				line: i.cst_node().as_ref().end_position().row as u32 + 1,
+				character: 0,
+			}),
+			_ => None,
+		})
+		.last()
+		.unwrap_or_default()
+}
+
+/// Get a name based on the given name which does not appear in the text
+fn fresh_name(text: &str, name: &str) -> String {
+	let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+	(1..)
+		.map(|i| format!("{}_{}", name, i))
+		.find(|n| {
+			!text
+				.split(|c: char| !is_identifier_char(c))
+				.any(|word| word == n)
+		})
+		.unwrap()
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::{expect, Expect};
+	use lsp_types::{CodeActionContext, Position, Range, Url};
+
+	use super::CodeActionHandler;
+	use crate::handlers::test::test_handler;
+
+	fn code_action_params(start: (u32, u32), end: (u32, u32)) -> lsp_types::CodeActionParams {
+		lsp_types::CodeActionParams {
+			text_document: lsp_types::TextDocumentIdentifier {
+				uri: Url::from_str("file:///test.mzn").unwrap(),
+			},
+			range: Range {
+				start: Position::new(start.0, start.1),
+				end: Position::new(end.0, end.1),
+			},
+			context: CodeActionContext::default(),
+			work_done_progress_params: lsp_types::WorkDoneProgressParams {
+				work_done_token: None,
+			},
+			partial_result_params: lsp_types::PartialResultParams {
+				partial_result_token: None,
+			},
+		}
+	}
+
+	fn check_code_actions(model: &str, start: (u32, u32), end: (u32, u32), expected: Expect) {
+		test_handler::<CodeActionHandler, _, _>(
+			model,
+			true,
+			code_action_params(start, end),
+			expected,
+		)
+	}
+
+	#[test]
+	fn test_did_you_mean() {
+		check_code_actions(
+			r#"
+int: foo = 1;
+int: bar = fooo;
+"#,
+			(2, 11),
+			(2, 11),
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "title": "Replace with 'foo'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 2,
+                      "character": 11
+                    },
+                    "end": {
+                      "line": 2,
+                      "character": 15
+                    }
+                  },
+                  "newText": "foo"
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+
+	#[test]
+	fn test_include_globals() {
+		test_handler::<CodeActionHandler, _, _>(
+			r#"array [1..3] of var 1..3: x;
+constraint all_different(x);
+"#,
+			false,
+			code_action_params((1, 11), (1, 11)),
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "title": "Add include \"globals.mzn\"",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 0,
+                      "character": 0
+                    },
+                    "end": {
+                      "line": 0,
+                      "character": 0
+                    }
+                  },
+                  "newText": "include \"globals.mzn\";\n"
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+
+	#[test]
+	fn test_case_arms() {
+		check_code_actions(
+			r#"
+enum Foo = {A, B, C};
+Foo: x;
+int: y = case x of A => 1, _ => 2, B => 3 endcase;
+int: z = case x of A => 1 endcase;
+"#,
+			(3, 0),
+			(5, 0),
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "title": "Add missing case arms",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 4,
+                      "character": 25
+                    },
+                    "end": {
+                      "line": 4,
+                      "character": 25
+                    }
+                  },
+                  "newText": ", B => 0, C => 0"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "title": "Remove unreachable case arm",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 3,
+                      "character": 33
+                    },
+                    "end": {
+                      "line": 3,
+                      "character": 41
+                    }
+                  },
+                  "newText": ""
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+
+	#[test]
+	fn test_rename_shadowing() {
+		check_code_actions(
+			r#"
+int: x = 1;
+int: y = let { int: x = 2; int: x_1 = 3 } in x + x_1;
+"#,
+			(2, 20),
+			(2, 20),
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "title": "Rename 'x' to 'x_2'",
+          "kind": "quickfix",
+          "edit": {
+            "changes": {
+              "file:///test.mzn": [
+                {
+                  "range": {
+                    "start": {
+                      "line": 2,
+                      "character": 20
+                    },
+                    "end": {
+                      "line": 2,
+                      "character": 21
+                    }
+                  },
+                  "newText": "x_2"
+                },
+                {
+                  "range": {
+                    "start": {
+                      "line": 2,
+                      "character": 45
+                    },
+                    "end": {
+                      "line": 2,
+                      "character": 46
+                    }
+                  },
+                  "newText": "x_2"
+                }
+              ]
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+}
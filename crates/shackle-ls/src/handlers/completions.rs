@@ -1,19 +1,22 @@
 use lsp_server::ResponseError;
 use lsp_types::{
 	request::Completion, CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse,
+	Documentation, MarkupContent, MarkupKind,
 };
 use shackle_compiler::{
 	db::CompilerDatabase,
 	file::ModelRef,
 	hir::{
 		db::Hir,
+		doc::pattern_documentation,
+		ids::PatternRef,
 		source::{find_expression, Point},
 		Expression, PatternTy,
 	},
 	ty::TyData,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::position_to_point};
 
 #[derive(Debug)]
 pub struct CompletionsHandler;
@@ -27,10 +30,7 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 			db.set_active_file_from_document(&params.text_document_position.text_document)?;
 		Ok((
 			model,
-			Point {
-				row: params.text_document_position.position.line as usize,
-				column: (params.text_document_position.position.character) as usize,
-			},
+			position_to_point(db, *model, params.text_document_position.position),
 		))
 	}
 
@@ -122,6 +122,7 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 								additional_overloads,
 							)
 						}),
+						documentation: documentation(db, *p),
 						..Default::default()
 					}),
 					PatternTy::EnumConstructor(ec) => {
@@ -141,6 +142,7 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 									additional_overloads,
 								)
 							}),
+							documentation: documentation(db, *p),
 							..Default::default()
 						});
 					}
@@ -161,6 +163,7 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 									additional_overloads,
 								)
 							}),
+							documentation: documentation(db, *p),
 							..Default::default()
 						});
 					}
@@ -175,6 +178,7 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 							label: i.pretty_print(db),
 							kind: Some(CompletionItemKind::VARIABLE),
 							detail: Some(ty.pretty_print(db)),
+							documentation: documentation(db, p),
 							..Default::default()
 						})
 					}
@@ -182,28 +186,43 @@ impl RequestHandler<Completion, (ModelRef, Point)> for CompletionsHandler {
 						label: i.pretty_print(db),
 						kind: Some(CompletionItemKind::ENUM),
 						detail: Some(ty.pretty_print(db)),
+						documentation: documentation(db, p),
 						..Default::default()
 					}),
 					PatternTy::EnumAtom(ty) => completions.push(CompletionItem {
 						label: i.pretty_print(db),
 						kind: Some(CompletionItemKind::ENUM_MEMBER),
 						detail: Some(ty.pretty_print(db)),
+						documentation: documentation(db, p),
 						..Default::default()
 					}),
 					PatternTy::AnnotationAtom => completions.push(CompletionItem {
 						label: i.pretty_print(db),
 						kind: Some(CompletionItemKind::CONSTANT),
 						detail: Some("ann".to_owned()),
+						documentation: documentation(db, p),
 						..Default::default()
 					}),
 					_ => (),
 				}
 			}
+			// Scopes are not ordered, so sort to give consistent results
+			completions.sort_by(|a, b| a.label.cmp(&b.label));
 			Some(CompletionResponse::Array(completions))
 		})())
 	}
 }
 
+/// Get the documentation for the item declared by the given pattern
+fn documentation(db: &CompilerDatabase, pattern: PatternRef) -> Option<Documentation> {
+	pattern_documentation(db, pattern).map(|value| {
+		Documentation::MarkupContent(MarkupContent {
+			kind: MarkupKind::Markdown,
+			value,
+		})
+	})
+}
+
 #[cfg(test)]
 mod test {
 	use std::str::FromStr;
@@ -244,19 +263,14 @@ any: y = he
 			expect!([r#"
     {
       "Ok": [
-        {
-          "label": "y",
-          "kind": 6,
-          "detail": "error"
-        },
         {
           "label": "A",
           "kind": 20,
           "detail": "Foo"
         },
         {
-          "label": "hello",
-          "kind": 6,
+          "label": "B",
+          "kind": 20,
           "detail": "Foo"
         },
         {
@@ -265,9 +279,14 @@ any: y = he
           "detail": "set of Foo"
         },
         {
-          "label": "B",
-          "kind": 20,
+          "label": "hello",
+          "kind": 6,
           "detail": "Foo"
+        },
+        {
+          "label": "y",
+          "kind": 6,
+          "detail": "error"
         }
       ]
     }"#]),
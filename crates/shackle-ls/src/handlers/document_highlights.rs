@@ -0,0 +1,151 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::DocumentHighlightRequest, DocumentHighlight, DocumentHighlightKind,
+	DocumentHighlightParams,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		ids::{LocalEntityRef, LocalItemRef, NodeRef},
+		source::Point,
+	},
+};
+
+use super::{find_references, pattern_at};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, position_to_point},
+};
+
+#[derive(Debug)]
+pub struct DocumentHighlightHandler;
+
+impl RequestHandler<DocumentHighlightRequest, (ModelRef, Point)> for DocumentHighlightHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentHighlightParams,
+	) -> Result<(ModelRef, Point), ResponseError> {
+		let model_ref =
+			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
+		let position = params.text_document_position_params.position;
+		Ok((model_ref, position_to_point(db, *model_ref, position)))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, point): (ModelRef, Point),
+	) -> Result<Option<Vec<DocumentHighlight>>, ResponseError> {
+		Ok((|| {
+			let pattern = pattern_at(db, model_ref, point).ok()??;
+			let highlights = find_references(db, pattern, model_ref)?
+				.into_iter()
+				.filter_map(|(node_ref, is_declaration)| {
+					let kind = if is_declaration || is_assignee(db, node_ref) {
+						DocumentHighlightKind::WRITE
+					} else {
+						DocumentHighlightKind::READ
+					};
+					Some(DocumentHighlight {
+						range: node_ref_to_location(db, node_ref)?.range,
+						kind: Some(kind),
+					})
+				})
+				.collect();
+			Some(highlights)
+		})())
+	}
+}
+
+/// Whether the node is the left-hand side of an assignment item
+fn is_assignee(db: &CompilerDatabase, node_ref: NodeRef) -> bool {
+	let NodeRef::Entity(entity) = node_ref else {
+		return false;
+	};
+	let item = entity.item(db);
+	match (item.local_item_ref(db), entity.entity(db)) {
+		(LocalItemRef::Assignment(a), LocalEntityRef::Expression(e)) => {
+			item.model(db)[a].assignee == e
+		}
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::{Position, TextDocumentIdentifier, TextDocumentPositionParams, Url};
+
+	use super::DocumentHighlightHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_document_highlights() {
+		test_handler::<DocumentHighlightHandler, _, _>(
+			r#"
+int: x;
+x = 1;
+int: y = x;
+int: z = let { int: x = 2 } in x;
+"#,
+			true,
+			lsp_types::DocumentHighlightParams {
+				text_document_position_params: TextDocumentPositionParams {
+					text_document: TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: Position::new(3, 9),
+				},
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 5
+            },
+            "end": {
+              "line": 1,
+              "character": 6
+            }
+          },
+          "kind": 3
+        },
+        {
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 0
+            },
+            "end": {
+              "line": 2,
+              "character": 1
+            }
+          },
+          "kind": 3
+        },
+        {
+          "range": {
+            "start": {
+              "line": 3,
+              "character": 9
+            },
+            "end": {
+              "line": 3,
+              "character": 10
+            }
+          },
+          "kind": 2
+        }
+      ]
+    }"#]],
+		)
+	}
+}
@@ -0,0 +1,565 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::DocumentSymbolRequest, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+	Range, SymbolKind,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{EntityRef, ItemRef, LocalItemRef, NodeRef, PatternRef},
+		EnumConstructor, Expression, Goal, ItemData, LetItem, Pattern, PatternTy,
+	},
+	utils::arena::ArenaIndex,
+};
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+
+#[derive(Debug)]
+pub struct DocumentSymbolHandler;
+
+impl RequestHandler<DocumentSymbolRequest, ModelRef> for DocumentSymbolHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentSymbolParams,
+	) -> Result<ModelRef, ResponseError> {
+		db.set_active_file_from_document(&params.text_document)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		model_ref: ModelRef,
+	) -> Result<Option<DocumentSymbolResponse>, ResponseError> {
+		Ok(Some(DocumentSymbolResponse::Nested(document_symbols(
+			db, model_ref,
+		))))
+	}
+}
+
+/// Get the symbols for the items in the given model, with `let` bindings
+/// and enum constructors as nested symbols
+pub fn document_symbols(db: &CompilerDatabase, model_ref: ModelRef) -> Vec<DocumentSymbol> {
+	db.lookup_items(model_ref)
+		.iter()
+		.flat_map(|item| item_symbols(db, *item))
+		.collect()
+}
+
+/// Get the symbols declared by an item
+pub fn item_symbols(db: &CompilerDatabase, item: ItemRef) -> Vec<DocumentSymbol> {
+	let Some(range) = range_of(db, item) else {
+		return Vec::new();
+	};
+	let model = item.model(db);
+	let local_item = item.local_item_ref(db);
+	let data = local_item.data(&model);
+	let types = db.lookup_item_types(item);
+
+	let named = |pattern: ArenaIndex<Pattern>, kind, detail: Option<String>| {
+		let name = data[pattern].identifier()?.pretty_print(db);
+		Some(symbol(
+			name,
+			kind,
+			detail,
+			range,
+			range_of(db, PatternRef::new(item, pattern).into_entity(db)).unwrap_or(range),
+		))
+	};
+	let constructors = |ctors: &[EnumConstructor]| {
+		ctors
+			.iter()
+			.filter_map(|c| match c {
+				EnumConstructor::Named(c) => {
+					let pattern = c.constructor_pattern();
+					let selection = range_of(db, PatternRef::new(item, pattern).into_entity(db))?;
+					let name = data[pattern].identifier()?.pretty_print(db);
+					Some(symbol(
+						name,
+						SymbolKind::ENUM_MEMBER,
+						None,
+						selection,
+						selection,
+					))
+				}
+				EnumConstructor::Anonymous { .. } => None,
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let mut symbols = match local_item {
+		LocalItemRef::Annotation(a) => {
+			let pattern = model[a].constructor_pattern();
+			let detail = types.pretty_print_pattern_ty(db, data, pattern);
+			named(pattern, SymbolKind::CONSTRUCTOR, detail)
+				.into_iter()
+				.collect()
+		}
+		LocalItemRef::Assignment(_) => Vec::new(),
+		LocalItemRef::Constraint(_) => vec![symbol(
+			"constraint".to_owned(),
+			SymbolKind::BOOLEAN,
+			None,
+			range,
+			range,
+		)],
+		LocalItemRef::Declaration(d) => Pattern::identifiers(model[d].pattern, data)
+			.filter_map(|p| {
+				let detail = types.get_pattern(p).and_then(|t| variable_ty(db, t));
+				named(p, SymbolKind::VARIABLE, detail)
+			})
+			.collect(),
+		LocalItemRef::Enumeration(e) => {
+			let mut s = named(model[e].pattern, SymbolKind::ENUM, None);
+			if let (Some(s), Some(ctors)) = (&mut s, &model[e].definition) {
+				s.children = Some(constructors(ctors));
+			}
+			s.into_iter().collect()
+		}
+		LocalItemRef::EnumAssignment(a) => match &data[model[a].assignee] {
+			Expression::Identifier(i) => vec![DocumentSymbol {
+				children: Some(constructors(&model[a].definition)),
+				..symbol(
+					i.pretty_print(db),
+					SymbolKind::ENUM,
+					None,
+					range,
+					range_of(db, EntityRef::new(db, item, model[a].assignee)).unwrap_or(range),
+				)
+			}],
+			_ => Vec::new(),
+		},
+		LocalItemRef::Function(f) => {
+			let pattern = model[f].pattern;
+			let detail = types.pretty_print_pattern_ty(db, data, pattern);
+			named(pattern, SymbolKind::FUNCTION, detail)
+				.into_iter()
+				.collect()
+		}
+		LocalItemRef::Output(o) => {
+			let name = match model[o].section.map(|s| &data[s]) {
+				Some(Expression::StringLiteral(s)) => format!("output :: {:?}", s.value(db)),
+				_ => "output".to_owned(),
+			};
+			vec![symbol(name, SymbolKind::STRING, None, range, range)]
+		}
+		LocalItemRef::Solve(s) => {
+			let name = match model[s].goal {
+				Goal::Satisfy => "solve satisfy",
+				Goal::Maximize { .. } => "solve maximize",
+				Goal::Minimize { .. } => "solve minimize",
+			};
+			vec![symbol(
+				name.to_owned(),
+				SymbolKind::EVENT,
+				None,
+				range,
+				range,
+			)]
+		}
+		LocalItemRef::TypeAlias(t) => {
+			let pattern = model[t].name;
+			let detail = types.pretty_print_pattern_ty(db, data, pattern);
+			named(pattern, SymbolKind::TYPE_PARAMETER, detail)
+				.into_iter()
+				.collect()
+		}
+	};
+
+	// Add the let bindings as children of the item
+	let bindings = let_bindings(db, item, data);
+	if !bindings.is_empty() {
+		if let Some(s) = symbols.first_mut() {
+			for binding in bindings {
+				insert_nested(s.children.get_or_insert_with(Vec::new), binding);
+			}
+		}
+	}
+	symbols
+}
+
+/// Get the symbols for the declarations in `let` expressions in an item,
+/// sorted by position
+fn let_bindings(db: &CompilerDatabase, item: ItemRef, data: &ItemData) -> Vec<DocumentSymbol> {
+	let types = db.lookup_item_types(item);
+	let mut bindings = Vec::new();
+	for (_, e) in data.expressions.iter() {
+		let Expression::Let(l) = e else {
+			continue;
+		};
+		for i in l.items.iter() {
+			let LetItem::Declaration(d) = i else {
+				continue;
+			};
+			// The range of the declaration spans its type, pattern and definition
+			let range = [
+				range_of(db, EntityRef::new(db, item, d.declared_type)),
+				range_of(db, PatternRef::new(item, d.pattern).into_entity(db)),
+				d.definition
+					.and_then(|e| range_of(db, EntityRef::new(db, item, e))),
+			]
+			.into_iter()
+			.flatten()
+			.reduce(|a, b| Range {
+				start: a.start.min(b.start),
+				end: a.end.max(b.end),
+			});
+			for p in Pattern::identifiers(d.pattern, data) {
+				let (Some(range), Some(selection), Some(name)) = (
+					range,
+					range_of(db, PatternRef::new(item, p).into_entity(db)),
+					data[p].identifier(),
+				) else {
+					continue;
+				};
+				bindings.push(symbol(
+					name.pretty_print(db),
+					SymbolKind::VARIABLE,
+					types.get_pattern(p).and_then(|t| variable_ty(db, t)),
+					range,
+					selection,
+				));
+			}
+		}
+	}
+	bindings.sort_by_key(|s| (s.range.start, std::cmp::Reverse(s.range.end)));
+	bindings
+}
+
+/// Insert a symbol into the innermost sibling which contains it.
+///
+/// Symbols must be inserted in order of position.
+fn insert_nested(siblings: &mut Vec<DocumentSymbol>, s: DocumentSymbol) {
+	if let Some(last) = siblings.last_mut() {
+		if last.range.start <= s.range.start && s.range.end <= last.range.end {
+			insert_nested(last.children.get_or_insert_with(Vec::new), s);
+			return;
+		}
+	}
+	siblings.push(s);
+}
+
+fn variable_ty(db: &CompilerDatabase, ty: &PatternTy) -> Option<String> {
+	match ty {
+		PatternTy::Variable(ty) => Some(ty.pretty_print(db)),
+		_ => None,
+	}
+}
+
+fn range_of(db: &CompilerDatabase, node: impl Into<NodeRef>) -> Option<Range> {
+	Some(node_ref_to_location(db, node)?.range)
+}
+
+#[allow(deprecated)]
+fn symbol(
+	name: String,
+	kind: SymbolKind,
+	detail: Option<String>,
+	range: Range,
+	selection_range: Range,
+) -> DocumentSymbol {
+	DocumentSymbol {
+		name,
+		detail,
+		kind,
+		tags: None,
+		deprecated: None,
+		range,
+		selection_range,
+		children: None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::DocumentSymbolHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_document_symbols() {
+		test_handler::<DocumentSymbolHandler, _, _>(
+			r#"
+enum Foo = {A, B};
+int: x = let { int: y = 1; } in y;
+function int: foo(int: a) = let { int: b = let { int: c = a; } in c; } in b;
+constraint x > 0;
+solve satisfy;
+			"#,
+			true,
+			lsp_types::DocumentSymbolParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "name": "Foo",
+          "kind": 10,
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 0
+            },
+            "end": {
+              "line": 1,
+              "character": 17
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 1,
+              "character": 5
+            },
+            "end": {
+              "line": 1,
+              "character": 8
+            }
+          },
+          "children": [
+            {
+              "name": "A",
+              "kind": 22,
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 12
+                },
+                "end": {
+                  "line": 1,
+                  "character": 13
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 1,
+                  "character": 12
+                },
+                "end": {
+                  "line": 1,
+                  "character": 13
+                }
+              }
+            },
+            {
+              "name": "B",
+              "kind": 22,
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 15
+                },
+                "end": {
+                  "line": 1,
+                  "character": 16
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 1,
+                  "character": 15
+                },
+                "end": {
+                  "line": 1,
+                  "character": 16
+                }
+              }
+            }
+          ]
+        },
+        {
+          "name": "x",
+          "detail": "int",
+          "kind": 13,
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 0
+            },
+            "end": {
+              "line": 2,
+              "character": 33
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 2,
+              "character": 5
+            },
+            "end": {
+              "line": 2,
+              "character": 6
+            }
+          },
+          "children": [
+            {
+              "name": "y",
+              "detail": "int",
+              "kind": 13,
+              "range": {
+                "start": {
+                  "line": 2,
+                  "character": 15
+                },
+                "end": {
+                  "line": 2,
+                  "character": 25
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 2,
+                  "character": 20
+                },
+                "end": {
+                  "line": 2,
+                  "character": 21
+                }
+              }
+            }
+          ]
+        },
+        {
+          "name": "foo",
+          "detail": "function int: foo(int)",
+          "kind": 12,
+          "range": {
+            "start": {
+              "line": 3,
+              "character": 0
+            },
+            "end": {
+              "line": 3,
+              "character": 75
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 3,
+              "character": 14
+            },
+            "end": {
+              "line": 3,
+              "character": 17
+            }
+          },
+          "children": [
+            {
+              "name": "b",
+              "detail": "int",
+              "kind": 13,
+              "range": {
+                "start": {
+                  "line": 3,
+                  "character": 34
+                },
+                "end": {
+                  "line": 3,
+                  "character": 67
+                }
+              },
+              "selectionRange": {
+                "start": {
+                  "line": 3,
+                  "character": 39
+                },
+                "end": {
+                  "line": 3,
+                  "character": 40
+                }
+              },
+              "children": [
+                {
+                  "name": "c",
+                  "detail": "int",
+                  "kind": 13,
+                  "range": {
+                    "start": {
+                      "line": 3,
+                      "character": 49
+                    },
+                    "end": {
+                      "line": 3,
+                      "character": 59
+                    }
+                  },
+                  "selectionRange": {
+                    "start": {
+                      "line": 3,
+                      "character": 54
+                    },
+                    "end": {
+                      "line": 3,
+                      "character": 55
+                    }
+                  }
+                }
+              ]
+            }
+          ]
+        },
+        {
+          "name": "constraint",
+          "kind": 17,
+          "range": {
+            "start": {
+              "line": 4,
+              "character": 0
+            },
+            "end": {
+              "line": 4,
+              "character": 16
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 4,
+              "character": 0
+            },
+            "end": {
+              "line": 4,
+              "character": 16
+            }
+          }
+        },
+        {
+          "name": "solve satisfy",
+          "kind": 24,
+          "range": {
+            "start": {
+              "line": 5,
+              "character": 0
+            },
+            "end": {
+              "line": 5,
+              "character": 13
+            }
+          },
+          "selectionRange": {
+            "start": {
+              "line": 5,
+              "character": 0
+            },
+            "end": {
+              "line": 5,
+              "character": 13
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+}
@@ -0,0 +1,138 @@
+use lsp_server::ResponseError;
+use lsp_types::{request::FoldingRangeRequest, FoldingRange, FoldingRangeKind, FoldingRangeParams};
+use shackle_compiler::{db::CompilerDatabase, file::ModelRef, syntax::db::SourceParser};
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler};
+
+#[derive(Debug)]
+pub struct FoldingRangeHandler;
+
+impl RequestHandler<FoldingRangeRequest, ModelRef> for FoldingRangeHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: FoldingRangeParams,
+	) -> Result<ModelRef, ResponseError> {
+		db.set_active_file_from_document(&params.text_document)
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		model_ref: ModelRef,
+	) -> Result<Option<Vec<FoldingRange>>, ResponseError> {
+		let Ok(cst) = db.cst(*model_ref) else {
+			return Ok(None);
+		};
+		let mut ranges = Vec::new();
+		let mut cursor = cst.root_node().walk();
+		'outer: loop {
+			let node = cursor.node();
+			let kind = match node.kind() {
+				"block_comment" => Some(FoldingRangeKind::Comment),
+				"let_expression"
+				| "array_literal"
+				| "array_literal_2d"
+				| "array_comprehension"
+				| "set_comprehension" => Some(FoldingRangeKind::Region),
+				"function_item" | "predicate" if node.child_by_field_name("body").is_some() => {
+					Some(FoldingRangeKind::Region)
+				}
+				_ => None,
+			};
+			let start = node.start_position().row;
+			let end = node.end_position().row;
+			if let Some(kind) = kind {
+				if start < end {
+					ranges.push(FoldingRange {
+						start_line: start as u32,
+						start_character: None,
+						end_line: end as u32,
+						end_character: None,
+						kind: Some(kind),
+						collapsed_text: None,
+					});
+				}
+			}
+			// Only descend into nodes spanning multiple lines
+			if start < end && cursor.goto_first_child() {
+				continue;
+			}
+			while !cursor.goto_next_sibling() {
+				if !cursor.goto_parent() {
+					break 'outer;
+				}
+			}
+		}
+		Ok(Some(ranges))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::FoldingRangeHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_folding_ranges() {
+		test_handler::<FoldingRangeHandler, _, _>(
+			r#"
+/*
+ * A comment
+ */
+function int: foo(int: x) =
+	let {
+		int: y = x;
+	} in y;
+array [int] of int: a = [
+	foo(i) | i in 1..3
+];
+array [int, int] of int: b = [|
+	1, 2 |
+	3, 4
+|];
+"#,
+			true,
+			lsp_types::FoldingRangeParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "startLine": 1,
+          "endLine": 3,
+          "kind": "comment"
+        },
+        {
+          "startLine": 4,
+          "endLine": 7,
+          "kind": "region"
+        },
+        {
+          "startLine": 5,
+          "endLine": 7,
+          "kind": "region"
+        },
+        {
+          "startLine": 8,
+          "endLine": 10,
+          "kind": "region"
+        },
+        {
+          "startLine": 11,
+          "endLine": 14,
+          "kind": "region"
+        }
+      ]
+    }"#]],
+		)
+	}
+}
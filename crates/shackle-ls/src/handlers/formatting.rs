@@ -0,0 +1,203 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::{Formatting, RangeFormatting},
+	DocumentFormattingParams, DocumentRangeFormattingParams, FormattingOptions, Position, Range,
+	TextEdit,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	syntax::{
+		db::SourceParser,
+		format::{format, format_range, FormatOptions},
+	},
+};
+
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{offset_to_position, position_to_offset},
+};
+
+#[derive(Debug)]
+pub struct FormattingHandler;
+
+impl RequestHandler<Formatting, (ModelRef, FormatOptions)> for FormattingHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentFormattingParams,
+	) -> Result<(ModelRef, FormatOptions), ResponseError> {
+		let model_ref = db.set_active_file_from_document(&params.text_document)?;
+		Ok((model_ref, format_options(&params.options)))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, options): (ModelRef, FormatOptions),
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		let Ok(cst) = db.cst(*model_ref) else {
+			return Ok(None);
+		};
+		let text = cst.text();
+		Ok(format(&cst, &options).map(|formatted| {
+			if formatted == text {
+				return Vec::new();
+			}
+			vec![TextEdit {
+				range: Range {
+					start: Position::default(),
+					end: offset_to_position(text, text.len()),
+				},
+				new_text: formatted,
+			}]
+		}))
+	}
+}
+
+#[derive(Debug)]
+pub struct RangeFormattingHandler;
+
+impl RequestHandler<RangeFormatting, (ModelRef, FormatOptions, Range)> for RangeFormattingHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: DocumentRangeFormattingParams,
+	) -> Result<(ModelRef, FormatOptions, Range), ResponseError> {
+		let model_ref = db.set_active_file_from_document(&params.text_document)?;
+		Ok((model_ref, format_options(&params.options), params.range))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, options, range): (ModelRef, FormatOptions, Range),
+	) -> Result<Option<Vec<TextEdit>>, ResponseError> {
+		let Ok(cst) = db.cst(*model_ref) else {
+			return Ok(None);
+		};
+		let text = cst.text();
+		let range = position_to_offset(text, range.start)..position_to_offset(text, range.end);
+		Ok(
+			format_range(&cst, &options, range).map(|(span, formatted)| {
+				if formatted == text[span.clone()] {
+					return Vec::new();
+				}
+				vec![TextEdit {
+					range: Range {
+						start: offset_to_position(text, span.start),
+						end: offset_to_position(text, span.end),
+					},
+					new_text: formatted,
+				}]
+			}),
+		)
+	}
+}
+
+fn format_options(options: &FormattingOptions) -> FormatOptions {
+	FormatOptions {
+		indent_width: options.tab_size as usize,
+		use_tabs: !options.insert_spaces,
+		..Default::default()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::Url;
+
+	use super::{FormattingHandler, RangeFormattingHandler};
+	use crate::handlers::test::test_handler;
+
+	fn options() -> lsp_types::FormattingOptions {
+		lsp_types::FormattingOptions {
+			tab_size: 4,
+			insert_spaces: true,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_formatting() {
+		test_handler::<FormattingHandler, _, _>(
+			r#"int:x=1;
+constraint let {int:y=x;} in y>0;"#,
+			true,
+			lsp_types::DocumentFormattingParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				options: options(),
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 0,
+              "character": 0
+            },
+            "end": {
+              "line": 1,
+              "character": 33
+            }
+          },
+          "newText": "int: x = 1;\nconstraint let { int: y = x; } in y > 0;\n"
+        }
+      ]
+    }"#]],
+		)
+	}
+
+	#[test]
+	fn test_range_formatting() {
+		test_handler::<RangeFormattingHandler, _, _>(
+			r#"int:x=1;
+int:y=2;
+int:z=3;"#,
+			true,
+			lsp_types::DocumentRangeFormattingParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				range: lsp_types::Range {
+					start: lsp_types::Position {
+						line: 1,
+						character: 2,
+					},
+					end: lsp_types::Position {
+						line: 1,
+						character: 4,
+					},
+				},
+				options: options(),
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 0
+            },
+            "end": {
+              "line": 1,
+              "character": 8
+            }
+          },
+          "newText": "int: y = 2;"
+        }
+      ]
+    }"#]],
+		)
+	}
+}
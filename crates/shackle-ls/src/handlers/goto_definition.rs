@@ -10,7 +10,11 @@ use shackle_compiler::{
 	},
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, position_to_point},
+};
 
 #[derive(Debug)]
 pub struct GotoDefinitionHandler;
@@ -22,10 +26,7 @@ impl RequestHandler<GotoDefinition, (ModelRef, Point)> for GotoDefinitionHandler
 	) -> Result<(ModelRef, Point), ResponseError> {
 		let model =
 			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
-		let start = Point {
-			row: params.text_document_position_params.position.line as usize,
-			column: params.text_document_position_params.position.character as usize,
-		};
+		let start = position_to_point(db, *model, params.text_document_position_params.position);
 		Ok((model, start))
 	}
 	fn execute(
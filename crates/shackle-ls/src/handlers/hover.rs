@@ -1,18 +1,24 @@
 use lsp_server::ResponseError;
 use lsp_types::{
 	request::HoverRequest, Hover, HoverContents, HoverParams, LanguageString, MarkedString,
+	MarkupContent, MarkupKind,
 };
 use shackle_compiler::{
 	db::CompilerDatabase,
 	file::ModelRef,
 	hir::{
 		db::Hir,
-		ids::{LocalEntityRef, NodeRef},
+		doc::{pattern_documentation, pattern_function_signature},
+		ids::{LocalEntityRef, NodeRef, PatternRef},
 		source::{find_node, Point},
 	},
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, position_to_point},
+};
 
 #[derive(Debug)]
 pub struct HoverHandler;
@@ -24,10 +30,7 @@ impl RequestHandler<HoverRequest, (ModelRef, Point)> for HoverHandler {
 	) -> Result<(ModelRef, Point), ResponseError> {
 		let model =
 			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
-		let start = Point {
-			row: params.text_document_position_params.position.line as usize,
-			column: params.text_document_position_params.position.character as usize,
-		};
+		let start = position_to_point(db, *model, params.text_document_position_params.position);
 		Ok((model, start))
 	}
 
@@ -46,21 +49,21 @@ impl RequestHandler<HoverRequest, (ModelRef, Point)> for HoverHandler {
 							let types = db.lookup_item_types(item);
 							let model = item.model(db);
 							let data = item.local_item_ref(db).data(&model);
+							let resolution = types.name_resolution(e);
 							let value =
 								types.pretty_print_expression_ty(db, data, e).or_else(|| {
-									let res = types.name_resolution(e)?;
+									let res = resolution?;
 									let types = db.lookup_item_types(res.item());
 									let model = res.item().model(db);
 									let data = res.item().local_item_ref(db).data(&model);
 									types.pretty_print_pattern_ty(db, data, res.pattern())
 								})?;
+							let value = resolution
+								.and_then(|p| pattern_function_signature(db, p))
+								.unwrap_or(value);
+							let doc = resolution.and_then(|p| pattern_documentation(db, p));
 							Some(Hover {
-								contents: HoverContents::Scalar(MarkedString::LanguageString(
-									LanguageString {
-										language: "minizinc".to_owned(),
-										value,
-									},
-								)),
+								contents: hover_contents(value, doc),
 								range: Some(node_ref_to_location(db, node)?.range),
 							})
 						}
@@ -68,13 +71,14 @@ impl RequestHandler<HoverRequest, (ModelRef, Point)> for HoverHandler {
 							let types = db.lookup_item_types(item);
 							let model = item.model(db);
 							let data = item.local_item_ref(db).data(&model);
+							let pattern = PatternRef::new(item, p);
+							let value = match pattern_function_signature(db, pattern) {
+								Some(signature) => signature,
+								None => types.pretty_print_pattern_ty(db, data, p)?,
+							};
+							let doc = pattern_documentation(db, pattern);
 							Some(Hover {
-								contents: HoverContents::Scalar(MarkedString::LanguageString(
-									LanguageString {
-										language: "minizinc".to_owned(),
-										value: types.pretty_print_pattern_ty(db, data, p)?,
-									},
-								)),
+								contents: hover_contents(value, doc),
 								range: Some(node_ref_to_location(db, node)?.range),
 							})
 						}
@@ -87,6 +91,20 @@ impl RequestHandler<HoverRequest, (ModelRef, Point)> for HoverHandler {
 	}
 }
 
+/// Show the type of a node, along with its documentation in markdown if there is any
+fn hover_contents(value: String, doc: Option<String>) -> HoverContents {
+	match doc {
+		Some(doc) => HoverContents::Markup(MarkupContent {
+			kind: MarkupKind::Markdown,
+			value: format!("```minizinc\n{}\n```\n\n{}", value, doc),
+		}),
+		None => HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
+			language: "minizinc".to_owned(),
+			value,
+		})),
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use std::str::FromStr;
@@ -141,4 +159,51 @@ any: y = x.1;
     }"#]),
 		)
 	}
+
+	#[test]
+	fn test_hover_documentation() {
+		test_handler::<HoverHandler, _, _>(
+			r#"
+/** Return \a x incremented by one
+  @param x: the value to increment
+*/
+function int: inc(int: x) = x;
+any: y = inc(1);
+			"#,
+			true,
+			lsp_types::HoverParams {
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position_params: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: lsp_types::Position {
+						line: 5,
+						character: 9,
+					},
+				},
+			},
+			expect!([r#"
+    {
+      "Ok": {
+        "contents": {
+          "kind": "markdown",
+          "value": "```minizinc\nfunction int: inc(int: x)\n```\n\nReturn `x` incremented by one\n\n**Parameters**\n\n- `x`: the value to increment"
+        },
+        "range": {
+          "start": {
+            "line": 5,
+            "character": 9
+          },
+          "end": {
+            "line": 5,
+            "character": 12
+          }
+        }
+      }
+    }"#]),
+		)
+	}
 }
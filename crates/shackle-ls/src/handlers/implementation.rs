@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::{GotoImplementation, GotoImplementationParams, GotoImplementationResponse},
+	Location,
+};
+use shackle_compiler::{
+	db::{CompilerDatabase, CompilerSettings, FileReader},
+	file::{FileRef, ModelRef},
+	hir::{db::Hir, ids::LocalItemRef, source::Point, Identifier},
+	syntax::{
+		ast::{AstNode, Item},
+		db::SourceParser,
+	},
+};
+
+use super::function_at;
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, path_to_uri, position_to_point, ts_node_to_range},
+};
+
+#[derive(Debug)]
+pub struct GotoImplementationHandler;
+
+impl RequestHandler<GotoImplementation, (ModelRef, Point)> for GotoImplementationHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: GotoImplementationParams,
+	) -> Result<(ModelRef, Point), ResponseError> {
+		let model_ref =
+			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
+		let position = params.text_document_position_params.position;
+		Ok((model_ref, position_to_point(db, *model_ref, position)))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, point): (ModelRef, Point),
+	) -> Result<Option<GotoImplementationResponse>, ResponseError> {
+		let Some(item) = function_at(db, model_ref, point) else {
+			return Ok(None);
+		};
+		let model = item.model(db);
+		let local_item = item.local_item_ref(db);
+		let LocalItemRef::Function(f) = local_item else {
+			return Ok(None);
+		};
+		let Some(name) = local_item.data(&model)[model[f].pattern].identifier() else {
+			return Ok(None);
+		};
+
+		// All overloads visible in the program
+		let overloads = db.lookup_global_function(name);
+		let mut locations = overloads
+			.iter()
+			.filter_map(|p| node_ref_to_location(db, p.into_entity(db)))
+			.collect::<Vec<_>>();
+
+		// Redefinitions in other library directories (e.g. solver-specific redefinitions)
+		let mut files = overloads
+			.iter()
+			.filter_map(|p| p.item().model_ref(db).path(db))
+			.collect::<Vec<_>>();
+		files.sort();
+		files.dedup();
+		for file in files {
+			locations.extend(redefinitions(db, &file, name));
+		}
+		locations.dedup();
+		Ok(Some(GotoImplementationResponse::Array(locations)))
+	}
+}
+
+/// Find declarations of the given function in files with the same name as `file` in other
+/// include search directories
+fn redefinitions(db: &CompilerDatabase, file: &Path, name: Identifier) -> Vec<Location> {
+	let (Some(file_name), Ok(canonical)) = (file.file_name(), file.canonicalize()) else {
+		return Vec::new();
+	};
+	let name = name.pretty_print(db);
+	let mut locations = Vec::new();
+	for dir in db.include_search_dirs().iter() {
+		let path: PathBuf = dir.join(file_name);
+		if !path.exists() || path.canonicalize().ok().as_ref() == Some(&canonical) {
+			continue;
+		}
+		let file = FileRef::new(&path, db);
+		let (Ok(model), Ok(text)) = (db.ast(file), db.file_contents(file)) else {
+			continue;
+		};
+		let Some(uri) = path_to_uri(&path) else {
+			continue;
+		};
+		for item in model.items() {
+			let id = match item {
+				Item::Function(f) => f.id(),
+				Item::Predicate(p) => p.id(),
+				_ => continue,
+			};
+			if id.name() == name {
+				locations.push(Location {
+					uri: uri.clone(),
+					range: ts_node_to_range(&text, *id.cst_node().as_ref()),
+				});
+			}
+		}
+	}
+	locations
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::{Position, TextDocumentIdentifier, TextDocumentPositionParams, Url};
+
+	use super::GotoImplementationHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_goto_implementation() {
+		test_handler::<GotoImplementationHandler, _, _>(
+			r#"
+function int: foo(int: x) = x;
+function float: foo(float: x) = x;
+predicate bar(bool: b) = b;
+int: y = foo(1);
+"#,
+			true,
+			lsp_types::request::GotoImplementationParams {
+				text_document_position_params: TextDocumentPositionParams {
+					text_document: TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: Position::new(4, 10),
+				},
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "uri": "file:///test.mzn",
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 14
+            },
+            "end": {
+              "line": 1,
+              "character": 17
+            }
+          }
+        },
+        {
+          "uri": "file:///test.mzn",
+          "range": {
+            "start": {
+              "line": 2,
+              "character": 16
+            },
+            "end": {
+              "line": 2,
+              "character": 19
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+}
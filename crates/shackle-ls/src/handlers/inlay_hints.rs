@@ -0,0 +1,327 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::InlayHintRequest, InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams, Range,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{EntityRef, ItemRef, LocalItemRef},
+		Expression, Generator, LetItem, Pattern, PatternTy,
+	},
+	syntax::db::SourceParser,
+	utils::arena::ArenaIndex,
+};
+
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{offset_to_position, position_to_offset},
+};
+
+#[derive(Debug)]
+pub struct InlayHintHandler;
+
+impl RequestHandler<InlayHintRequest, (ModelRef, Range)> for InlayHintHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: InlayHintParams,
+	) -> Result<(ModelRef, Range), ResponseError> {
+		let model_ref = db.set_active_file_from_document(&params.text_document)?;
+		Ok((model_ref, params.range))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, range): (ModelRef, Range),
+	) -> Result<Option<Vec<InlayHint>>, ResponseError> {
+		let Ok(cst) = db.cst(*model_ref) else {
+			return Ok(None);
+		};
+		let text = cst.text();
+		let start = position_to_offset(text, range.start);
+		let end = position_to_offset(text, range.end);
+		let mut hints = Vec::new();
+		for item in db.lookup_items(model_ref).iter() {
+			collect_item_hints(db, *item, &mut hints);
+		}
+		hints.retain(|(offset, _, _)| start <= *offset && *offset <= end);
+		hints.sort_by_key(|(offset, _, _)| *offset);
+		hints.dedup();
+		Ok(Some(
+			hints
+				.into_iter()
+				.map(|(offset, label, kind)| InlayHint {
+					position: offset_to_position(text, offset),
+					label: InlayHintLabel::String(label),
+					kind: Some(kind),
+					text_edits: None,
+					tooltip: None,
+					padding_left: None,
+					padding_right: None,
+					data: None,
+				})
+				.collect(),
+		))
+	}
+}
+
+/// Collect the inlay hints for an item as its byte offset, label and kind
+fn collect_item_hints(
+	db: &CompilerDatabase,
+	item: ItemRef,
+	hints: &mut Vec<(usize, String, InlayHintKind)>,
+) {
+	let model = item.model(db);
+	let local_item = item.local_item_ref(db);
+	let data = local_item.data(&model);
+	let types = db.lookup_item_types(item);
+	let sm = db.lookup_source_map(item.model_ref(db));
+	let span = |entity: EntityRef| {
+		let (_, span) = sm.get_origin(entity.into())?.source_span(db);
+		Some(span)
+	};
+
+	// Type hints for declarations without a complete type-inst
+	let mut untyped = Vec::new();
+	if let LocalItemRef::Declaration(d) = local_item {
+		if !data[model[d].declared_type].is_complete(data) {
+			untyped.push(model[d].pattern);
+		}
+	}
+	for (_, expression) in data.expressions.iter() {
+		match expression {
+			Expression::Let(l) => untyped.extend(l.items.iter().filter_map(|i| match i {
+				LetItem::Declaration(d) if !data[d.declared_type].is_complete(data) => {
+					Some(d.pattern)
+				}
+				_ => None,
+			})),
+			Expression::ArrayComprehension(c) => untyped.extend(generator_patterns(&c.generators)),
+			Expression::SetComprehension(c) => untyped.extend(generator_patterns(&c.generators)),
+			Expression::Lambda(l) => untyped.extend(
+				l.parameters
+					.iter()
+					.filter(|p| !data[p.declared_type].is_complete(data))
+					.filter_map(|p| p.pattern),
+			),
+			_ => (),
+		}
+	}
+	for pattern in untyped
+		.into_iter()
+		.flat_map(|p| Pattern::identifiers(p, data))
+	{
+		let ty = match types.get_pattern(pattern) {
+			Some(
+				PatternTy::Variable(ty) | PatternTy::Argument(ty) | PatternTy::Destructuring(ty),
+			) => ty,
+			_ => continue,
+		};
+		if ty.contains_error(db) {
+			continue;
+		}
+		if let Some(span) = span(EntityRef::new(db, item, pattern)) {
+			hints.push((
+				span.offset() + span.len(),
+				format!(": {}", ty.pretty_print(db)),
+				InlayHintKind::TYPE,
+			));
+		}
+	}
+
+	// Parameter name hints for call arguments
+	for (expression, e) in data.expressions.iter() {
+		let Expression::Call(c) = e else {
+			continue;
+		};
+		// Only show hints for explicit calls (not operators or generator calls)
+		let is_call = span(EntityRef::new(db, item, expression))
+			.and_then(|span| {
+				let cst = db.cst(*item.model_ref(db)).ok()?;
+				let node = cst
+					.root_node()
+					.descendant_for_byte_range(span.offset(), span.offset() + span.len())?;
+				Some(node.kind() == "call")
+			})
+			.unwrap_or(false);
+		if !is_call {
+			continue;
+		}
+		let Some(function) = types.name_resolution(c.function) else {
+			continue;
+		};
+		let function_model = function.item().model(db);
+		let function_item = function.item().local_item_ref(db);
+		let LocalItemRef::Function(f) = function_item else {
+			continue;
+		};
+		let function_data = function_item.data(&function_model);
+		for (parameter, argument) in function_model[f].parameters.iter().zip(c.arguments.iter()) {
+			let Some(name) = parameter
+				.pattern
+				.and_then(|p| function_data[p].identifier())
+				.map(|i| i.pretty_print(db))
+			else {
+				continue;
+			};
+			// Skip arguments which are already named after the parameter
+			if let Expression::Identifier(i) = data[*argument] {
+				if i.pretty_print(db) == name {
+					continue;
+				}
+			}
+			if let Some(span) = span(EntityRef::new(db, item, *argument)) {
+				hints.push((
+					span.offset(),
+					format!("{}: ", name),
+					InlayHintKind::PARAMETER,
+				));
+			}
+		}
+	}
+}
+
+/// Get the patterns declared by the given generators
+fn generator_patterns(generators: &[Generator]) -> impl '_ + Iterator<Item = ArenaIndex<Pattern>> {
+	generators.iter().flat_map(|g| match g {
+		Generator::Iterator { patterns, .. } => patterns.to_vec(),
+		Generator::Assignment { pattern, .. } => vec![*pattern],
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::{expect, Expect};
+	use lsp_types::{Position, Range, Url};
+
+	use super::InlayHintHandler;
+	use crate::handlers::test::test_handler;
+
+	fn check_inlay_hints(model: &str, expected: Expect) {
+		test_handler::<InlayHintHandler, _, _>(
+			model,
+			true,
+			lsp_types::InlayHintParams {
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				range: Range {
+					start: Position::new(0, 0),
+					end: Position::new(100, 0),
+				},
+			},
+			expected,
+		)
+	}
+
+	#[test]
+	fn test_type_hints() {
+		check_inlay_hints(
+			r#"
+any: x = 1;
+any: y = let { any: z = [x]; } in [(i, j) | i in z, j = {i}];
+any: f = lambda (int: a) => (a, a);
+"#,
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "position": {
+            "line": 1,
+            "character": 6
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 2,
+            "character": 6
+          },
+          "label": ": array [int] of tuple(int, set of int)",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 2,
+            "character": 21
+          },
+          "label": ": array [int] of int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 2,
+            "character": 45
+          },
+          "label": ": int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 2,
+            "character": 53
+          },
+          "label": ": set of int",
+          "kind": 1
+        },
+        {
+          "position": {
+            "line": 3,
+            "character": 6
+          },
+          "label": ": op(tuple(int, int): (int))",
+          "kind": 1
+        }
+      ]
+    }"#]],
+		)
+	}
+
+	#[test]
+	fn test_parameter_hints() {
+		check_inlay_hints(
+			r#"
+function int: foo(int: a, int: b) = a;
+int: a = 1;
+int: x = foo(a, foo(2, a));
+"#,
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "position": {
+            "line": 3,
+            "character": 16
+          },
+          "label": "b: ",
+          "kind": 2
+        },
+        {
+          "position": {
+            "line": 3,
+            "character": 20
+          },
+          "label": "a: ",
+          "kind": 2
+        },
+        {
+          "position": {
+            "line": 3,
+            "character": 23
+          },
+          "label": "b: ",
+          "kind": 2
+        }
+      ]
+    }"#]],
+		)
+	}
+}
@@ -1,20 +1,33 @@
+mod call_hierarchy;
+mod code_actions;
 mod completions;
+mod document_highlights;
+mod document_symbols;
+mod folding_ranges;
+mod formatting;
 mod goto_definition;
 mod hover;
+mod implementation;
+mod inlay_hints;
 mod references;
 mod rename_symbol;
+mod selection_ranges;
 mod semantic_tokens;
+mod signature_help;
 mod vfs;
 mod view_ast;
 mod view_cst;
 mod view_hir;
 mod view_pretty_print;
 mod view_scope;
+mod workspace_symbols;
 
 pub use self::{
-	completions::*, goto_definition::*, hover::*, references::*, rename_symbol::*,
-	semantic_tokens::*, vfs::*, view_ast::*, view_cst::*, view_hir::*, view_pretty_print::*,
-	view_scope::*,
+	call_hierarchy::*, code_actions::*, completions::*, document_highlights::*,
+	document_symbols::*, folding_ranges::*, formatting::*, goto_definition::*, hover::*,
+	implementation::*, inlay_hints::*, references::*, rename_symbol::*, selection_ranges::*,
+	semantic_tokens::*, signature_help::*, vfs::*, view_ast::*, view_cst::*, view_hir::*,
+	view_pretty_print::*, view_scope::*, workspace_symbols::*,
 };
 
 #[cfg(test)]
@@ -84,6 +97,9 @@ pub mod test {
 		fn get_workspace_uri(&self) -> Option<&lsp_types::Url> {
 			self.workspace.as_ref()
 		}
+		fn workspace_models(&self) -> Vec<PathBuf> {
+			vec![PathBuf::from_str("test.mzn").unwrap()]
+		}
 	}
 
 	pub fn run_handler<H, R, T>(
@@ -6,12 +6,17 @@ use shackle_compiler::{
 	hir::{
 		db::Hir,
 		ids::{LocalEntityRef, NodeRef, PatternRef},
-		source::{find_node, Point},
+		source::Point,
 	},
 	syntax::db::SourceParser,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use super::pattern_at;
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, position_to_point},
+};
 
 #[derive(Debug)]
 pub struct ReferencesHandler;
@@ -30,10 +35,7 @@ impl RequestHandler<References, ReferencesHandlerData> for ReferencesHandler {
 	) -> Result<ReferencesHandlerData, ResponseError> {
 		let model_ref =
 			db.set_active_file_from_document(&params.text_document_position.text_document)?;
-		let point = Point {
-			row: params.text_document_position.position.line as usize,
-			column: params.text_document_position.position.character as usize,
-		};
+		let point = position_to_point(db, *model_ref, params.text_document_position.position);
 		Ok(ReferencesHandlerData {
 			model_ref,
 			point,
@@ -46,57 +48,16 @@ impl RequestHandler<References, ReferencesHandlerData> for ReferencesHandler {
 		config: ReferencesHandlerData,
 	) -> Result<Option<Vec<Location>>, ResponseError> {
 		Ok((|| {
-			let node = find_node(db, *config.model_ref, config.point, config.point)?;
-			let pattern = match node {
-				NodeRef::Entity(e) => {
-					let item = e.item(db);
-					match e.entity(db) {
-						LocalEntityRef::Expression(e) => {
-							let types = db.lookup_item_types(item);
-							types.name_resolution(e)
-						}
-						LocalEntityRef::Pattern(p) => {
-							let types = db.lookup_item_types(item);
-							Some(
-								types
-									.pattern_resolution(p)
-									.unwrap_or_else(|| PatternRef::new(item, p)),
-							)
-						}
-						_ => None,
-					}
-				}
-				_ => None,
-			}?;
+			let pattern = pattern_at(db, config.model_ref, config.point).ok()??;
 			let models = db.resolve_includes().ok()?;
 			let mut locations = Vec::new();
 			for m in models.iter().copied() {
-				let cst = db.cst(*m).ok()?;
-				let query = tree_sitter::Query::new(
-					tree_sitter_minizinc::language(),
-					tree_sitter_minizinc::IDENTIFIERS_QUERY,
-				)
-				.expect("Failed to create query");
-				let mut cursor = tree_sitter::QueryCursor::new();
-				let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
-				let nodes = captures.map(|(c, _)| c.captures[0].node);
-				let source_map = db.lookup_source_map(m);
-				for node in nodes {
-					if let Some(node_ref @ NodeRef::Entity(entity)) = source_map.find_node(node) {
-						let item = entity.item(db);
-						let types = db.lookup_item_types(item);
-						let def = match entity.entity(db) {
-							LocalEntityRef::Expression(e) => types.name_resolution(e),
-							LocalEntityRef::Pattern(p) if config.include_decl => {
-								Some(PatternRef::new(item, p))
-							}
-							_ => None,
-						};
-						if def == Some(pattern) {
-							if let Some(loc) = node_ref_to_location(db, node_ref) {
-								locations.push(loc);
-							}
-						}
+				for (node_ref, is_declaration) in find_references(db, pattern, m)? {
+					if is_declaration && !config.include_decl {
+						continue;
+					}
+					if let Some(loc) = node_ref_to_location(db, node_ref) {
+						locations.push(loc);
 					}
 				}
 			}
@@ -105,6 +66,42 @@ impl RequestHandler<References, ReferencesHandlerData> for ReferencesHandler {
 	}
 }
 
+/// Find the identifiers in a model which refer to the given pattern.
+///
+/// Returns the node for each identifier, and whether it is the declaration of the pattern.
+pub fn find_references(
+	db: &CompilerDatabase,
+	pattern: PatternRef,
+	model_ref: ModelRef,
+) -> Option<Vec<(NodeRef, bool)>> {
+	let cst = db.cst(*model_ref).ok()?;
+	let query = tree_sitter::Query::new(
+		tree_sitter_minizinc::language(),
+		tree_sitter_minizinc::IDENTIFIERS_QUERY,
+	)
+	.expect("Failed to create query");
+	let mut cursor = tree_sitter::QueryCursor::new();
+	let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
+	let nodes = captures.map(|(c, _)| c.captures[0].node);
+	let source_map = db.lookup_source_map(model_ref);
+	let mut references = Vec::new();
+	for node in nodes {
+		if let Some(node_ref @ NodeRef::Entity(entity)) = source_map.find_node(node) {
+			let item = entity.item(db);
+			let types = db.lookup_item_types(item);
+			let (def, is_declaration) = match entity.entity(db) {
+				LocalEntityRef::Expression(e) => (types.name_resolution(e), false),
+				LocalEntityRef::Pattern(p) => (Some(PatternRef::new(item, p)), true),
+				_ => continue,
+			};
+			if def == Some(pattern) {
+				references.push((node_ref, is_declaration));
+			}
+		}
+	}
+	Some(references)
+}
+
 #[cfg(test)]
 mod test {
 	use std::str::FromStr;
@@ -14,7 +14,11 @@ use shackle_compiler::{
 	utils,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::node_ref_to_location};
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{node_ref_to_location, position_to_point},
+};
 
 #[derive(Debug)]
 pub struct RenameHandler;
@@ -38,12 +42,6 @@ impl RequestHandler<Rename, SymbolHandlerData> for RenameHandler {
 		db: &mut impl LanguageServerContext,
 		params: RenameParams,
 	) -> Result<SymbolHandlerData, ResponseError> {
-		// identifiers can consist of anything which is not in these chars
-		let cursor_pos = Point {
-			row: params.text_document_position.position.line as usize,
-			column: params.text_document_position.position.character as usize,
-		};
-
 		// cannot include single quotes
 		if params.new_name.chars().any(|ch| ch == '\'') {
 			return Err(create_error("Identifier cannot include single quotes"));
@@ -52,6 +50,7 @@ impl RequestHandler<Rename, SymbolHandlerData> for RenameHandler {
 		// the file it is in
 		let model_ref =
 			db.set_active_file_from_document(&params.text_document_position.text_document)?;
+		let cursor_pos = position_to_point(db, *model_ref, params.text_document_position.position);
 
 		// pretty print it to add single quotes, etc as necessary
 		let new_name = utils::pretty_print_identifier(&params.new_name);
@@ -67,77 +66,93 @@ impl RequestHandler<Rename, SymbolHandlerData> for RenameHandler {
 		db: &CompilerDatabase,
 		data: SymbolHandlerData,
 	) -> Result<Option<WorkspaceEdit>, ResponseError> {
-		// Find the node that is possibly going to be changed
-		let node: NodeRef = find_node(db, *data.model_ref, data.cursor_pos, data.cursor_pos)
-			.ok_or_else(|| create_error("Identifier not selected"))?;
-
-		let pattern: PatternRef = match node {
-			NodeRef::Entity(e) => {
-				let item = e.item(db);
-				match e.entity(db) {
-					LocalEntityRef::Expression(e) => db
-						.lookup_item_types(item)
-						.name_resolution(e)
-						.ok_or_else(|| create_error("Could not resolve pattern"))?,
-					LocalEntityRef::Pattern(p) => db
-						.lookup_item_types(item)
-						.pattern_resolution(p)
-						.unwrap_or_else(|| PatternRef::new(item, p)),
-					_ => return Ok(None), // Don't want a message in this case, so Ok(None) instead of an Err
-				}
-			}
-			_ => return Ok(None),
+		let Some(pattern) = pattern_at(db, data.model_ref, data.cursor_pos)? else {
+			return Ok(None); // Don't want a message in this case, so Ok(None) instead of an Err
 		};
+		Ok(Some(rename_pattern(db, pattern, &data.new_name)))
+	}
+}
 
-		let models = db.resolve_includes().ok().unwrap();
-		let mut edits = HashMap::new();
-
-		// loop over all the files included from the main file
-		for m in models.iter().copied() {
-			let cst = db.cst(*m).ok().unwrap();
-			let query = tree_sitter::Query::new(
-				tree_sitter_minizinc::language(),
-				tree_sitter_minizinc::IDENTIFIERS_QUERY,
-			)
-			.expect("Failed to create query");
-			let mut cursor = tree_sitter::QueryCursor::new();
-			let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
-			let nodes = captures.map(|(c, _)| c.captures[0].node);
-			let source_map = db.lookup_source_map(m);
-
-			// The edits to the current file
-			let mut model_edits = Vec::new();
-			let mut url = None;
-
-			// Loop over all the identifiers
-			for node in nodes {
-				if let Some(node_ref @ NodeRef::Entity(entity)) = source_map.find_node(node) {
-					let item = entity.item(db);
-					let types = db.lookup_item_types(item);
-					let def = match entity.entity(db) {
-						LocalEntityRef::Expression(e) => types.name_resolution(e),
-						LocalEntityRef::Pattern(p) => Some(PatternRef::new(item, p)),
-						_ => None,
-					};
-					// If the definition is matching, push it to be updated
-					if def == Some(pattern) {
-						if let Some(loc) = node_ref_to_location(db, node_ref) {
-							model_edits.push(TextEdit::new(loc.range, data.new_name.clone()));
-							url = Some(loc.uri);
-						}
-					}
-				}
+/// Find the pattern declaring the identifier at the given position
+pub fn pattern_at(
+	db: &CompilerDatabase,
+	model_ref: ModelRef,
+	cursor_pos: Point,
+) -> Result<Option<PatternRef>, ResponseError> {
+	// Find the node that is possibly going to be changed
+	let node: NodeRef = find_node(db, *model_ref, cursor_pos, cursor_pos)
+		.ok_or_else(|| create_error("Identifier not selected"))?;
+
+	let pattern = match node {
+		NodeRef::Entity(e) => {
+			let item = e.item(db);
+			match e.entity(db) {
+				LocalEntityRef::Expression(e) => db
+					.lookup_item_types(item)
+					.name_resolution(e)
+					.ok_or_else(|| create_error("Could not resolve pattern"))?,
+				LocalEntityRef::Pattern(p) => db
+					.lookup_item_types(item)
+					.pattern_resolution(p)
+					.unwrap_or_else(|| PatternRef::new(item, p)),
+				_ => return Ok(None),
 			}
+		}
+		_ => return Ok(None),
+	};
+	Ok(Some(pattern))
+}
 
-			// The file will be known iff there is an edit to change
-			if let Some(url) = url {
-				// Put it into the hashmap
-				edits.insert(url, model_edits);
+/// Get the edits which rename the given pattern and all references to it
+pub fn rename_pattern(db: &CompilerDatabase, pattern: PatternRef, new_name: &str) -> WorkspaceEdit {
+	let models = db.resolve_includes().ok().unwrap();
+	let mut edits = HashMap::new();
+
+	// loop over all the files included from the main file
+	for m in models.iter().copied() {
+		let cst = db.cst(*m).ok().unwrap();
+		let query = tree_sitter::Query::new(
+			tree_sitter_minizinc::language(),
+			tree_sitter_minizinc::IDENTIFIERS_QUERY,
+		)
+		.expect("Failed to create query");
+		let mut cursor = tree_sitter::QueryCursor::new();
+		let captures = cursor.captures(&query, cst.root_node(), cst.text().as_bytes());
+		let nodes = captures.map(|(c, _)| c.captures[0].node);
+		let source_map = db.lookup_source_map(m);
+
+		// The edits to the current file
+		let mut model_edits = Vec::new();
+		let mut url = None;
+
+		// Loop over all the identifiers
+		for node in nodes {
+			if let Some(node_ref @ NodeRef::Entity(entity)) = source_map.find_node(node) {
+				let item = entity.item(db);
+				let types = db.lookup_item_types(item);
+				let def = match entity.entity(db) {
+					LocalEntityRef::Expression(e) => types.name_resolution(e),
+					LocalEntityRef::Pattern(p) => Some(PatternRef::new(item, p)),
+					_ => None,
+				};
+				// If the definition is matching, push it to be updated
+				if def == Some(pattern) {
+					if let Some(loc) = node_ref_to_location(db, node_ref) {
+						model_edits.push(TextEdit::new(loc.range, new_name.to_owned()));
+						url = Some(loc.uri);
+					}
+				}
 			}
 		}
 
-		Ok(Some(WorkspaceEdit::new(edits)))
+		// The file will be known iff there is an edit to change
+		if let Some(url) = url {
+			// Put it into the hashmap
+			edits.insert(url, model_edits);
+		}
 	}
+
+	WorkspaceEdit::new(edits)
 }
 
 #[cfg(test)]
@@ -0,0 +1,166 @@
+use lsp_server::ResponseError;
+use lsp_types::{request::SelectionRangeRequest, Position, SelectionRange, SelectionRangeParams};
+use shackle_compiler::{db::CompilerDatabase, file::ModelRef, syntax::db::SourceParser};
+
+use crate::{
+	db::LanguageServerContext,
+	dispatch::RequestHandler,
+	utils::{position_to_point, ts_node_to_range},
+};
+
+#[derive(Debug)]
+pub struct SelectionRangeHandler;
+
+impl RequestHandler<SelectionRangeRequest, (ModelRef, Vec<Position>)> for SelectionRangeHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: SelectionRangeParams,
+	) -> Result<(ModelRef, Vec<Position>), ResponseError> {
+		let model_ref = db.set_active_file_from_document(&params.text_document)?;
+		Ok((model_ref, params.positions))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, positions): (ModelRef, Vec<Position>),
+	) -> Result<Option<Vec<SelectionRange>>, ResponseError> {
+		let Ok(cst) = db.cst(*model_ref) else {
+			return Ok(None);
+		};
+		let mut result = Vec::with_capacity(positions.len());
+		for position in positions {
+			let point = position_to_point(db, *model_ref, position);
+			let Some(node) = cst.root_node().descendant_for_point_range(point, point) else {
+				return Ok(None);
+			};
+			// Collect the ranges of the node and its ancestors from outermost to innermost
+			let mut ranges = Vec::new();
+			let mut current = Some(node);
+			while let Some(n) = current {
+				let range = ts_node_to_range(cst.text(), n);
+				if ranges.last() != Some(&range) {
+					ranges.push(range);
+				}
+				current = n.parent();
+			}
+			let selection = ranges
+				.into_iter()
+				.rev()
+				.fold(None, |parent, range| {
+					Some(SelectionRange {
+						range,
+						parent: parent.map(Box::new),
+					})
+				})
+				.expect("Node always has a range");
+			result.push(selection);
+		}
+		Ok(Some(result))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::expect;
+	use lsp_types::{Position, Url};
+
+	use super::SelectionRangeHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_selection_ranges() {
+		test_handler::<SelectionRangeHandler, _, _>(
+			r#"
+int: x = 1 + (2 * 3);
+"#,
+			true,
+			lsp_types::SelectionRangeParams {
+				text_document: lsp_types::TextDocumentIdentifier {
+					uri: Url::from_str("file:///test.mzn").unwrap(),
+				},
+				positions: vec![Position::new(1, 14)],
+				work_done_progress_params: Default::default(),
+				partial_result_params: Default::default(),
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "range": {
+            "start": {
+              "line": 1,
+              "character": 14
+            },
+            "end": {
+              "line": 1,
+              "character": 15
+            }
+          },
+          "parent": {
+            "range": {
+              "start": {
+                "line": 1,
+                "character": 14
+              },
+              "end": {
+                "line": 1,
+                "character": 19
+              }
+            },
+            "parent": {
+              "range": {
+                "start": {
+                  "line": 1,
+                  "character": 13
+                },
+                "end": {
+                  "line": 1,
+                  "character": 20
+                }
+              },
+              "parent": {
+                "range": {
+                  "start": {
+                    "line": 1,
+                    "character": 9
+                  },
+                  "end": {
+                    "line": 1,
+                    "character": 20
+                  }
+                },
+                "parent": {
+                  "range": {
+                    "start": {
+                      "line": 1,
+                      "character": 0
+                    },
+                    "end": {
+                      "line": 1,
+                      "character": 20
+                    }
+                  },
+                  "parent": {
+                    "range": {
+                      "start": {
+                        "line": 1,
+                        "character": 0
+                      },
+                      "end": {
+                        "line": 2,
+                        "character": 0
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+}
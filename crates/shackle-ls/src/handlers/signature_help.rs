@@ -0,0 +1,358 @@
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::SignatureHelpRequest, Documentation, MarkupContent, MarkupKind, ParameterInformation,
+	ParameterLabel, SignatureHelp, SignatureHelpParams, SignatureInformation,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::ModelRef,
+	hir::{
+		db::Hir,
+		ids::{LocalItemRef, PatternRef},
+		source::{find_expression, Point},
+		Expression, Identifier, PatternTy,
+	},
+	syntax::db::SourceParser,
+	ty::OverloadedFunction,
+};
+
+use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::position_to_point};
+
+#[derive(Debug)]
+pub struct SignatureHelpHandler;
+
+impl RequestHandler<SignatureHelpRequest, (ModelRef, Point)> for SignatureHelpHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: SignatureHelpParams,
+	) -> Result<(ModelRef, Point), ResponseError> {
+		let model =
+			db.set_active_file_from_document(&params.text_document_position_params.text_document)?;
+		let start = position_to_point(db, *model, params.text_document_position_params.position);
+		Ok((model, start))
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		(model_ref, start): (ModelRef, Point),
+	) -> Result<Option<SignatureHelp>, ResponseError> {
+		Ok((|| {
+			let cst = db.cst(*model_ref).ok()?;
+
+			// Collect the tokens before the cursor (the call may not be
+			// complete yet, so we cannot rely on the structure of the tree)
+			let mut tokens = Vec::new();
+			let mut cursor = cst.root_node().walk();
+			'outer: loop {
+				let node = cursor.node();
+				if node.start_position() >= start {
+					break;
+				}
+				if (node.child_count() == 0 || node.kind() == "quoted_identifier")
+					&& !node.is_missing()
+				{
+					tokens.push(node);
+				} else if cursor.goto_first_child() {
+					continue;
+				}
+				while !cursor.goto_next_sibling() {
+					if !cursor.goto_parent() {
+						break 'outer;
+					}
+				}
+			}
+
+			// Find the unclosed opening parenthesis of the call, counting the
+			// commas that follow it to find the active parameter
+			let mut depth = 0;
+			let mut active_parameter = 0;
+			let mut function = None;
+			for (i, token) in tokens.iter().enumerate().rev() {
+				match token.kind() {
+					")" | "]" | "}" => depth += 1,
+					"[" | "{" if depth > 0 => depth -= 1,
+					"(" if depth > 0 => depth -= 1,
+					"," if depth == 0 => active_parameter += 1,
+					"[" | "{" => return None,
+					"(" => {
+						function = Some(*tokens.get(i.checked_sub(1)?)?);
+						break;
+					}
+					_ => (),
+				}
+			}
+			let function = function?;
+			if !matches!(function.kind(), "identifier" | "quoted_identifier") {
+				return None;
+			}
+
+			// Resolve the called function
+			let (identifier, resolution) = match find_expression(
+				db,
+				*model_ref,
+				function.start_position(),
+				function.end_position(),
+			)
+			.and_then(|e| {
+				let model = e.item().model(db);
+				let data = e.item().local_item_ref(db).data(&model);
+				match data[e.expression()] {
+					Expression::Identifier(i) => Some((
+						i,
+						db.lookup_item_types(e.item())
+							.name_resolution(e.expression()),
+					)),
+					_ => None,
+				}
+			}) {
+				Some(r) => r,
+				None => {
+					let name = function.utf8_text(cst.text().as_bytes()).ok()?;
+					(Identifier::new(name.trim_matches('\''), db), None)
+				}
+			};
+
+			let mut active_signature = None;
+			let mut signatures = Vec::new();
+			for overload in db.lookup_global_function(identifier).iter() {
+				for f in function_overloads(db, *overload) {
+					if Some(*overload) == resolution {
+						active_signature = Some(signatures.len() as u32);
+					}
+					signatures.push(signature_information(
+						db,
+						*overload,
+						identifier,
+						&f,
+						active_parameter,
+					));
+				}
+			}
+			if signatures.is_empty() {
+				return None;
+			}
+			Some(SignatureHelp {
+				signatures,
+				active_signature,
+				active_parameter: Some(active_parameter),
+			})
+		})())
+	}
+}
+
+/// Get the function types declared by the given function pattern
+fn function_overloads(db: &CompilerDatabase, pattern: PatternRef) -> Vec<OverloadedFunction> {
+	let types = db.lookup_item_types(pattern.item());
+	match types.get_pattern(pattern.pattern()) {
+		Some(PatternTy::Function(f)) | Some(PatternTy::AnnotationConstructor(f)) => {
+			vec![f.overload.clone()]
+		}
+		Some(PatternTy::EnumConstructor(ecs)) => ecs.iter().map(|ec| ec.overload.clone()).collect(),
+		_ => Vec::new(),
+	}
+}
+
+/// Create the signature information for a function overload, using the
+/// parameter names from its declaration if available
+fn signature_information(
+	db: &CompilerDatabase,
+	pattern: PatternRef,
+	identifier: Identifier,
+	function: &OverloadedFunction,
+	active_parameter: u32,
+) -> SignatureInformation {
+	let model = pattern.item().model(db);
+	let local_item = pattern.item().local_item_ref(db);
+	let doc = model.docs.get(&local_item);
+	let names = match local_item {
+		LocalItemRef::Function(f) => {
+			let data = local_item.data(&model);
+			model[f]
+				.parameters
+				.iter()
+				.map(|p| p.pattern.and_then(|p| data[p].identifier()))
+				.collect()
+		}
+		_ => Vec::new(),
+	};
+
+	let item = function.pretty_print_item(db, identifier);
+	let call = function.pretty_print_call_signature(db, identifier);
+	let mut label = item.strip_suffix(&call).unwrap_or_default().to_owned();
+	label.push_str(&identifier.pretty_print(db));
+	label.push('(');
+	let mut parameters = Vec::with_capacity(function.params().len());
+	for (i, ty) in function.params().iter().enumerate() {
+		if i > 0 {
+			label.push_str(", ");
+		}
+		let start = label.encode_utf16().count() as u32;
+		label.push_str(&ty.pretty_print(db));
+		if let Some(name) = names.get(i).copied().flatten() {
+			label.push_str(": ");
+			label.push_str(&name.pretty_print(db));
+		}
+		let end = label.encode_utf16().count() as u32;
+		parameters.push(ParameterInformation {
+			label: ParameterLabel::LabelOffsets([start, end]),
+			documentation: names
+				.get(i)
+				.copied()
+				.flatten()
+				.and_then(|name| doc?.parameter(&name.lookup(db)))
+				.map(|d| Documentation::String(d.to_owned())),
+		});
+	}
+	label.push(')');
+
+	SignatureInformation {
+		label,
+		documentation: doc.filter(|d| !d.description.is_empty()).map(|d| {
+			Documentation::MarkupContent(MarkupContent {
+				kind: MarkupKind::Markdown,
+				value: d.description.clone(),
+			})
+		}),
+		active_parameter: if (active_parameter as usize) < parameters.len() {
+			Some(active_parameter)
+		} else {
+			None
+		},
+		parameters: Some(parameters),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::str::FromStr;
+
+	use expect_test::{expect, Expect};
+	use lsp_types::Url;
+
+	use super::SignatureHelpHandler;
+	use crate::handlers::test::test_handler;
+
+	fn check_signature_help(model: &str, line: u32, character: u32, expected: Expect) {
+		test_handler::<SignatureHelpHandler, _, _>(
+			model,
+			true,
+			lsp_types::SignatureHelpParams {
+				context: None,
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				text_document_position_params: lsp_types::TextDocumentPositionParams {
+					text_document: lsp_types::TextDocumentIdentifier {
+						uri: Url::from_str("file:///test.mzn").unwrap(),
+					},
+					position: lsp_types::Position { line, character },
+				},
+			},
+			expected,
+		)
+	}
+
+	#[test]
+	fn test_signature_help() {
+		check_signature_help(
+			r#"
+function int: foo(int: x, int: y) = x + y;
+function float: foo(float: a, float: b) = a + b;
+int: z = foo(1, 2);
+			"#,
+			3,
+			16,
+			expect![[r#"
+    {
+      "Ok": {
+        "signatures": [
+          {
+            "label": "function int: foo(int: x, int: y)",
+            "parameters": [
+              {
+                "label": [
+                  18,
+                  24
+                ]
+              },
+              {
+                "label": [
+                  26,
+                  32
+                ]
+              }
+            ],
+            "activeParameter": 1
+          },
+          {
+            "label": "function float: foo(float: a, float: b)",
+            "parameters": [
+              {
+                "label": [
+                  20,
+                  28
+                ]
+              },
+              {
+                "label": [
+                  30,
+                  38
+                ]
+              }
+            ],
+            "activeParameter": 1
+          }
+        ],
+        "activeSignature": 0,
+        "activeParameter": 1
+      }
+    }"#]],
+		);
+	}
+
+	#[test]
+	fn test_signature_help_nested() {
+		check_signature_help(
+			r#"
+predicate bar(array [int] of var int: xs, var int: y, int: c);
+function int: baz(int: x) = x;
+array [1..3] of var 1..3: x;
+constraint bar(x, baz(1), 
+			"#,
+			4,
+			26,
+			expect![[r#"
+    {
+      "Ok": {
+        "signatures": [
+          {
+            "label": "predicate bar(array [int] of var int: xs, var int: y, int: c)",
+            "parameters": [
+              {
+                "label": [
+                  14,
+                  40
+                ]
+              },
+              {
+                "label": [
+                  42,
+                  52
+                ]
+              },
+              {
+                "label": [
+                  54,
+                  60
+                ]
+              }
+            ],
+            "activeParameter": 2
+          }
+        ],
+        "activeParameter": 2
+      }
+    }"#]],
+		);
+	}
+}
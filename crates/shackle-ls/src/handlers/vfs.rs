@@ -19,14 +19,7 @@ pub fn on_document_changed(db: &mut LanguageServerDatabase, params: DidChangeTex
 		.uri
 		.to_file_path()
 		.expect("Failed to convert URI to file path");
-	db.manage_file(
-		file.as_path(),
-		&params
-			.content_changes
-			.iter()
-			.map(|c| c.text.clone())
-			.collect::<String>(),
-	);
+	db.change_file(file.as_path(), &params.content_changes);
 }
 
 pub fn on_document_closed(db: &mut LanguageServerDatabase, params: DidCloseTextDocumentParams) {
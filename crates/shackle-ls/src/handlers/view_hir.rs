@@ -11,7 +11,10 @@ use shackle_compiler::{
 	utils::DebugPrint,
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, extensions::ViewHir};
+use crate::{
+	db::LanguageServerContext, dispatch::RequestHandler, extensions::ViewHir,
+	utils::position_to_point,
+};
 
 #[derive(Debug)]
 pub struct ViewHirHandler;
@@ -22,10 +25,7 @@ impl RequestHandler<ViewHir, (ModelRef, Point)> for ViewHirHandler {
 		params: TextDocumentPositionParams,
 	) -> Result<(ModelRef, Point), ResponseError> {
 		let model_ref = db.set_active_file_from_document(&params.text_document)?;
-		let start = Point {
-			row: params.position.line as usize,
-			column: params.position.character as usize,
-		};
+		let start = position_to_point(db, *model_ref, params.position);
 		Ok((model_ref, start))
 	}
 
@@ -12,7 +12,10 @@ use shackle_compiler::{
 	},
 };
 
-use crate::{db::LanguageServerContext, dispatch::RequestHandler, extensions::ViewScope};
+use crate::{
+	db::LanguageServerContext, dispatch::RequestHandler, extensions::ViewScope,
+	utils::position_to_point,
+};
 
 #[derive(Debug)]
 pub struct ViewScopeHandler;
@@ -23,10 +26,7 @@ impl RequestHandler<ViewScope, (ModelRef, Point)> for ViewScopeHandler {
 		params: TextDocumentPositionParams,
 	) -> Result<(ModelRef, Point), ResponseError> {
 		let model_ref = db.set_active_file_from_document(&params.text_document)?;
-		let start = Point {
-			row: params.position.line as usize,
-			column: params.position.character as usize,
-		};
+		let start = position_to_point(db, *model_ref, params.position);
 		Ok((model_ref, start))
 	}
 
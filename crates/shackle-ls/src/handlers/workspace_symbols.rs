@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use lsp_server::ResponseError;
+use lsp_types::{
+	request::WorkspaceSymbolRequest, DocumentSymbol, Location, SymbolInformation, SymbolKind, Url,
+	WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use shackle_compiler::{
+	db::CompilerDatabase,
+	file::{FileRef, ModelRef},
+};
+
+use super::document_symbols;
+use crate::{db::LanguageServerContext, dispatch::RequestHandler, utils::path_to_uri};
+
+#[derive(Debug)]
+pub struct WorkspaceSymbolHandler;
+
+#[derive(Debug)]
+pub struct WorkspaceSymbolHandlerData {
+	query: String,
+	models: Vec<PathBuf>,
+}
+
+impl RequestHandler<WorkspaceSymbolRequest, WorkspaceSymbolHandlerData> for WorkspaceSymbolHandler {
+	fn prepare(
+		db: &mut impl LanguageServerContext,
+		params: WorkspaceSymbolParams,
+	) -> Result<WorkspaceSymbolHandlerData, ResponseError> {
+		Ok(WorkspaceSymbolHandlerData {
+			query: params.query,
+			models: db.workspace_models(),
+		})
+	}
+
+	fn execute(
+		db: &CompilerDatabase,
+		data: WorkspaceSymbolHandlerData,
+	) -> Result<Option<WorkspaceSymbolResponse>, ResponseError> {
+		let mut symbols = Vec::new();
+		for path in data.models.iter() {
+			let Some(uri) = path_to_uri(path) else {
+				continue;
+			};
+			let model = ModelRef::from(FileRef::new(path, db));
+			for s in document_symbols(db, model) {
+				add_symbol(&mut symbols, &uri, &data.query, None, s);
+			}
+		}
+		Ok(Some(WorkspaceSymbolResponse::Flat(symbols)))
+	}
+}
+
+/// Add symbol and any enum constructors if they match the query
+#[allow(deprecated)]
+fn add_symbol(
+	symbols: &mut Vec<SymbolInformation>,
+	uri: &Url,
+	query: &str,
+	container_name: Option<String>,
+	s: DocumentSymbol,
+) {
+	if s.kind == SymbolKind::ENUM {
+		for c in s.children.iter().flatten() {
+			if c.kind == SymbolKind::ENUM_MEMBER {
+				add_symbol(symbols, uri, query, Some(s.name.clone()), c.clone());
+			}
+		}
+	}
+	if fuzzy_match(query, &s.name) {
+		symbols.push(SymbolInformation {
+			name: s.name,
+			kind: s.kind,
+			tags: None,
+			deprecated: None,
+			location: Location {
+				uri: uri.clone(),
+				range: s.selection_range,
+			},
+			container_name,
+		});
+	}
+}
+
+/// Whether the characters of the query appear in order in the name (ignoring case)
+fn fuzzy_match(query: &str, name: &str) -> bool {
+	let mut name = name.chars().flat_map(char::to_lowercase);
+	query
+		.chars()
+		.flat_map(char::to_lowercase)
+		.all(|q| name.any(|c| c == q))
+}
+
+#[cfg(test)]
+mod test {
+	use expect_test::expect;
+
+	use super::WorkspaceSymbolHandler;
+	use crate::handlers::test::test_handler;
+
+	#[test]
+	fn test_workspace_symbols() {
+		test_handler::<WorkspaceSymbolHandler, _, _>(
+			r#"
+enum Colour = {Red, Green};
+predicate my_cumulative(array [int] of var int: s);
+predicate other(var int: x);
+int: cumul_count;
+			"#,
+			true,
+			lsp_types::WorkspaceSymbolParams {
+				query: "cumul".to_owned(),
+				work_done_progress_params: lsp_types::WorkDoneProgressParams {
+					work_done_token: None,
+				},
+				partial_result_params: lsp_types::PartialResultParams {
+					partial_result_token: None,
+				},
+			},
+			expect![[r#"
+    {
+      "Ok": [
+        {
+          "name": "my_cumulative",
+          "kind": 12,
+          "location": {
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 2,
+                "character": 10
+              },
+              "end": {
+                "line": 2,
+                "character": 23
+              }
+            }
+          }
+        },
+        {
+          "name": "cumul_count",
+          "kind": 13,
+          "location": {
+            "uri": "file:///test.mzn",
+            "range": {
+              "start": {
+                "line": 4,
+                "character": 5
+              },
+              "end": {
+                "line": 4,
+                "character": 16
+              }
+            }
+          }
+        }
+      ]
+    }"#]],
+		)
+	}
+}
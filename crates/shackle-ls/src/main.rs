@@ -4,9 +4,11 @@ use db::LanguageServerDatabase;
 use lsp_server::{Connection, ExtractError, Message};
 use lsp_types::{
 	notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument},
-	CompletionOptions, HoverProviderCapability, InitializeParams, OneOf, SemanticTokensFullOptions,
+	CallHierarchyServerCapability, CodeActionProviderCapability, CompletionOptions,
+	FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+	InitializeParams, OneOf, SelectionRangeProviderCapability, SemanticTokensFullOptions,
 	SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
-	ServerCapabilities, TextDocumentSyncKind,
+	ServerCapabilities, SignatureHelpOptions, TextDocumentSyncKind,
 };
 
 use crate::{
@@ -37,14 +39,29 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
 
 	let server_capabilities = serde_json::to_value(ServerCapabilities {
 		definition_provider: Some(OneOf::Left(true)),
+		document_symbol_provider: Some(OneOf::Left(true)),
+		document_formatting_provider: Some(OneOf::Left(true)),
+		document_range_formatting_provider: Some(OneOf::Left(true)),
+		workspace_symbol_provider: Some(OneOf::Left(true)),
 		references_provider: Some(OneOf::Left(true)),
-		text_document_sync: Some(TextDocumentSyncKind::FULL.into()),
+		document_highlight_provider: Some(OneOf::Left(true)),
+		folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+		selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+		text_document_sync: Some(TextDocumentSyncKind::INCREMENTAL.into()),
 		hover_provider: Some(HoverProviderCapability::Simple(true)),
+		inlay_hint_provider: Some(OneOf::Left(true)),
 		rename_provider: Some(OneOf::Left(true)),
+		code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+		call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+		implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
 		completion_provider: Some(CompletionOptions {
 			trigger_characters: Some(vec![".".to_owned()]),
 			..Default::default()
 		}),
+		signature_help_provider: Some(SignatureHelpOptions {
+			trigger_characters: Some(vec!["(".to_owned(), ",".to_owned()]),
+			..Default::default()
+		}),
 		semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
 			SemanticTokensOptions {
 				full: Some(SemanticTokensFullOptions::Delta { delta: Some(false) }),
@@ -88,8 +105,22 @@ fn main_loop(
 					.on::<GotoDefinitionHandler, _, _>()
 					.on::<ReferencesHandler, _, _>()
 					.on::<RenameHandler, _, _>()
+					.on::<CodeActionHandler, _, _>()
 					.on::<HoverHandler, _, _>()
+					.on::<InlayHintHandler, _, _>()
+					.on::<CallHierarchyPrepareHandler, _, _>()
+					.on::<CallHierarchyIncomingCallsHandler, _, _>()
+					.on::<CallHierarchyOutgoingCallsHandler, _, _>()
+					.on::<GotoImplementationHandler, _, _>()
+					.on::<DocumentHighlightHandler, _, _>()
+					.on::<FoldingRangeHandler, _, _>()
+					.on::<SelectionRangeHandler, _, _>()
 					.on::<CompletionsHandler, _, _>()
+					.on::<SignatureHelpHandler, _, _>()
+					.on::<DocumentSymbolHandler, _, _>()
+					.on::<WorkspaceSymbolHandler, _, _>()
+					.on::<FormattingHandler, _, _>()
+					.on::<RangeFormattingHandler, _, _>()
 					.on::<SemanticTokensHandler, _, _>()
 					.finish();
 
@@ -1,8 +1,12 @@
-use std::str::FromStr;
+use std::{path::Path, str::FromStr};
 
-use lsp_types::Url;
+use lsp_types::{Position, Url};
 use miette::{SourceCode, SpanContents};
-use shackle_compiler::hir::{db::Hir, ids::NodeRef};
+use shackle_compiler::{
+	db::{CompilerDatabase, FileReader},
+	file::FileRef,
+	hir::{db::Hir, ids::NodeRef, source::Point},
+};
 
 pub fn span_contents_to_range(r: &dyn SpanContents) -> lsp_types::Range {
 	let mut range = lsp_types::Range::default();
@@ -32,9 +36,81 @@ pub fn node_ref_to_location<T: Into<NodeRef>>(
 ) -> Option<lsp_types::Location> {
 	let (src, span) = node.into().source_span(db);
 	let span_contents = src.read_span(&span, 0, 0).ok()?;
-	let uri = Url::from_file_path(src.path()?)
-		.ok()
-		.or_else(|| Url::from_str(&format!("file:///{}", src.path()?.to_string_lossy())).ok())?;
+	let uri = path_to_uri(src.path()?)?;
 	let range = span_contents_to_range(&*span_contents);
 	Some(lsp_types::Location { uri, range })
 }
+
+pub fn path_to_uri(path: &Path) -> Option<Url> {
+	Url::from_file_path(path)
+		.ok()
+		.or_else(|| Url::from_str(&format!("file:///{}", path.to_string_lossy())).ok())
+}
+
+/// Get the byte offset of a position given in UTF-16 code units
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+	let mut offset = 0;
+	for _ in 0..position.line {
+		match text[offset..].find('\n') {
+			Some(i) => offset += i + 1,
+			None => return text.len(),
+		}
+	}
+	let mut units = 0;
+	for (i, c) in text[offset..].char_indices() {
+		if c == '\n' || units >= position.character as usize {
+			return offset + i;
+		}
+		units += c.len_utf16();
+	}
+	text.len()
+}
+
+/// Get the position (in UTF-16 code units) of the given byte offset in the text
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+	let before = &text[..offset];
+	let start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+	Position {
+		line: before.matches('\n').count() as u32,
+		character: before[start..].encode_utf16().count() as u32,
+	}
+}
+
+/// Get the syntax tree point (which uses a byte column) of a position in the given file
+pub fn position_to_point(db: &CompilerDatabase, file: FileRef, position: Position) -> Point {
+	let text = db.file_contents(file).unwrap_or_default();
+	let offset = position_to_offset(&text, position);
+	let start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+	Point {
+		row: position.line as usize,
+		column: offset - start,
+	}
+}
+
+/// Get the range of a syntax tree node in the given text
+pub fn ts_node_to_range(text: &str, node: tree_sitter::Node) -> lsp_types::Range {
+	lsp_types::Range {
+		start: offset_to_position(text, node.start_byte()),
+		end: offset_to_position(text, node.end_byte()),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use lsp_types::Position;
+
+	use super::{offset_to_position, position_to_offset};
+
+	#[test]
+	fn test_utf16_positions() {
+		let text = "string: s = \"😀\";\nint: x;";
+		let offset = text.find("\";").unwrap();
+		let position = offset_to_position(text, offset);
+		assert_eq!(position, Position::new(0, 15));
+		assert_eq!(position_to_offset(text, position), offset);
+		assert_eq!(
+			position_to_offset(text, Position::new(1, 5)),
+			text.find('x').unwrap()
+		);
+	}
+}
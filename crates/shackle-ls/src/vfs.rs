@@ -5,14 +5,17 @@ use std::{
 	sync::{Arc, Mutex},
 };
 
+use lsp_types::TextDocumentContentChangeEvent;
 use shackle_compiler::{diagnostics::FileError, file::FileHandler};
 
+use crate::utils::position_to_offset;
+
 /// Virtual filesystem allowing us to override file reads
 ///
 /// Uses a mutex internally so can be cloned and used by immutable reference.
 #[derive(Debug)]
 pub struct Vfs {
-	files: Arc<Mutex<HashMap<PathBuf, String>>>,
+	files: Arc<Mutex<HashMap<PathBuf, Arc<String>>>>,
 }
 
 impl Vfs {
@@ -24,15 +27,50 @@ impl Vfs {
 	}
 
 	/// Use the given string as the contents of this file instead of loading from the filesystem
-	pub fn manage_file(&self, file: &Path, contents: &str) {
+	pub fn manage_file(&self, file: &Path, contents: &str) -> Arc<String> {
+		let contents = Arc::new(contents.to_owned());
 		let mut guard = self.files.lock().unwrap();
-		guard.insert(file.to_owned(), contents.to_owned());
+		guard.insert(file.to_owned(), contents.clone());
+		contents
+	}
+
+	/// Apply the given changes to the contents of a managed file.
+	///
+	/// Returns `None` if the file is not managed.
+	pub fn change_file(
+		&self,
+		file: &Path,
+		changes: &[TextDocumentContentChangeEvent],
+	) -> Option<Arc<String>> {
+		let mut guard = self.files.lock().unwrap();
+		let contents = guard.get_mut(file)?;
+		let text = Arc::make_mut(contents);
+		for change in changes {
+			match change.range {
+				Some(range) => {
+					let start = position_to_offset(text, range.start);
+					let end = position_to_offset(text, range.end).max(start);
+					text.replace_range(start..end, &change.text);
+				}
+				None => {
+					text.clear();
+					text.push_str(&change.text);
+				}
+			}
+		}
+		Some(contents.clone())
+	}
+
+	/// Get the paths of the managed files
+	pub fn paths(&self) -> Vec<PathBuf> {
+		let guard = self.files.lock().unwrap();
+		guard.keys().cloned().collect()
 	}
 
 	/// Load the given file from the filesystem instead of using the managed contents
 	pub fn unmanage_file(&self, file: &Path) {
 		let mut guard = self.files.lock().unwrap();
-		guard.remove(&file.to_owned());
+		guard.remove(file);
 	}
 }
 
@@ -44,7 +82,7 @@ impl FileHandler for Vfs {
 	fn read_file(&self, path: &Path) -> Result<Arc<String>, FileError> {
 		let guard = self.files.lock().unwrap();
 		if let Some(s) = guard.get(path) {
-			return Ok(Arc::new(s.clone()));
+			return Ok(s.clone());
 		}
 
 		std::fs::read_to_string(path)
@@ -68,3 +106,44 @@ impl Clone for Vfs {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::path::Path;
+
+	use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+	use super::Vfs;
+
+	fn change(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+		TextDocumentContentChangeEvent {
+			range: Some(Range {
+				start: Position::new(start.0, start.1),
+				end: Position::new(end.0, end.1),
+			}),
+			range_length: None,
+			text: text.to_owned(),
+		}
+	}
+
+	#[test]
+	fn test_change_file() {
+		let vfs = Vfs::new();
+		let path = Path::new("test.mzn");
+		vfs.manage_file(path, "int: x = 1;\nstring: s = \"😀\"; int: y;\n");
+		let contents = vfs
+			.change_file(
+				path,
+				&[
+					change((0, 9), (0, 10), "2"),
+					change((1, 18), (1, 21), "bool"),
+					change((2, 0), (2, 0), "solve satisfy;\n"),
+				],
+			)
+			.unwrap();
+		assert_eq!(
+			contents.as_str(),
+			"int: x = 2;\nstring: s = \"😀\"; bool: y;\nsolve satisfy;\n"
+		);
+	}
+}
@@ -0,0 +1,400 @@
+//! On-disk cache of compiled programs.
+//!
+//! Entries are stored as JSON files named by a hash of the compiler version and build, the
+//! solver, the input models and the include search directories. Each entry records every file
+//! used by the model along with a hash of its contents, and is ignored if any of these files has
+//! changed.
+//!
+//! Entries contain the final THIR of the model, so that par expressions can be evaluated once
+//! data is added to a cached program without compiling the model again.
+//!
+//! The cache is best effort: failing to read or write an entry only produces a log message.
+
+use std::{
+	borrow::Cow,
+	env, fs,
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::SystemTime,
+};
+
+use rustc_hash::{FxHashMap, FxHasher};
+use serde::{Deserialize, Serialize};
+use shackle_compiler::{
+	db::{CompilerDatabase, CompilerSettings, FileReader, Inputs},
+	file::InputFile,
+	hir::db::Hir,
+	thir::{self, serialize::with_database},
+};
+use tempfile::NamedTempFile;
+
+use crate::{
+	value::{CtorArg, EnumInner, Index},
+	Enum, OptType, OutputSections, Program, Solver, Type,
+};
+
+/// Version of the cache entry format, to be incremented whenever it changes
+const CACHE_FORMAT: u32 = 3;
+
+/// A cached compiled program
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<'a> {
+	dependencies: Vec<Dependency>,
+	thir: Cow<'a, thir::Model>,
+	input_types: Vec<(String, CachedType)>,
+	output_types: Vec<(String, CachedType)>,
+	enums: Vec<CachedEnum>,
+	legacy_enums: Vec<String>,
+}
+
+/// A file used by a cached program
+#[derive(Serialize, Deserialize)]
+struct Dependency {
+	path: PathBuf,
+	hash: u64,
+}
+
+/// Serialisable version of [`Type`], which refers to enums by name
+#[derive(Serialize, Deserialize)]
+enum CachedType {
+	Boolean(bool),
+	Integer(bool),
+	Float(bool),
+	Enum(bool, String),
+	String(bool),
+	Annotation(bool),
+	Array {
+		opt: bool,
+		dim: Vec<CachedType>,
+		element: Box<CachedType>,
+	},
+	Set(bool, Box<CachedType>),
+	Tuple(bool, Vec<CachedType>),
+	Record(bool, Vec<(String, CachedType)>),
+}
+
+/// Serialisable version of an [`Enum`] which has not been initialised
+#[derive(Serialize, Deserialize)]
+struct CachedEnum {
+	name: String,
+	/// The constructors of the enum, or `None` if it is defined in data
+	constructors: Option<Vec<(String, Vec<CachedCtorArg>)>>,
+}
+
+/// Serialisable version of a [`CtorArg`]
+#[derive(Serialize, Deserialize)]
+enum CachedCtorArg {
+	Integer(i64, i64),
+	Enum(String),
+}
+
+fn hash_contents(contents: &str) -> u64 {
+	let mut hasher = FxHasher::default();
+	contents.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Identify the build of the running executable, so that builds of the same version do not
+/// share entries
+fn build_id() -> Option<(SystemTime, u64)> {
+	let metadata = fs::metadata(env::current_exe().ok()?).ok()?;
+	Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Get the path of the cache entry for the given model
+fn entry_path(directory: &Path, db: &CompilerDatabase, slv: &Solver) -> PathBuf {
+	let mut hasher = FxHasher::default();
+	CACHE_FORMAT.hash(&mut hasher);
+	env!("CARGO_PKG_VERSION").hash(&mut hasher);
+	build_id().hash(&mut hasher);
+	slv.ident.hash(&mut hasher);
+	for file in db.input_files().iter() {
+		match file {
+			InputFile::Path(p) => p
+				.canonicalize()
+				.unwrap_or_else(|_| p.clone())
+				.hash(&mut hasher),
+			InputFile::ModelString(s) | InputFile::DznString(s) | InputFile::JsonString(s) => {
+				s.hash(&mut hasher)
+			}
+		}
+	}
+	db.include_search_dirs().hash(&mut hasher);
+	db.ignore_stdlib().hash(&mut hasher);
+	directory.join(format!("{:016x}.json", hasher.finish()))
+}
+
+/// Load the program for the given model from the cache, giving back the database if there is no
+/// valid entry
+pub(crate) fn load(
+	directory: &Path,
+	db: CompilerDatabase,
+	slv: &Solver,
+) -> Result<Program, CompilerDatabase> {
+	match read(directory, &db, slv) {
+		Some(entry) => entry.into_program(db, slv),
+		None => Err(db),
+	}
+}
+
+/// Read the cache entry for the given model, if it is up to date
+fn read(directory: &Path, db: &CompilerDatabase, slv: &Solver) -> Option<CacheEntry<'static>> {
+	let path = entry_path(directory, db, slv);
+	let contents = fs::read_to_string(&path).ok()?;
+	let mut deserializer = serde_json::Deserializer::from_str(&contents);
+	// The nesting of the THIR is only limited by the model
+	deserializer.disable_recursion_limit();
+	let entry = with_database(db, || CacheEntry::deserialize(&mut deserializer));
+	let entry = match entry {
+		Ok(entry) => entry,
+		Err(e) => {
+			log::warn!("Ignoring invalid cache entry {}: {}", path.display(), e);
+			return None;
+		}
+	};
+	for dependency in entry.dependencies.iter() {
+		let current = fs::read_to_string(&dependency.path).ok();
+		if current.map(|c| hash_contents(&c)) != Some(dependency.hash) {
+			log::info!(
+				"Cache entry {} is stale as {} has changed",
+				path.display(),
+				dependency.path.display()
+			);
+			return None;
+		}
+	}
+	log::info!("Using cached program {}", path.display());
+	Some(entry)
+}
+
+/// Store a newly compiled program for the given model in the cache
+pub(crate) fn store(directory: &Path, db: &CompilerDatabase, slv: &Solver, program: &Program) {
+	let path = entry_path(directory, db, slv);
+	let Some(entry) = CacheEntry::new(db, program) else {
+		log::info!("Program cannot be cached");
+		return;
+	};
+	let result = fs::create_dir_all(directory)
+		.and_then(|_| NamedTempFile::new_in(directory))
+		.and_then(|mut file| {
+			with_database(db, || serde_json::to_writer(&mut file, &entry))?;
+			// Rename so that concurrent runs never see partially written entries
+			file.persist(&path).map_err(|e| e.error)?;
+			Ok(())
+		});
+	match result {
+		Ok(()) => log::info!("Stored program in cache {}", path.display()),
+		Err(e) => log::warn!("Unable to write cache entry {}: {}", path.display(), e),
+	}
+}
+
+impl<'a> CacheEntry<'a> {
+	/// Create the program for this entry using the database of the model
+	fn into_program(self, db: CompilerDatabase, slv: &Solver) -> Result<Program, CompilerDatabase> {
+		let enum_types = self
+			.enums
+			.into_iter()
+			.map(|e| {
+				let name: Arc<str> = e.name.into();
+				let e = match e.constructors {
+					Some(ctors) => Enum::model_defined(
+						name.clone(),
+						ctors.into_iter().map(|(ctor, args)| {
+							let args = args
+								.into_iter()
+								.map(|arg| match arg {
+									CachedCtorArg::Integer(lb, ub) => {
+										CtorArg::Index(Index::Integer(lb..=ub))
+									}
+									CachedCtorArg::Enum(e) => CtorArg::Enum(e.into()),
+								})
+								.collect();
+							(Arc::from(ctor), args)
+						}),
+					),
+					None => Enum::from_data(name.clone()),
+				};
+				(name, Arc::new(e))
+			})
+			.collect::<FxHashMap<_, _>>();
+		let types = |tys: Vec<(String, CachedType)>| {
+			tys.into_iter()
+				.map(|(name, ty)| Some((Arc::from(name), ty.to_type(&enum_types)?)))
+				.collect::<Option<FxHashMap<_, _>>>()
+		};
+		let (Some(input_types), Some(output_types), Some(legacy_enums)) = (
+			types(self.input_types),
+			types(self.output_types),
+			self.legacy_enums
+				.iter()
+				.map(|e| enum_types.get(e.as_str()).cloned())
+				.collect::<Option<Vec<_>>>(),
+		) else {
+			return Err(db);
+		};
+		Ok(Program {
+			db,
+			code: Arc::new(self.thir.into_owned()),
+			slv: slv.clone(),
+			input_types,
+			input_data: FxHashMap::default(),
+			enum_types,
+			legacy_enums,
+			output_types,
+			enable_stats: false,
+			time_limit: None,
+			output_sections: OutputSections::default(),
+		})
+	}
+
+	fn new(db: &CompilerDatabase, program: &'a Program) -> Option<Self> {
+		let mut dependencies = Vec::new();
+		for file in db.input_files().iter() {
+			if let InputFile::Path(p) = file {
+				let contents = fs::read_to_string(p).ok()?;
+				dependencies.push(Dependency {
+					path: p.clone(),
+					hash: hash_contents(&contents),
+				});
+			}
+		}
+		for model in db.resolve_includes().ok()?.iter() {
+			if let Some(path) = model.path(db) {
+				let contents = db.file_contents(**model).ok()?;
+				dependencies.push(Dependency {
+					path,
+					hash: hash_contents(&contents),
+				});
+			}
+		}
+		let types = |tys: &FxHashMap<Arc<str>, Type>| {
+			tys.iter()
+				.map(|(name, ty)| (name.to_string(), CachedType::from_type(ty)))
+				.collect()
+		};
+		let enums = program
+			.enum_types
+			.values()
+			.map(|e| CachedEnum::from_enum(e))
+			.collect::<Option<_>>()?;
+		Some(CacheEntry {
+			dependencies,
+			thir: Cow::Borrowed(&program.code),
+			input_types: types(&program.input_types),
+			output_types: types(&program.output_types),
+			enums,
+			legacy_enums: program
+				.legacy_enums
+				.iter()
+				.map(|e| e.name().to_string())
+				.collect(),
+		})
+	}
+}
+
+impl CachedType {
+	fn from_type(ty: &Type) -> Self {
+		let opt = |opt: &OptType| *opt == OptType::Opt;
+		match ty {
+			Type::Boolean(o) => CachedType::Boolean(opt(o)),
+			Type::Integer(o) => CachedType::Integer(opt(o)),
+			Type::Float(o) => CachedType::Float(opt(o)),
+			Type::Enum(o, e) => CachedType::Enum(opt(o), e.name().to_string()),
+			Type::String(o) => CachedType::String(opt(o)),
+			Type::Annotation(o) => CachedType::Annotation(opt(o)),
+			Type::Array {
+				opt: o,
+				dim,
+				element,
+			} => CachedType::Array {
+				opt: opt(o),
+				dim: dim.iter().map(CachedType::from_type).collect(),
+				element: Box::new(CachedType::from_type(element)),
+			},
+			Type::Set(o, e) => CachedType::Set(opt(o), Box::new(CachedType::from_type(e))),
+			Type::Tuple(o, fields) => {
+				CachedType::Tuple(opt(o), fields.iter().map(CachedType::from_type).collect())
+			}
+			Type::Record(o, fields) => CachedType::Record(
+				opt(o),
+				fields
+					.iter()
+					.map(|(name, ty)| (name.to_string(), CachedType::from_type(ty)))
+					.collect(),
+			),
+		}
+	}
+
+	fn to_type(&self, enums: &FxHashMap<Arc<str>, Arc<Enum>>) -> Option<Type> {
+		let opt = |opt: &bool| if *opt { OptType::Opt } else { OptType::NonOpt };
+		Some(match self {
+			CachedType::Boolean(o) => Type::Boolean(opt(o)),
+			CachedType::Integer(o) => Type::Integer(opt(o)),
+			CachedType::Float(o) => Type::Float(opt(o)),
+			CachedType::Enum(o, e) => Type::Enum(opt(o), enums.get(e.as_str())?.clone()),
+			CachedType::String(o) => Type::String(opt(o)),
+			CachedType::Annotation(o) => Type::Annotation(opt(o)),
+			CachedType::Array {
+				opt: o,
+				dim,
+				element,
+			} => Type::Array {
+				opt: opt(o),
+				dim: dim
+					.iter()
+					.map(|d| d.to_type(enums))
+					.collect::<Option<_>>()?,
+				element: Box::new(element.to_type(enums)?),
+			},
+			CachedType::Set(o, e) => Type::Set(opt(o), Box::new(e.to_type(enums)?)),
+			CachedType::Tuple(o, fields) => Type::Tuple(
+				opt(o),
+				fields
+					.iter()
+					.map(|f| f.to_type(enums))
+					.collect::<Option<_>>()?,
+			),
+			CachedType::Record(o, fields) => Type::Record(
+				opt(o),
+				fields
+					.iter()
+					.map(|(name, ty)| Some((Arc::from(name.as_str()), ty.to_type(enums)?)))
+					.collect::<Option<_>>()?,
+			),
+		})
+	}
+}
+
+impl CachedEnum {
+	/// Create a cached version of an enum, or `None` if it has already been initialised
+	fn from_enum(e: &Enum) -> Option<Self> {
+		let state = e.state.lock().unwrap();
+		let constructors = match &*state {
+			EnumInner::NoDefinition => None,
+			EnumInner::AwaitData(pending) => Some(
+				pending
+					.iter()
+					.map(|(name, args)| {
+						let args = args
+							.iter()
+							.map(|arg| match arg {
+								CtorArg::Index(Index::Integer(r)) => {
+									Some(CachedCtorArg::Integer(*r.start(), *r.end()))
+								}
+								CtorArg::Enum(e) => Some(CachedCtorArg::Enum(e.to_string())),
+								_ => None,
+							})
+							.collect::<Option<_>>()?;
+						Some((name.to_string(), args))
+					})
+					.collect::<Option<_>>()?,
+			),
+			EnumInner::Constructors(_) => return None,
+		};
+		Some(CachedEnum {
+			name: e.name().to_string(),
+			constructors,
+		})
+	}
+}
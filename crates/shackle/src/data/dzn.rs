@@ -21,7 +21,7 @@ use tree_sitter::Parser;
 
 use crate::{
 	data::ParserVal,
-	value::{EnumInner, Index, Polarity, Set},
+	value::{CtorArg, EnumInner, Index, Polarity, Set},
 	Enum, OptType, Type, Value,
 };
 
@@ -512,7 +512,7 @@ impl EnumInner {
 				Expression::SetLiteral(v) => {
 					for el in v.members() {
 						if let Expression::Identifier(ident) = el {
-							ctors.push((ident.name().into(), Vec::new().into_boxed_slice(), 1))
+							ctors.push((ident.name().into(), Vec::new().into_boxed_slice()))
 						} else {
 							return Err(SyntaxError {
 							src: file.clone(),
@@ -529,8 +529,12 @@ impl EnumInner {
 					let name: Arc<str> = c.function().cast::<Identifier>().unwrap().name().into();
 
 					let mut args = Vec::new();
-					let mut len = 0;
 					for arg in c.arguments() {
+						// Identifiers refer to (possibly not yet defined) enumerated types
+						if let Expression::Identifier(ident) = arg {
+							args.push(CtorArg::Enum(ident.name().into()));
+							continue;
+						}
 						let int_set_ty =
 							Type::Set(OptType::NonOpt, Box::new(Type::Integer(OptType::NonOpt)));
 						let val = collect_dzn_value(file, &arg, &int_set_ty)?;
@@ -541,10 +545,9 @@ impl EnumInner {
 						if x.len() != 1 {
 							todo!("handle non-continuous (and empty) integer sets for constructors")
 						}
-						args.push(Index::Integer(x[0].clone()));
-						len += args.last().unwrap().len();
+						args.push(CtorArg::Index(Index::Integer(x[0].clone())));
 					}
-					ctors.push((name, args.into_boxed_slice(), len))
+					ctors.push((name, args.into_boxed_slice()))
 				}
 				Expression::InfixOperator(op) => match op.operator().name() {
 					"++" => {
@@ -586,7 +589,7 @@ impl EnumInner {
 			}
 		}
 
-		*self = EnumInner::Constructors(ctors.into_boxed_slice());
+		*self = EnumInner::from_pending(ctors);
 		Ok(())
 	}
 }
@@ -831,6 +834,32 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_parse_opt_tuple() {
+		let ty = Type::Tuple(
+			OptType::Opt,
+			Arc::new([
+				Type::Integer(OptType::NonOpt),
+				Type::Boolean(OptType::NonOpt),
+			]),
+		);
+		check_serialization("<>", &ty, &expect!("<>"));
+		check_serialization("(1, true)", &ty, &expect!("(1, true)"));
+	}
+
+	#[test]
+	fn test_parse_opt_record() {
+		let ty = Type::Record(
+			OptType::Opt,
+			Arc::new([
+				("a".into(), Type::Integer(OptType::NonOpt)),
+				("b".into(), Type::Float(OptType::Opt)),
+			]),
+		);
+		check_serialization("<>", &ty, &expect!("<>"));
+		check_serialization("(a: 1, b: <>)", &ty, &expect!("(a: 1, b: <>)"));
+	}
+
 	#[test]
 	fn test_parse_record() {
 		let a: Arc<str> = "a".into();
@@ -1008,5 +1037,16 @@ mod tests {
 				expect!("Z(3)"),
 			],
 		);
+
+		check_enum_serialization(
+			"X(1..2, 1..3) ++ {Y}",
+			["X(1, 3)", "X(2, 1)", "Y"],
+			&[
+				expect!("A = X(1..2,1..3) ++ {Y}"),
+				expect!("X(1,3)"),
+				expect!("X(2,1)"),
+				expect!("Y"),
+			],
+		);
 	}
 }
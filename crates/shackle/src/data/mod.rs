@@ -69,7 +69,8 @@ impl ParserVal {
 				if args.len() != doms.len() {
 					todo!("add error for non-matching constructor call");
 				}
-				let mut offset = offset;
+				// Position of the arguments within the constructor (row-major order)
+				let mut pos = 0;
 				for (arg, dom) in args.into_iter().zip_eq(doms.iter()) {
 					match dom {
 						Index::Integer(r) => {
@@ -81,7 +82,7 @@ impl ParserVal {
 							if !r.contains(&arg) {
 								todo!("invalid argument - out of domain")
 							}
-							offset += (arg - r.start()) as usize;
+							pos = pos * dom.len() + (arg - r.start()) as usize;
 						}
 						Index::Enum(r) => {
 							let Value::Enum(arg) =
@@ -92,11 +93,14 @@ impl ParserVal {
 							if !r.contains(&arg) {
 								todo!("invalid argument - out of domain")
 							}
-							offset += r.start().int_val() - arg.int_val();
+							pos = pos * dom.len() + (arg.int_val() - r.start().int_val());
 						}
 					}
 				}
-				Ok(Value::Enum(EnumValue::from_enum_and_pos(e.clone(), offset)))
+				Ok(Value::Enum(EnumValue::from_enum_and_pos(
+					e.clone(),
+					offset + pos,
+				)))
 			}
 			ParserVal::Ann(_, _) => todo!(),
 			ParserVal::SimpleArray(ranges, elements) => {
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
@@ -10,7 +10,7 @@ use serde::{
 
 use super::ParserVal;
 use crate::{
-	value::{Array, Constructor, EnumInner, EnumValue, Index, Record, Set},
+	value::{Array, CtorArg, EnumInner, EnumValue, Index, PendingConstructor, Record, Set},
 	Enum, OptType, Type, Value,
 };
 
@@ -368,20 +368,66 @@ impl<'de, 'a> Visitor<'de> for SerdeArrayVisitor<'a> {
 
 impl<'de> Deserialize<'de> for EnumInner {
 	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		// Internal Visitor that deserialises a single constructor argument, which is
+		// either the name of an enumerated type or an integer range
+		#[derive(Clone)]
+		struct EnumCtorArg;
+		impl<'de> Visitor<'de> for EnumCtorArg {
+			type Value = CtorArg;
+
+			fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+				write!(
+					formatter,
+					"enumerated type name or integer set as constructor argument"
+				)
+			}
+
+			fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+				Ok(CtorArg::Enum(v.into()))
+			}
+			fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+				Ok(CtorArg::Enum(v.into()))
+			}
+
+			fn visit_map<A: serde::de::MapAccess<'de>>(
+				self,
+				map: A,
+			) -> Result<Self::Value, A::Error> {
+				let int_set = Type::Set(OptType::NonOpt, Box::new(Type::Integer(OptType::NonOpt)));
+				let val = SerdeValueVisitor(&int_set).visit_map(map)?;
+				let Value::Set(Set::Int(s)) = val.resolve_value(&int_set).unwrap() else {
+					unreachable!()
+				};
+				if s.len() != 1 {
+					todo!("handle non-continuous (and empty) integer sets for constructors")
+				}
+				Ok(CtorArg::Index(Index::Integer(s[0].clone())))
+			}
+		}
+		impl<'de> DeserializeSeed<'de> for EnumCtorArg {
+			type Value = <EnumCtorArg as Visitor<'de>>::Value;
+
+			fn deserialize<D: serde::Deserializer<'de>>(
+				self,
+				deserializer: D,
+			) -> Result<Self::Value, D::Error> {
+				deserializer.deserialize_any(self)
+			}
+		}
 		// Internal Visitor that deserialises a single constructor object
 		struct EnumCtor;
 		impl<'de> Visitor<'de> for EnumCtor {
-			type Value = Constructor;
+			type Value = PendingConstructor;
 
 			fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
 				write!(formatter, "enumerated type constructor")
 			}
 
 			fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
-				Ok((v.into(), Vec::new().into_boxed_slice(), 1))
+				Ok((v.into(), Vec::new().into_boxed_slice()))
 			}
 			fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
-				Ok((v.into(), Vec::new().into_boxed_slice(), 1))
+				Ok((v.into(), Vec::new().into_boxed_slice()))
 			}
 
 			fn visit_map<A: serde::de::MapAccess<'de>>(
@@ -394,43 +440,12 @@ impl<'de> Deserialize<'de> for EnumInner {
 				while let Some(k) = map.next_key()? {
 					match k {
 						"c" => name = Some(map.next_value()?),
-						"a" => {
-							let intset_list = Type::Array {
-								opt: OptType::NonOpt,
-								dim: Box::new([Type::Integer(OptType::NonOpt)]),
-								element: Box::new(Type::Set(
-									OptType::NonOpt,
-									Box::new(Type::Integer(OptType::NonOpt)),
-								)),
-							};
-							let val = map.next_value_seed(SerdeValueVisitor(&intset_list))?;
-							let Value::Array(x) = val.resolve_value(&intset_list).unwrap() else {
-								unreachable!()
-							};
-							args = x
-								.members
-								.iter()
-								.map(|i| {
-									let Value::Set(Set::Int(s)) = i else {
-										unreachable!()
-									};
-									if s.len() != 1 {
-										todo!("handle non-continuous (and empty) integer sets for constructors")
-									}
-									Index::Integer(s[0].clone())
-								})
-								.collect();
-						}
+						"a" => args = map.next_value_seed(SerdeSeqVisitor(EnumCtorArg))?,
 						_ => return Err(Error::unknown_field(k, FIELDS)),
 					}
 				}
-				let count = if args.is_empty() {
-					1
-				} else {
-					args.iter().map(|x| x.len()).sum()
-				};
 				if let Some(name) = name {
-					Ok((name.into(), args.into_boxed_slice(), count))
+					Ok((name.into(), args.into_boxed_slice()))
 				} else {
 					Err(Error::missing_field("c"))
 				}
@@ -449,7 +464,7 @@ impl<'de> Deserialize<'de> for EnumInner {
 		// Helper visitor that visits a list of enum constructors
 		struct EnumDefVisitor;
 		impl<'de> Visitor<'de> for EnumDefVisitor {
-			type Value = Box<[Constructor]>;
+			type Value = Vec<PendingConstructor>;
 
 			fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
 				write!(formatter, "list of constructors of an enumerated type")
@@ -463,12 +478,12 @@ impl<'de> Deserialize<'de> for EnumInner {
 				while let Some(el) = seq.next_element_seed(EnumCtor)? {
 					v.push(el);
 				}
-				Ok(v.into_boxed_slice())
+				Ok(v)
 			}
 		}
 
 		let ctors = deserializer.deserialize_seq(EnumDefVisitor)?;
-		Ok(EnumInner::Constructors(ctors))
+		Ok(EnumInner::from_pending(ctors))
 	}
 }
 
@@ -495,7 +510,9 @@ impl<'de, 'a> Visitor<'de> for SerdeFileVisitor<'a> {
 				if *inner == EnumInner::NoDefinition {
 					*inner = map.next_value::<EnumInner>()?;
 				} else {
-					todo!("create custom serde error that will be converted into Error")
+					return Err(A::Error::custom(format!(
+						"enumerated type \"{k}\" has already been defined"
+					)));
 				}
 			} else {
 				map.next_value::<IgnoredAny>()?; // Ignore unknown
@@ -505,6 +522,56 @@ impl<'de, 'a> Visitor<'de> for SerdeFileVisitor<'a> {
 	}
 }
 
+/// Find the byte ranges of the entries of the top-level object of the given JSON
+/// source, along with their keys.
+///
+/// The source is assumed to be valid JSON.
+pub(crate) fn json_entry_spans(contents: &str) -> Vec<(String, Range<usize>)> {
+	let bytes = contents.as_bytes();
+	let mut entries = Vec::new();
+	let mut current = None;
+	let mut depth = 0;
+	let mut i = 0;
+	let mut finish = |current: &mut Option<(String, usize)>, end: usize| {
+		if let Some((key, start)) = current.take() {
+			let end = start + contents[start..end].trim_end().len();
+			entries.push((key, start..end));
+		}
+	};
+	while i < bytes.len() {
+		match bytes[i] {
+			b'"' => {
+				let start = i;
+				i += 1;
+				while i < bytes.len() && bytes[i] != b'"' {
+					if bytes[i] == b'\\' {
+						i += 1;
+					}
+					i += 1;
+				}
+				if depth == 1 && current.is_none() {
+					let key = contents
+						.get(start..=i)
+						.and_then(|k| serde_json::from_str(k).ok())
+						.unwrap_or_default();
+					current = Some((key, start));
+				}
+			}
+			b'{' | b'[' => depth += 1,
+			b'}' | b']' => {
+				if depth == 1 {
+					finish(&mut current, i);
+				}
+				depth -= 1;
+			}
+			b',' if depth == 1 => finish(&mut current, i),
+			_ => (),
+		}
+		i += 1;
+	}
+	entries
+}
+
 struct SerdeSeqVisitor<X: Clone>(X);
 impl<'de, X: DeserializeSeed<'de> + Clone> DeserializeSeed<'de> for SerdeSeqVisitor<X> {
 	type Value = Vec<X::Value>;
@@ -747,6 +814,7 @@ impl Serialize for Index {
 	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
 		match self {
 			Index::Integer(r) => Set::Int(vec![r.clone()]).serialize(serializer),
+			Index::Enum(r) if r.is_whole_enum() => r.enum_type().name().serialize(serializer),
 			Index::Enum(_) => todo!(),
 		}
 	}
@@ -836,7 +904,7 @@ mod tests {
 	use serde::Deserializer;
 	use shackle_compiler::file::SourceFile;
 
-	use super::SerdeFileVisitor;
+	use super::{json_entry_spans, SerdeFileVisitor};
 	use crate::{Enum, Error, OptType, Type};
 
 	fn check_serialization(input: &str, ty: &Type, expected: &Expect) {
@@ -1083,6 +1151,32 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_parse_opt_tuple() {
+		let ty = Type::Tuple(
+			OptType::Opt,
+			Arc::new([
+				Type::Integer(OptType::NonOpt),
+				Type::Boolean(OptType::NonOpt),
+			]),
+		);
+		check_serialization("null", &ty, &expect!("<>"));
+		check_serialization("[1, true]", &ty, &expect!("(1, true)"));
+	}
+
+	#[test]
+	fn test_parse_opt_record() {
+		let ty = Type::Record(
+			OptType::Opt,
+			Arc::new([
+				("a".into(), Type::Integer(OptType::NonOpt)),
+				("b".into(), Type::Float(OptType::Opt)),
+			]),
+		);
+		check_serialization("null", &ty, &expect!("<>"));
+		check_serialization(r#"{"a": 1, "b": null}"#, &ty, &expect!("(a: 1, b: <>)"));
+	}
+
 	#[test]
 	fn test_parse_record() {
 		let a: Arc<str> = "a".into();
@@ -1274,4 +1368,18 @@ mod tests {
 			],
 		);
 	}
+
+	#[test]
+	fn test_json_entry_spans() {
+		let src = r#"{ "a": 1, "b\"c": {"d": [1, "}"]} ,"e":"f" }"#;
+		let spans = json_entry_spans(src)
+			.into_iter()
+			.map(|(k, r)| format!("{k}: {}", &src[r]))
+			.collect::<Vec<_>>();
+		expect![[r#"
+		    a: "a": 1
+		    b"c: "b\"c": {"d": [1, "}"]}
+		    e: "e":"f""#]]
+		.assert_eq(&spans.join("\n"));
+	}
 }
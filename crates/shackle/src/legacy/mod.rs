@@ -17,7 +17,7 @@ use tempfile::Builder;
 
 use crate::{
 	data::serde::SerdeValueVisitor,
-	error::{FileError, InternalError},
+	error::{FileError, InternalError, MissingValue},
 	value::{Array, EnumInner, EnumRangeInclusive, EnumValue, Index, Polarity, Set, Value},
 	Enum, Error, Message, OptType, Program, Result, Status, Type,
 };
@@ -58,13 +58,21 @@ impl Program {
 			} else if ty.is_opt() {
 				&Value::Absent
 			} else {
-				todo!("add new error type - {} is not initialized", name)
+				return Err(MissingValue {
+					kind: "parameter".to_owned(),
+					name: name.to_string(),
+				}
+				.into());
 			};
 			writeln!(file_mut, "{name} = {};", LegacyValue { val, ty }).map_err(write_err)?;
 		}
 		for e in &self.legacy_enums {
-			if e.state.lock().unwrap().deref() == &EnumInner::NoDefinition {
-				todo!("add new error type - {} is not initialized", e.name())
+			if !matches!(e.state.lock().unwrap().deref(), EnumInner::Constructors(_)) {
+				return Err(MissingValue {
+					kind: "enumerated type".to_owned(),
+					name: e.name().to_string(),
+				}
+				.into());
 			}
 			writeln!(file_mut, "{};", LegacyEnum(e)).map_err(write_err)?;
 		}
@@ -106,20 +114,8 @@ impl Program {
 					.into())
 				}
 				Ok(line) => {
-					match serde_json::Deserializer::from_str(&line)
-						.deserialize_map(SerdeMessageVisitor(&self.output_types))
-						.map_err(|e| Error::from_serde_json(e, &Arc::new(line.clone()).into()))?
-					{
-						LegacyOutput::Status(s) => status = s,
-						LegacyOutput::Msg(msg) => {
-							if let Message::Solution(_) = msg {
-								if status == Status::Unknown {
-									status = Status::Satisfied
-								}
-							}
-							msg_callback(&msg)?
-						}
-						LegacyOutput::Error(err) => return Err(err),
+					if let Some(msg) = self.parse_output(&line, &mut status)? {
+						msg_callback(&msg)?
 					}
 				}
 			}
@@ -137,6 +133,32 @@ impl Program {
 			Err(e) => Err(InternalError::new(format!("process error: {}", e)).into()),
 		}
 	}
+
+	/// Parse a line of output from the interpreter, updating the status and selecting the
+	/// output sections of solutions
+	fn parse_output<'a>(&self, line: &'a str, status: &mut Status) -> Result<Option<Message<'a>>> {
+		match serde_json::Deserializer::from_str(line)
+			.deserialize_map(SerdeMessageVisitor(&self.output_types))
+			.map_err(|e| Error::from_serde_json(e, &Arc::new(line.to_owned()).into()))?
+		{
+			LegacyOutput::Status(s) => {
+				*status = s;
+				Ok(None)
+			}
+			LegacyOutput::Msg(mut msg) => {
+				if let Message::Solution(_, sections) = &mut msg {
+					if let Some(sections) = sections {
+						sections.retain(|(name, _)| self.output_sections.includes(name));
+					}
+					if *status == Status::Unknown {
+						*status = Status::Satisfied
+					}
+				}
+				Ok(Some(msg))
+			}
+			LegacyOutput::Error(err) => Err(err),
+		}
+	}
 }
 
 struct LegacyValue<'a> {
@@ -280,6 +302,14 @@ struct DummyValue<'a>(&'a Type);
 impl<'a> Display for DummyValue<'a> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		let ty = self.0;
+		// Optional fields are given in their type erased form
+		let field = |ty: &Type, g: &mut dyn FnMut(&dyn Display) -> std::fmt::Result| {
+			if ty.is_opt() {
+				g(&format_args!("(false, {})", DummyValue(ty)))
+			} else {
+				g(&DummyValue(ty))
+			}
+		};
 		match ty {
 			Type::Boolean(_) => write!(f, "true"),
 			Type::Integer(_) => write!(f, "0"),
@@ -297,7 +327,7 @@ impl<'a> Display for DummyValue<'a> {
 				write!(
 					f,
 					"({}{})",
-					tys.iter().map(DummyValue).format(","),
+					tys.iter().format_with(",", |ty, g| field(ty, g)),
 					if tys.len() == 1 { "," } else { "" }
 				)
 			}
@@ -305,7 +335,7 @@ impl<'a> Display for DummyValue<'a> {
 				write!(
 					f,
 					"({}{})",
-					tys.iter().map(|(_, ty)| DummyValue(ty)).format(","),
+					tys.iter().format_with(",", |(_, ty), g| field(ty, g)),
 					if tys.len() == 1 { "," } else { "" }
 				)
 			}
@@ -396,10 +426,7 @@ impl<'de, 'a> Visitor<'de> for SerdeMessageVisitor<'a> {
 					if solution.is_some() {
 						return Err(SerdeError::duplicate_field("output"));
 					}
-					match map.next_value_seed(SerdeWrappedName {
-						name: "json",
-						seed: SerdeOutputVisitor(type_map),
-					})? {
+					match map.next_value_seed(SerdeSolutionVisitor(type_map))? {
 						Ok(sol) => solution = Some(sol),
 						Err(e) => return Ok(LegacyOutput::Error(e)),
 					}
@@ -453,7 +480,7 @@ impl<'de, 'a> Visitor<'de> for SerdeMessageVisitor<'a> {
 		match msg_type {
 			Some("solution") => match solution {
 				None => Err(SerdeError::missing_field("output")),
-				Some(x) => Ok(LegacyOutput::Msg(Message::Solution(x))),
+				Some((x, sections)) => Ok(LegacyOutput::Msg(Message::Solution(x, sections))),
 			},
 			Some("statistics") => match statistics {
 				None => Err(SerdeError::missing_field("statistics")),
@@ -527,13 +554,14 @@ impl<'a, 'de> DeserializeSeed<'de> for SerdeOutputVisitor<'a> {
 	}
 }
 
-struct SerdeWrappedName<X: Clone> {
-	name: &'static str,
-	seed: X,
-}
+/// Visitor for the output of a solution, which contains the values of the output variables in
+/// the `json` section, and the text of the output sections of the model.
+struct SerdeSolutionVisitor<'a>(pub &'a FxHashMap<Arc<str>, Type>);
+
+type SolutionOutput<'de> = (FxHashMap<&'de str, Value>, Option<Vec<(String, String)>>);
 
-impl<'de, X: DeserializeSeed<'de> + Clone> DeserializeSeed<'de> for SerdeWrappedName<X> {
-	type Value = X::Value;
+impl<'a, 'de> DeserializeSeed<'de> for SerdeSolutionVisitor<'a> {
+	type Value = Result<SolutionOutput<'de>, Error>;
 
 	fn deserialize<D: serde::Deserializer<'de>>(
 		self,
@@ -543,26 +571,41 @@ impl<'de, X: DeserializeSeed<'de> + Clone> DeserializeSeed<'de> for SerdeWrapped
 	}
 }
 
-impl<'de, X: DeserializeSeed<'de> + Clone> Visitor<'de> for SerdeWrappedName<X> {
-	type Value = X::Value;
+impl<'de, 'a> Visitor<'de> for SerdeSolutionVisitor<'a> {
+	type Value = Result<SolutionOutput<'de>, Error>;
 
 	fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-		write!(formatter, "map with {} identifier", self.name)
+		write!(formatter, "map with json identifier")
 	}
 
 	fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
-		let mut ret = None;
+		let mut values = None;
+		let mut sections = Vec::new();
 
 		while let Some(k) = map.next_key::<&str>()? {
-			if k == self.name {
-				ret = Some(map.next_value_seed(self.seed.clone())?);
-			} else {
-				map.next_value::<IgnoredAny>()?; // Ignore unknown
+			match k {
+				"json" => match map.next_value_seed(SerdeOutputVisitor(self.0))? {
+					Ok(v) => values = Some(v),
+					Err(e) => return Ok(Err(e)),
+				},
+				// Concatenation of all sections
+				"raw" => {
+					map.next_value::<IgnoredAny>()?;
+				}
+				_ => {
+					// Sections with names ending in `_json` are given as JSON values
+					let text = match map.next_value::<serde_json::Value>()? {
+						serde_json::Value::String(s) => s,
+						v => format!("{}\n", v),
+					};
+					sections.push((k.to_owned(), text));
+				}
 			}
 		}
-		match ret {
-			None => Err(SerdeError::missing_field(self.name)),
-			Some(x) => Ok(x),
+		match values {
+			None => Err(SerdeError::missing_field("json")),
+			// Models without output sections are output using the values of the variables
+			Some(x) => Ok(Ok((x, (!sections.is_empty()).then_some(sections)))),
 		}
 	}
 }
@@ -657,10 +700,11 @@ impl Value {
 						}
 					})
 					.collect();
-				let members = if matches!(
-					**element,
-					Type::Enum(_, _) | Type::Tuple(_, _) | Type::Record(_, _) | Type::Set(_, _)
-				) {
+				let members = if element.is_opt()
+					|| matches!(
+						**element,
+						Type::Enum(_, _) | Type::Tuple(_, _) | Type::Record(_, _) | Type::Set(_, _)
+					) {
 					x.members
 						.iter()
 						.cloned()
@@ -697,3 +741,153 @@ impl Value {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use expect_test::expect;
+	use shackle_compiler::file::SourceFile;
+
+	use super::LegacyValue;
+	use crate::{
+		data::dzn::{collect_dzn_value, parse_dzn},
+		value::{Array, Index},
+		Model, OptType, OutputSections, Program, Solver, Status, Type, Value,
+	};
+
+	#[test]
+	fn test_legacy_opt_tuple() {
+		let ty = Type::Tuple(
+			OptType::Opt,
+			Arc::new([
+				Type::Integer(OptType::NonOpt),
+				Type::Boolean(OptType::NonOpt),
+			]),
+		);
+		let val = Value::Tuple(vec![Value::Integer(1), Value::Boolean(true)]);
+		expect!("(false, (0,true))").assert_eq(
+			&LegacyValue {
+				val: &Value::Absent,
+				ty: &ty,
+			}
+			.to_string(),
+		);
+		expect!("(true, (1,true))").assert_eq(&LegacyValue { val: &val, ty: &ty }.to_string());
+
+		// Solutions are received in their type erased form
+		let erased = ty.type_erase();
+		expect!("tuple(bool, tuple(int, bool))").assert_eq(&erased.to_string());
+		assert_eq!(
+			Value::Tuple(vec![Value::Boolean(true), val.clone()]).reverse_type_erase(&ty),
+			val
+		);
+		assert_eq!(
+			Value::Tuple(vec![
+				Value::Boolean(false),
+				Value::Tuple(vec![Value::Integer(0), Value::Boolean(true)])
+			])
+			.reverse_type_erase(&ty),
+			Value::Absent
+		);
+	}
+
+	#[test]
+	fn test_legacy_nested_opt_tuple_round_trip() {
+		let ty = Type::Tuple(
+			OptType::Opt,
+			Arc::new([Type::Integer(OptType::NonOpt), Type::Boolean(OptType::Opt)]),
+		);
+		let erased = ty.type_erase();
+		expect!("tuple(bool, tuple(int, tuple(bool, bool)))").assert_eq(&erased.to_string());
+		for val in [
+			Value::Absent,
+			Value::Tuple(vec![Value::Integer(2), Value::Absent]),
+			Value::Tuple(vec![Value::Integer(3), Value::Boolean(false)]),
+		] {
+			// The legacy value is written in type erased form, so it parses as the erased type
+			let src = SourceFile::from(Arc::new(format!(
+				"x = {};",
+				LegacyValue { val: &val, ty: &ty }
+			)));
+			let assignments = parse_dzn(&src).expect("unexpected syntax error");
+			let parsed = collect_dzn_value(&src, &assignments[0].definition(), &erased)
+				.expect("unexpected type error")
+				.resolve_value(&erased)
+				.expect("unexpected resolve error");
+			assert_eq!(parsed.reverse_type_erase(&ty), val);
+		}
+	}
+
+	#[test]
+	fn test_legacy_opt_record_array() {
+		let ty = Type::Array {
+			opt: OptType::NonOpt,
+			dim: Box::new([Type::Integer(OptType::NonOpt)]),
+			element: Box::new(Type::Record(
+				OptType::Opt,
+				Arc::new([("a".into(), Type::Integer(OptType::NonOpt))]),
+			)),
+		};
+		let erased = Value::Array(Array::new(
+			vec![Index::Integer(1..=2)],
+			vec![
+				Value::Tuple(vec![
+					Value::Boolean(true),
+					Value::Tuple(vec![Value::Integer(3)]),
+				]),
+				Value::Tuple(vec![
+					Value::Boolean(false),
+					Value::Tuple(vec![Value::Integer(0)]),
+				]),
+			],
+		));
+		let val = erased.reverse_type_erase(&ty);
+		expect!("[(a: 3), <>]").assert_eq(&val.to_string());
+		expect!("[1: (true, (3,)),(false, (0,))]")
+			.assert_eq(&LegacyValue { val: &val, ty: &ty }.to_string());
+	}
+
+	#[test]
+	fn test_legacy_output_sections() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let program = |only: Option<&[&str]>, not: &[&str]| {
+			Model::from_string("var 1..3: x;".to_owned())
+				.compile(&slv)
+				.unwrap()
+				.with_output_sections(OutputSections {
+					only: only.map(|s| s.iter().map(|s| s.to_string()).collect()),
+					not: not.iter().map(|s| s.to_string()).collect(),
+				})
+		};
+		let output = |prg: &Program, line: &str| {
+			let mut status = Status::Unknown;
+			let msg = prg.parse_output(line, &mut status).unwrap().unwrap();
+			assert_eq!(status, Status::Satisfied);
+			msg.to_string()
+		};
+		let line = r#"{"type": "solution", "output": {"default": "x = 2\n", "report_json": {"x": 2}, "raw": "x = 2\n{\"x\": 2}\n", "json": {"x": 2}}, "sections": ["default", "report_json", "raw", "json"]}"#;
+		expect![[r#"
+    x = 2
+    {"x":2}
+    ----------
+"#]]
+		.assert_eq(&output(&program(None, &[]), line));
+		expect![[r#"
+    {"x":2}
+    ----------
+"#]]
+		.assert_eq(&output(&program(None, &["default"]), line));
+		expect![[r#"
+    ----------
+"#]]
+		.assert_eq(&output(&program(Some(&["missing"]), &[]), line));
+
+		let line = r#"{"type": "solution", "output": {"raw": "", "json": {"x": 2}}, "sections": ["raw", "json"]}"#;
+		expect![[r#"
+    x = 2;
+    ----------
+"#]]
+		.assert_eq(&output(&program(Some(&["missing"]), &[]), line));
+	}
+}
@@ -4,6 +4,7 @@
 #![warn(unused_crate_dependencies, unused_extern_crates)]
 #![warn(variant_size_differences)]
 
+mod cache;
 mod data;
 mod legacy;
 mod value;
@@ -12,7 +13,7 @@ use std::{
 	ffi::OsStr,
 	fmt::Display,
 	io::Write,
-	ops::Deref,
+	ops::{Deref, Range},
 	path::{Path, PathBuf},
 	sync::Arc,
 	time::Duration,
@@ -20,7 +21,8 @@ use std::{
 
 use data::{
 	dzn::{collect_dzn_value, parse_dzn},
-	serde::SerdeFileVisitor,
+	serde::{json_entry_spans, SerdeFileVisitor},
+	ParserVal,
 };
 // Result type for Shackle operations
 pub use error::{Error, Result};
@@ -29,16 +31,44 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use serde::Deserializer;
 // Export OptType enumeration used in [`Type`]
 pub use shackle_compiler::ty::OptType;
+// Export the options used by [`Model::format`]
+pub use shackle_compiler::syntax::format::FormatOptions;
+// Export the project configuration used by [`Model::with_project_config`]
+pub use shackle_compiler::project::{ProjectConfig, PROJECT_FILE_NAME};
+// Export the documentation formats used by [`Model::documentation`]
+pub use shackle_compiler::hir::doc::DocFormat;
+// Export the pipeline options used by [`Model::compile_with_pipeline`]
+pub use shackle_compiler::thir::transform::{PipelineOptions, THIR_PASSES};
+// Export the statistics returned by [`Model::statistics`]
+pub use shackle_compiler::thir::{
+	statistics::{FinalModelSizes, ModelStatistics},
+	transform::PassTiming,
+};
 use shackle_compiler::{
-	db::{CompilerDatabase, Inputs, InternedString, Interner},
-	file::{InputFile, SourceFile},
-	hir::db::Hir,
-	syntax::ast::{AstNode, Identifier},
-	thir::{self, db::Thir, pretty_print::PrettyPrinter, Declaration},
+	db::{CompilerDatabase, FileReader, Inputs, InternedString, Interner},
+	file::{FileRef, InputFile, SourceFile},
+	hir::{db::Hir, doc::generate_documentation},
+	syntax::{
+		ast::{AstNode, Identifier},
+		db::SourceParser,
+		format::format,
+	},
+	thir::{
+		self,
+		db::Thir,
+		pretty_print::PrettyPrinter,
+		transform::{
+			partial_evaluation::{partially_evaluate, ParValue},
+			pipeline_passes, run_pipeline, InsertionPoint, Pass,
+		},
+		Callable, Declaration, DomainData, ExpressionData, FunctionName,
+	},
 	ty::{Ty, TyData},
+	utils::closest_matches,
 };
-use value::EnumInner;
+use value::{CtorArg, CtorBound, EnumInner, Index, Set};
 pub use value::{Enum, Value};
+pub use warning::Warning;
 
 /// Shackle errors
 pub mod error {
@@ -50,9 +80,30 @@ pub mod warning {
 	pub use shackle_compiler::diagnostics::warning::*;
 }
 
+/// Machine-readable reports of errors and warnings
+pub mod report {
+	pub use shackle_compiler::diagnostics::report::*;
+}
+
+/// User-defined THIR transforms, added using [`Model::with_transform`]
+pub mod transform {
+	pub use shackle_compiler::{
+		constants::{IdentifierRegistry, TypeRegistry},
+		db::Interner,
+		hir::db::Hir,
+		thir::{
+			self,
+			db::Thir,
+			transform::{DynTransformFn, InsertionPoint, Pass, TransformFn},
+		},
+	};
+}
+
 /// Structure used to build a shackle model
 pub struct Model {
 	db: CompilerDatabase,
+	transforms: Vec<(InsertionPoint, Pass)>,
+	cache: Option<PathBuf>,
 }
 
 impl Model {
@@ -60,36 +111,206 @@ impl Model {
 	pub fn from_file(path: PathBuf) -> Model {
 		let mut db = CompilerDatabase::default();
 		db.set_input_files(Arc::new(vec![InputFile::Path(path)]));
-		Model { db }
+		Model {
+			db,
+			transforms: Vec::new(),
+			cache: None,
+		}
 	}
 
 	/// Create a Model from the given string
 	pub fn from_string(m: String) -> Model {
 		let mut db = CompilerDatabase::default();
 		db.set_input_files(Arc::new(vec![InputFile::ModelString(m)]));
-		Model { db }
+		Model {
+			db,
+			transforms: Vec::new(),
+			cache: None,
+		}
+	}
+
+	/// Use the search directories and library settings of the given project, with the globals
+	/// library of the given solver unless the project gives a globals directory
+	///
+	/// The default [`ProjectConfig`] can be used to only select the globals library of the solver.
+	pub fn with_project_config(mut self, config: &ProjectConfig, slv: &Solver) -> Self {
+		config.apply(&mut self.db, Some(&slv.ident));
+		self
+	}
+
+	/// Run an additional THIR transform at the given point in the pipeline when compiling
+	///
+	/// Transforms are inserted in the order this method is called.
+	pub fn with_transform(mut self, point: InsertionPoint, pass: Pass) -> Self {
+		self.transforms.push((point, pass));
+		self
+	}
+
+	/// Store compiled programs in the given directory, and reuse them when compiling the same
+	/// model again
+	///
+	/// Cached programs are not used when the THIR pipeline is inspected or extended, and are
+	/// invalidated when the model or any file it includes changes.
+	pub fn with_cache(mut self, directory: PathBuf) -> Self {
+		self.cache = Some(directory);
+		self
+	}
+
+	/// Load the compiled program for this model from the cache, giving back the model if there
+	/// is no up to date entry (or no cache has been set using [`Model::with_cache`])
+	///
+	/// Cached programs were checked for errors when they were compiled, so the model is not
+	/// checked again.
+	pub fn load_cached(mut self, slv: &Solver) -> std::result::Result<Program, Box<Model>> {
+		let Some(directory) = self.cache.as_ref().filter(|_| self.transforms.is_empty()) else {
+			return Err(Box::new(self));
+		};
+		match cache::load(directory, self.db, slv) {
+			Ok(program) => Ok(program),
+			Err(db) => {
+				self.db = db;
+				Err(Box::new(self))
+			}
+		}
 	}
 
 	/// Check whether a model contains any (non-runtime) errors
-	pub fn check(&self, _slv: &Solver, _data: &[PathBuf], _complete: bool) -> Vec<Error> {
-		// TODO: Check data files
-		self.db
-			.run_hir_phase()
-			.map(|_| Vec::new())
-			.unwrap_or_else(|e| e.iter().cloned().collect())
+	pub fn check(&self, _slv: &Solver, data: &[PathBuf], _complete: bool) -> Vec<Error> {
+		if let Err(e) = self.db.run_hir_phase() {
+			return e.iter().cloned().collect();
+		}
+		check_data_files(&self.db, data)
+	}
+
+	/// Format the source code of the model
+	///
+	/// Gives an error if the model contains syntax errors.
+	pub fn format(&self, options: &FormatOptions) -> Result<String> {
+		let model = self.db.input_models()[0];
+		let cst = self.db.cst(*model)?;
+		cst.error(|f| SourceFile::new(f.unwrap(), &self.db))?;
+		format(&cst, options).ok_or_else(|| {
+			error::InternalError::new("Formatting did not preserve the meaning of the model").into()
+		})
+	}
+
+	/// Generate the API documentation for the model and the library files it includes
+	///
+	/// Only included files in the same directory as the model (or its subdirectories) are
+	/// documented. Gives an error if the files contain syntax errors.
+	pub fn documentation(&self, format: DocFormat) -> Result<String> {
+		let models = self.db.resolve_includes()?;
+		if let Some(e) = self.db.syntax_errors().first() {
+			return Err(e.clone());
+		}
+		let canonicalize = |p: PathBuf| p.canonicalize().unwrap_or(p);
+		let inputs = self.db.input_models();
+		let root = inputs[0]
+			.path(&self.db)
+			.and_then(|p| p.parent().map(|p| canonicalize(p.to_owned())));
+		let models = models
+			.iter()
+			.filter(|m| match (&root, m.path(&self.db)) {
+				(Some(root), Some(path)) => canonicalize(path).starts_with(root),
+				(None, path) => path.is_none(),
+				_ => false,
+			})
+			.copied()
+			.sorted_by_key(|m| !inputs.contains(m))
+			.collect::<Vec<_>>();
+		Ok(generate_documentation(&self.db, &models, format))
+	}
+
+	/// Get the warnings produced when checking the model
+	///
+	/// Warnings about unbounded variables are only included if the model has no errors.
+	pub fn warnings(&self) -> Vec<Warning> {
+		let mut warnings = self.db.all_warnings().iter().cloned().collect::<Vec<_>>();
+		if self.db.run_hir_phase().is_ok() {
+			warnings.extend(self.db.unbounded_variable_warnings().iter().cloned());
+		}
+		warnings
 	}
 
 	/// Compile current model into a Program that can be used by the Shackle interpreter
+	///
+	/// If a cache directory has been set using [`Model::with_cache`], a cached program is used
+	/// when available, and newly compiled programs are stored in the cache.
 	pub fn compile(self, slv: &Solver) -> Result<Program> {
+		self.compile_with_pipeline(slv, &PipelineOptions::default(), |_, _| ())
+	}
+
+	/// Compile current model into a Program, running the THIR pipeline with the given
+	/// inspection options.
+	///
+	/// The `dump` function is called with the name of the pass and the pretty printed
+	/// model after each pass listed in [`PipelineOptions::dump_after`].
+	pub fn compile_with_pipeline(
+		self,
+		slv: &Solver,
+		options: &PipelineOptions,
+		dump: impl FnMut(&str, &str),
+	) -> Result<Program> {
+		let model = if options.is_default() {
+			match self.load_cached(slv) {
+				Ok(program) => return Ok(program),
+				Err(model) => *model,
+			}
+		} else {
+			self
+		};
+		let errors = model.check(slv, &[], false);
+		if !errors.is_empty() {
+			return Err(Error::try_from(errors).unwrap());
+		}
+		model.compile_program(slv, options, dump, None)
+	}
+
+	/// Compile the model with the given data files, and collect statistics about the
+	/// resulting program and the time taken by each THIR pass
+	///
+	/// The model and data are checked for errors, so this does not need to be preceded by a
+	/// call to [`Model::check`].
+	pub fn statistics(self, slv: &Solver, data: &[PathBuf]) -> Result<ModelStatistics> {
 		let errors = self.check(slv, &[], false);
 		if !errors.is_empty() {
 			return Err(Error::try_from(errors).unwrap());
 		}
+		// The THIR passes consume the lowered model, so keep a copy to count its declarations
+		let model = self.db.model_thir().get().as_ref().clone();
+		let mut timings = Vec::new();
+		let mut program = self.compile_program(
+			slv,
+			&PipelineOptions::default(),
+			|_, _| (),
+			Some(&mut timings),
+		)?;
+		program.add_data_files(data.iter().map(|f| f.deref()))?;
+		Ok(ModelStatistics::new(
+			&program.db,
+			&model,
+			&program.code,
+			timings,
+		))
+	}
+
+	/// Compile the model, adding the time taken by each THIR pass to `timings` if given
+	///
+	/// The model must not contain any errors.
+	fn compile_program(
+		self,
+		slv: &Solver,
+		options: &PipelineOptions,
+		mut dump: impl FnMut(&str, &str),
+		timings: Option<&mut Vec<PassTiming>>,
+	) -> Result<Program> {
+		let default_pipeline =
+			options.is_default() && self.transforms.is_empty() && timings.is_none();
 		let ModelIoInterface {
 			input,
 			output,
 			enums,
-		} = ModelIoInterface::new(&self.db);
+		} = ModelIoInterface::new(&self.db)?;
 		let legacy_enums = enums
 			.iter()
 			.filter_map(|(_, e)| {
@@ -101,9 +322,25 @@ impl Model {
 			})
 			.collect();
 
-		let prg_model = self.db.final_thir()?;
+		let prg_model = if default_pipeline {
+			self.db.final_thir()?
+		} else {
+			let passes = pipeline_passes(&self.transforms)?;
+			let model = self.db.model_thir().take();
+			let mut discarded = Vec::new();
+			let result = run_pipeline(
+				&self.db,
+				model,
+				&passes,
+				options,
+				|name, m| dump(name, &PrettyPrinter::new(&self.db, m).pretty_print()),
+				timings.unwrap_or(&mut discarded),
+			)?;
+			Arc::new(result)
+		};
 
-		Ok(Program {
+		let cache = self.cache.filter(|_| default_pipeline);
+		let program = Program {
 			db: self.db,
 			slv: slv.clone(),
 			code: prg_model,
@@ -114,7 +351,44 @@ impl Model {
 			output_types: output,
 			enable_stats: false,
 			time_limit: None,
+			output_sections: OutputSections::default(),
+		};
+		if let Some(directory) = cache {
+			cache::store(&directory, &program.db, slv, &program);
+		}
+		Ok(program)
+	}
+}
+
+/// Check whether the given data files are valid for the model in the given database.
+///
+/// The model must not contain any errors.
+pub fn check_data_files(db: &dyn Thir, data: &[PathBuf]) -> Vec<Error> {
+	if data.is_empty() {
+		return Vec::new();
+	}
+	let ModelIoInterface { input, enums, .. } = match ModelIoInterface::new(db) {
+		Ok(interface) => interface,
+		Err(e) => return vec![e],
+	};
+	// Read the data files through the database so that the contents of files
+	// open in an editor are used
+	let reader: &dyn FileReader = db.upcast();
+	let files = match data
+		.iter()
+		.map(|f| {
+			let contents = reader.file_contents(FileRef::new(f, reader))?;
+			Ok(SourceFile::with_path(f.clone(), contents))
 		})
+		.collect::<Result<Vec<_>, Error>>()
+	{
+		Ok(files) => files,
+		Err(e) => return vec![e],
+	};
+	let mut values = FxHashMap::default();
+	match load_data_files(files, &input, &enums, &mut values) {
+		Ok(()) => Vec::new(),
+		Err(e) => vec![e],
 	}
 }
 
@@ -154,6 +428,26 @@ pub struct Program {
 	// run() options
 	enable_stats: bool,
 	time_limit: Option<Duration>,
+	output_sections: OutputSections,
+}
+
+/// Selection of the output sections included in solution messages
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputSections {
+	/// The sections to include (or all sections if `None`)
+	pub only: Option<Vec<String>>,
+	/// The sections to exclude
+	pub not: Vec<String>,
+}
+
+impl OutputSections {
+	/// Whether the section with the given name is included
+	pub fn includes(&self, section: &str) -> bool {
+		self.only
+			.iter()
+			.all(|only| only.iter().any(|s| s == section))
+			&& !self.not.iter().any(|s| s == section)
+	}
 }
 
 /// Status of running and solving a Program
@@ -259,11 +553,8 @@ impl Type {
 				)),
 			),
 			TyData::Tuple(opt, li) => {
-				let tmp = if opt == OptType::NonOpt {
-					value
-				} else {
-					todo!()
-				};
+				// Optional and non-optional versions share their member types
+				let tmp = value.with_opt(db, OptType::NonOpt);
 				let Type::Tuple(_, li) = (if let Some(x) = type_map.get(&tmp) {
 					x
 				} else {
@@ -277,7 +568,10 @@ impl Type {
 							*ty,
 						))
 					}
-					type_map.insert(tmp, Type::Tuple(opt, v.into_boxed_slice().into()));
+					type_map.insert(
+						tmp,
+						Type::Tuple(OptType::NonOpt, v.into_boxed_slice().into()),
+					);
 					&type_map[&tmp]
 				}) else {
 					unreachable!()
@@ -285,11 +579,8 @@ impl Type {
 				Type::Tuple(opt, li.clone())
 			}
 			TyData::Record(opt, li) => {
-				let tmp = if opt == OptType::NonOpt {
-					value
-				} else {
-					todo!()
-				};
+				// Optional and non-optional versions share their member types
+				let tmp = value.with_opt(db, OptType::NonOpt);
 				let Type::Record(_, li) = (if let Some(x) = type_map.get(&tmp) {
 					x
 				} else {
@@ -300,7 +591,10 @@ impl Type {
 							Type::from_compiler(db, str_interner, type_map, enum_map, *ty),
 						))
 					}
-					type_map.insert(tmp, Type::Record(opt, v.into_boxed_slice().into()));
+					type_map.insert(
+						tmp,
+						Type::Record(OptType::NonOpt, v.into_boxed_slice().into()),
+					);
 					&type_map[&tmp]
 				}) else {
 					unreachable!()
@@ -328,6 +622,23 @@ impl Type {
 				| Type::Record(OptType::Opt, _)
 		)
 	}
+
+	/// Returns an enumerated type used in this type which has not been initialised
+	fn uninitialised_enum(&self) -> Option<&Arc<Enum>> {
+		match self {
+			Type::Enum(_, e) => {
+				(!matches!(*e.state.lock().unwrap(), EnumInner::Constructors(_))).then_some(e)
+			}
+			Type::Array { dim, element, .. } => dim
+				.iter()
+				.chain([element.as_ref()])
+				.find_map(|ty| ty.uninitialised_enum()),
+			Type::Set(_, ty) => ty.uninitialised_enum(),
+			Type::Tuple(_, fields) => fields.iter().find_map(|ty| ty.uninitialised_enum()),
+			Type::Record(_, fields) => fields.iter().find_map(|(_, ty)| ty.uninitialised_enum()),
+			_ => None,
+		}
+	}
 }
 
 impl Display for Type {
@@ -366,8 +677,10 @@ impl Display for Type {
 /// Intermediate messages emitted by shackle in processing and solving a program
 #[derive(Debug)]
 pub enum Message<'a> {
-	/// (Intermediate) solution emitted in the process
-	Solution(FxHashMap<&'a str, Value>),
+	/// (Intermediate) solution emitted in the process, containing the values of the output
+	/// variables and the text of each selected output section in order (or `None` if the model
+	/// has no output sections)
+	Solution(FxHashMap<&'a str, Value>, Option<Vec<(String, String)>>),
 	/// Statistical information of the shackle or solving process
 	Statistic(Vec<(&'a str, serde_json::Value)>),
 	/// Trace messages emitted during the shackle process
@@ -379,12 +692,18 @@ pub enum Message<'a> {
 impl<'a> Display for Message<'a> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
-			Message::Solution(sol) => {
+			Message::Solution(sol, None) => {
 				for (name, val) in sol {
 					writeln!(f, "{} = {};", name, val)?;
 				}
 				writeln!(f, "----------")
 			}
+			Message::Solution(_, Some(sections)) => {
+				for (_, text) in sections {
+					write!(f, "{}", text)?;
+				}
+				writeln!(f, "----------")
+			}
 			Message::Statistic(map) => {
 				for (name, val) in map {
 					writeln!(f, "%%%mzn-stat: {}={}", name, val)?;
@@ -403,6 +722,11 @@ impl Program {
 		self.enable_stats = stats;
 		self
 	}
+	/// Set which output sections are included in the solution messages sent by the run method
+	pub fn with_output_sections(mut self, sections: OutputSections) -> Self {
+		self.output_sections = sections;
+		self
+	}
 	/// Add the maximum duration that the run method is allowed to take before it will be canceled
 	pub fn with_time_limit(mut self, dur: Duration) -> Self {
 		self.time_limit = Some(dur);
@@ -415,114 +739,350 @@ impl Program {
 	}
 
 	/// Add and parse data to be used by the program.
+	///
+	/// Once the data is loaded, par expressions which only depend on known values are
+	/// evaluated, and any assertions which fail are reported.
 	pub fn add_data_files<'a>(
 		&mut self,
 		files: impl Iterator<Item = &'a Path>,
 	) -> Result<(), Error> {
-		// First parse all files:
-		// - most values will be simple values that can be directly assigned
-		// - some values will be values of enumerated types, possible part of tuples, records, or indices.
-		// - files can also contain the constructors for enumerated types.
-		let mut data = Vec::new();
-		let mut names = FxHashSet::default();
-		for f in files {
-			let src = SourceFile::try_from(f)?;
-			match f.extension().and_then(OsStr::to_str) {
-				Some("dzn") => {
-					// Parse the DZN file
-					let assignments = parse_dzn(&src)?;
-					data.reserve(assignments.len());
-					names.reserve(assignments.len());
-					// Match the parser
-					for asg in assignments {
-						let ident = asg.assignee().cast::<Identifier>().unwrap();
-						if let Some((k, ty)) = self.input_types.get_key_value::<str>(&ident.name())
-						{
-							let val = collect_dzn_value(&src, &asg.definition(), ty)?;
-							data.push((k, ty, val));
-							// Identifier already seen
-							if names.contains(k) || self.input_data.contains_key(k) {
-								return Err(error::IdentifierAlreadyDefined {
-									src,
-									span: asg.cst_node().as_ref().byte_range().into(),
-									identifier: k.to_string(),
-								}
-								.into());
-							}
-							names.insert(k);
-						} else if let Some((k, e)) =
-							self.enum_types.get_key_value::<str>(&ident.name())
-						{
-							let mut inner = e.state.lock().unwrap();
-							if matches!(*inner, EnumInner::NoDefinition) {
-								(*inner).collect_definition(&src, &asg.definition())?
-							} else {
-								return Err(error::IdentifierAlreadyDefined {
-									src,
-									span: asg.cst_node().as_ref().byte_range().into(),
-									identifier: k.to_string(),
-								}
-								.into());
-							}
-						} else {
-							// Unknown identifier
-							return Err(error::UndefinedIdentifier {
+		let files = files
+			.map(SourceFile::try_from)
+			.collect::<Result<Vec<_>, _>>()?;
+		load_data_files(
+			files,
+			&self.input_types,
+			&self.enum_types,
+			&mut self.input_data,
+		)?;
+		self.evaluate_par()
+	}
+
+	/// Replace the par expressions of the program which can be evaluated using the input data
+	/// with their values.
+	fn evaluate_par(&mut self) -> Result<(), Error> {
+		let data = self
+			.input_data
+			.iter()
+			.filter_map(|(name, value)| {
+				let value = par_value(value, self.input_types.get(name)?)?;
+				Some((thir::Identifier::new(name.as_ref(), &self.db), value))
+			})
+			.collect();
+		self.code = Arc::new(partially_evaluate(&self.db, &self.code, &data)?);
+		Ok(())
+	}
+}
+
+/// Convert a data value into a value usable for compile-time evaluation.
+///
+/// Returns `None` for values whose representation differs in the compiled program
+/// (i.e. optional, enum and record values).
+fn par_value(value: &Value, ty: &Type) -> Option<ParValue> {
+	Some(match (value, ty) {
+		(Value::Boolean(b), Type::Boolean(OptType::NonOpt)) => ParValue::Boolean(*b),
+		(Value::Integer(i), Type::Integer(OptType::NonOpt)) => ParValue::Integer(*i),
+		(Value::Float(f), Type::Float(OptType::NonOpt)) => ParValue::Float(*f),
+		(Value::String(s), Type::String(OptType::NonOpt)) => ParValue::String(s.as_ref().into()),
+		(Value::Set(Set::Int(rs)), Type::Set(OptType::NonOpt, _)) => {
+			ParValue::integer_set(rs.iter().cloned())
+		}
+		(
+			Value::Array(arr),
+			Type::Array {
+				opt: OptType::NonOpt,
+				element,
+				..
+			},
+		) => ParValue::Array(
+			arr.indices
+				.iter()
+				.map(|i| match i {
+					Index::Integer(r) => Some(r.clone()),
+					Index::Enum(_) => None,
+				})
+				.collect::<Option<_>>()?,
+			arr.members
+				.iter()
+				.map(|v| par_value(v, element))
+				.collect::<Option<_>>()?,
+		),
+		(Value::Tuple(vs), Type::Tuple(OptType::NonOpt, tys)) if vs.len() == tys.len() => {
+			ParValue::Tuple(
+				vs.iter()
+					.zip(tys.iter())
+					.map(|(v, t)| par_value(v, t))
+					.collect::<Option<_>>()?,
+			)
+		}
+		_ => return None,
+	})
+}
+
+/// Parse the given data files and add the values to `input_data`.
+fn load_data_files(
+	files: Vec<SourceFile>,
+	input_types: &FxHashMap<Arc<str>, Type>,
+	enum_types: &FxHashMap<Arc<str>, Arc<Enum>>,
+	input_data: &mut FxHashMap<Arc<str>, Value>,
+) -> Result<(), Error> {
+	// First parse all files:
+	// - most values will be simple values that can be directly assigned
+	// - some values will be values of enumerated types, possible part of tuples, records, or indices.
+	// - files can also contain the constructors for enumerated types.
+	let mut data = Vec::new();
+	let mut names = FxHashSet::default();
+	let mut enum_defs = FxHashMap::default();
+	for src in files {
+		match src.path().and_then(Path::extension).and_then(OsStr::to_str) {
+			Some("dzn") => {
+				// Parse the DZN file
+				let assignments = parse_dzn(&src)?;
+				data.reserve(assignments.len());
+				names.reserve(assignments.len());
+				// Match the parser
+				for asg in assignments {
+					let ident = asg.assignee().cast::<Identifier>().unwrap();
+					if let Some((k, ty)) = input_types.get_key_value::<str>(&ident.name()) {
+						let val = collect_dzn_value(&src, &asg.definition(), ty)?;
+						data.push((k, ty, val));
+						// Identifier already seen
+						if names.contains(k) || input_data.contains_key(k) {
+							return Err(error::IdentifierAlreadyDefined {
 								src,
-								span: ident.cst_node().as_ref().byte_range().into(),
-								identifier: ident.name().to_string(),
+								span: asg.cst_node().as_ref().byte_range().into(),
+								identifier: k.to_string(),
 							}
 							.into());
 						}
-					}
-				}
-				Some("json") => {
-					let assignments = serde_json::Deserializer::from_str(src.contents())
-						.deserialize_map(SerdeFileVisitor {
-							input_types: &self.input_types,
-							enum_types: &self.enum_types,
-						})
-						.map_err(|err| Error::from_serde_json(err, &src))?;
-
-					data.reserve(assignments.len());
-					names.reserve(assignments.len());
-					for asg in assignments {
-						// Identifier already seen
-						if names.contains(asg.0) || self.input_data.contains_key(asg.0) {
+						names.insert(k);
+					} else if let Some((k, e)) = enum_types.get_key_value::<str>(&ident.name()) {
+						let mut inner = e.state.lock().unwrap();
+						if matches!(*inner, EnumInner::NoDefinition) {
+							(*inner).collect_definition(&src, &asg.definition())?;
+							enum_defs.insert(
+								k.clone(),
+								(src.clone(), asg.cst_node().as_ref().byte_range()),
+							);
+						} else {
 							return Err(error::IdentifierAlreadyDefined {
 								src,
-								span: (0, 0).into(), // TODO: actual byte range
-								identifier: asg.0.to_string(),
+								span: asg.cst_node().as_ref().byte_range().into(),
+								identifier: k.to_string(),
 							}
 							.into());
 						}
-						names.insert(asg.0);
-						data.push(asg);
+					} else {
+						// Unknown identifier
+						let span = ident.cst_node().as_ref().byte_range().into();
+						let candidates = input_types
+							.keys()
+							.chain(enum_types.keys())
+							.map(|k| k.as_ref());
+						let suggestions = error::Suggestions::did_you_mean(
+							span,
+							closest_matches(&ident.name(), candidates, 3)
+								.into_iter()
+								.map(|k| k.to_owned()),
+						);
+						return Err(error::UndefinedIdentifier {
+							src,
+							span,
+							identifier: ident.name().to_string(),
+							suggestions,
+						}
+						.into());
 					}
 				}
-				_ => {
-					return Err(error::FileError {
-						file: f.into(),
-						message: format!(
-							"Attempting to read data file using unknown extension \"{}\"",
-							f.display()
-						),
-						other: vec![],
+			}
+			Some("json") => {
+				let undefined: Vec<_> = enum_types
+					.iter()
+					.filter(|(_, e)| *e.state.lock().unwrap() == EnumInner::NoDefinition)
+					.map(|(k, _)| k)
+					.collect();
+				let assignments = serde_json::Deserializer::from_str(src.contents())
+					.deserialize_map(SerdeFileVisitor {
+						input_types,
+						enum_types,
+					})
+					.map_err(|err| Error::from_serde_json(err, &src))?;
+				let spans = json_entry_spans(src.contents());
+				let span_of = |k: &str| {
+					spans
+						.iter()
+						.rev()
+						.find(|(key, _)| key == k)
+						.map(|(_, span)| span.clone())
+						.unwrap_or(0..0)
+				};
+				for k in undefined {
+					if *enum_types[k].state.lock().unwrap() != EnumInner::NoDefinition {
+						enum_defs.insert(k.clone(), (src.clone(), span_of(k)));
 					}
-					.into());
 				}
+
+				data.reserve(assignments.len());
+				names.reserve(assignments.len());
+				for asg in assignments {
+					// Identifier already seen
+					if names.contains(asg.0) || input_data.contains_key(asg.0) {
+						return Err(error::IdentifierAlreadyDefined {
+							src,
+							span: span_of(asg.0).into(),
+							identifier: asg.0.to_string(),
+						}
+						.into());
+					}
+					names.insert(asg.0);
+					data.push(asg);
+				}
+			}
+			_ => {
+				let file = src.path().map(Path::to_owned).unwrap_or_default();
+				return Err(error::FileError {
+					message: format!(
+						"Attempting to read data file using unknown extension \"{}\"",
+						file.display()
+					),
+					file,
+					other: vec![],
+				}
+				.into());
+			}
+		};
+	}
+	// Initialise the enumerated types in order of their dependencies, using the
+	// integer parameters which can be used as bounds of constructor arguments
+	let params = |name: &str| match input_data.get(name) {
+		Some(Value::Integer(i)) => Some(*i),
+		Some(_) => None,
+		None => {
+			data.iter()
+				.find(|(k, _, _)| k.as_ref() == name)
+				.and_then(|(_, _, val)| match val {
+					ParserVal::Integer(i) => Some(*i),
+					_ => None,
+				})
+		}
+	};
+	initialise_enums(enum_types, &enum_defs, &params)?;
+
+	// Values can only be created once the enumerated types they use are initialised
+	for (_, ty, _) in data.iter() {
+		if let Some(e) = ty.uninitialised_enum() {
+			let state = e.state.lock().unwrap();
+			let (kind, name) = match state.parameters().find(|p| params(p).is_none()) {
+				Some(p) => ("parameter", p.to_string()),
+				None => ("enumerated type", e.name().to_string()),
 			};
+			return Err(error::MissingValue {
+				kind: kind.to_owned(),
+				name,
+			}
+			.into());
 		}
-		// Topologically sort the constructors to allow us to resolve the dependencies
-		// data.sort_by(|_a, _b| todo!());
+	}
+
+	// Create the final values for the interpreter
+	for (key, ty, val) in data {
+		let _none = input_data.insert(key.clone(), val.resolve_value(ty)?);
+		debug_assert_eq!(_none, None);
+	}
 
-		// Itererate between initializing the enumerated types and creating the final values for the interpreter
-		for (key, ty, val) in data {
-			let _none = self.input_data.insert(key.clone(), val.resolve_value(ty)?);
-			debug_assert_eq!(_none, None);
+	Ok(())
+}
+
+/// Initialise the enumerated types that are awaiting data, once all the
+/// enumerated types used as arguments to their constructors are initialised.
+///
+/// Enumerated types that depend on an enumerated type that has not yet been
+/// defined in data will remain uninitialised. An error is returned if an
+/// enumerated type defined in data depends on an unknown identifier, or
+/// (indirectly) on itself.
+fn initialise_enums(
+	enum_types: &FxHashMap<Arc<str>, Arc<Enum>>,
+	enum_defs: &FxHashMap<Arc<str>, (SourceFile, Range<usize>)>,
+	params: &dyn Fn(&str) -> Option<i64>,
+) -> Result<(), Error> {
+	for (name, (src, span)) in enum_defs.iter().sorted_by_key(|(k, _)| *k) {
+		let undefined = enum_types[name]
+			.state
+			.lock()
+			.unwrap()
+			.dependencies()
+			.find(|dep| !enum_types.contains_key(*dep))
+			.cloned();
+		if let Some(dep) = undefined {
+			return Err(error::UndefinedIdentifier {
+				src: src.clone(),
+				span: span.clone().into(),
+				identifier: dep.to_string(),
+				suggestions: error::Suggestions::did_you_mean(
+					span.clone().into(),
+					closest_matches(&dep, enum_types.keys().map(|k| k.as_ref()), 3)
+						.into_iter()
+						.map(|k| k.to_owned()),
+				),
+			}
+			.into());
 		}
+	}
 
-		Ok(())
+	let mut pending = enum_types
+		.iter()
+		.filter(|(_, e)| matches!(*e.state.lock().unwrap(), EnumInner::AwaitData(_)))
+		.collect::<Vec<_>>();
+	loop {
+		let n = pending.len();
+		pending.retain(|(_, e)| !e.try_initialise(enum_types, params));
+		if pending.len() == n {
+			break;
+		}
 	}
+
+	// The remaining enumerated types are either waiting for the definition of
+	// another enumerated type or parameter, or are part of a cycle
+	let mut waiting: FxHashSet<&Arc<str>> = enum_types
+		.iter()
+		.filter(|(_, e)| {
+			let state = e.state.lock().unwrap();
+			*state == EnumInner::NoDefinition || state.parameters().any(|p| params(p).is_none())
+		})
+		.map(|(k, _)| k)
+		.collect();
+	loop {
+		let n = waiting.len();
+		for (k, e) in pending.iter() {
+			if !waiting.contains(k)
+				&& e.state
+					.lock()
+					.unwrap()
+					.dependencies()
+					.any(|dep| waiting.contains(dep))
+			{
+				waiting.insert(k);
+			}
+		}
+		if waiting.len() == n {
+			break;
+		}
+	}
+	if let Some((name, (src, span))) = pending
+		.iter()
+		.filter(|(k, _)| !waiting.contains(k))
+		.filter_map(|(k, _)| enum_defs.get_key_value(*k))
+		.sorted_by_key(|(k, _)| *k)
+		.next()
+	{
+		return Err(error::CyclicDefinition {
+			variable: name.to_string(),
+			src: src.clone(),
+			span: span.clone().into(),
+		}
+		.into());
+	}
+	Ok(())
 }
 
 /// Get a mapping from input/output identifiers to their computed types or enumerated type declaration
@@ -534,7 +1094,7 @@ struct ModelIoInterface {
 }
 
 impl ModelIoInterface {
-	fn new(db: &dyn Thir) -> Self {
+	fn new(db: &dyn Thir) -> Result<Self, Error> {
 		let sh = db.model_thir();
 		let val = sh.get();
 		let model = val.as_ref();
@@ -553,10 +1113,38 @@ impl ModelIoInterface {
 		let mut enums = FxHashMap::default();
 		for (_, e) in model.enumerations() {
 			let name = resolve_name(e.enum_type().name(db.upcast()));
-			if let Some(_ctor) = e.definition() {
-				log::warn!("TODO: enumerated type {} is defined in the model and member can currently not be constructed in data", name);
-				// TODO: determine dependencies or directly initialize the enumerated type
-				enums.insert(name.clone(), Arc::new(Enum::model_defined(name, [])));
+			if let Some(ctors) = e.definition() {
+				let pending = ctors
+					.iter()
+					.map(|ctor| {
+						let ctor_name = ctor
+							.name
+							.map(|ident| resolve_name(ident.0))
+							.unwrap_or_else(|| Arc::from("_"));
+						let args = ctor
+							.parameters
+							.iter()
+							.flatten()
+							.map(|p| {
+								constructor_argument(db, model, &model[*p], &mut resolve_name)
+									.ok_or_else(|| {
+										let origin = match &**model[*p].domain() {
+											DomainData::Bounded(dom) => dom.origin(),
+											_ => model[*p].origin(),
+										};
+										let (src, span) = origin.source_span(db);
+										Error::from(error::UnsupportedConstructorArgument {
+											src,
+											name: name.to_string(),
+											span,
+										})
+									})
+							})
+							.collect::<Result<Box<[_]>, _>>()?;
+						Ok((ctor_name, args))
+					})
+					.collect::<Result<Vec<_>, Error>>()?;
+				enums.insert(name.clone(), Arc::new(Enum::model_defined(name, pending)));
 			} else {
 				enums.insert(name.clone(), Arc::new(Enum::from_data(name)));
 			}
@@ -599,20 +1187,496 @@ impl ModelIoInterface {
 			}
 			if should_output == Some(true)
 				|| (should_output.is_none()
-					&& decl.top_level() && !decl.domain().ty().known_par(db.upcast())
+					&& decl.top_level()
+					&& !decl.domain().ty().known_par(db.upcast())
 					&& decl.definition().is_none())
 			{
 				insert_decl(&mut output, decl)
 			}
 		}
 
-		ModelIoInterface {
+		Ok(ModelIoInterface {
 			input,
 			output,
 			enums,
+		})
+	}
+}
+
+/// Determine the values that a parameter of a constructor of a model-defined
+/// enumerated type can take, or `None` if these cannot be determined
+fn constructor_argument(
+	db: &dyn Thir,
+	model: &thir::Model,
+	param: &Declaration,
+	resolve_name: &mut impl FnMut(InternedString) -> Arc<str>,
+) -> Option<CtorArg> {
+	if let Some(e) = param.domain().ty().enum_ty(db.upcast()) {
+		return Some(CtorArg::Enum(resolve_name(e.name(db.upcast()))));
+	}
+	let DomainData::Bounded(dom) = &**param.domain() else {
+		return None;
+	};
+	match &***dom {
+		ExpressionData::Call(c) => {
+			let Callable::Function(f) = &c.function else {
+				return None;
+			};
+			if model[*f].name() != FunctionName::new(db.identifier_registry().dot_dot) {
+				return None;
+			}
+			let [lb, ub] = c.arguments.as_slice() else {
+				return None;
+			};
+			match (
+				constructor_bound(model, lb, resolve_name)?,
+				constructor_bound(model, ub, resolve_name)?,
+			) {
+				(CtorBound::Integer(lb), CtorBound::Integer(ub)) => {
+					Some(CtorArg::Index(Index::Integer(lb..=ub)))
+				}
+				(lb, ub) => Some(CtorArg::Range(lb, ub)),
+			}
+		}
+		_ => None,
+	}
+}
+
+/// Determine the bound of a constructor argument range, which is either an integer
+/// literal or an integer parameter which is given in data
+fn constructor_bound(
+	model: &thir::Model,
+	bound: &thir::Expression,
+	resolve_name: &mut impl FnMut(InternedString) -> Arc<str>,
+) -> Option<CtorBound> {
+	match &**bound {
+		ExpressionData::IntegerLiteral(i) => Some(CtorBound::Integer(i.0)),
+		ExpressionData::Identifier(thir::ResolvedIdentifier::Declaration(d)) => {
+			let decl = &model[*d];
+			match decl.definition().map(|def| &**def) {
+				Some(ExpressionData::IntegerLiteral(i)) => Some(CtorBound::Integer(i.0)),
+				None if decl.top_level() => {
+					Some(CtorBound::Parameter(resolve_name(decl.name()?.0)))
+				}
+				_ => None,
+			}
 		}
+		_ => None,
 	}
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+	use std::{
+		io::Write,
+		panic::RefUnwindSafe,
+		path::{Path, PathBuf},
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		},
+	};
+
+	use expect_test::{expect, Expect};
+	use itertools::Itertools;
+	use shackle_compiler::{
+		db::{CompilerDatabase, Inputs},
+		diagnostics::FileError,
+		file::{DefaultFileHandler, FileHandler, InputFile},
+	};
+	use tempfile::{Builder, NamedTempFile};
+
+	use crate::{
+		check_data_files,
+		transform::{
+			thir::{source::Origin, BooleanLiteral, Constraint, Expression, Item, Model as Thir},
+			InsertionPoint, Pass, Thir as ThirDb,
+		},
+		Error, Model, PipelineOptions, Program, Result, Solver,
+	};
+
+	fn data_file(ext: &str, contents: &str) -> NamedTempFile {
+		let mut file = Builder::new().suffix(ext).tempfile().unwrap();
+		file.write_all(contents.as_bytes()).unwrap();
+		file
+	}
+
+	fn add_data(model: &str, data: &[(&str, &str)]) -> (Program, Result<(), Error>) {
+		let slv = Solver::lookup("gecode").unwrap();
+		let mut prg = Model::from_string(model.to_string())
+			.compile(&slv)
+			.expect("unexpected compilation error");
+		let files = data
+			.iter()
+			.map(|(ext, contents)| data_file(ext, contents))
+			.collect::<Vec<_>>();
+		let res = prg.add_data_files(files.iter().map(|f| f.path()));
+		(prg, res)
+	}
+
+	fn check_data(model: &str, data: &[(&str, &str)], expected: Expect) {
+		let (prg, res) = add_data(model, data);
+		res.expect("unexpected data error");
+		let enums = prg
+			.enum_types
+			.values()
+			.sorted_by_key(|e| e.name().clone())
+			.map(|e| e.to_string());
+		let values = prg
+			.input_data
+			.iter()
+			.sorted_by_key(|(k, _)| *k)
+			.map(|(k, v)| format!("{k} = {v}"));
+		expected.assert_eq(&enums.chain(values).join("\n"));
+	}
+
+	#[test]
+	fn test_model_defined_enum() {
+		check_data(
+			r#"
+			enum Y;
+			enum X = A(Y) ++ {Z} ++ B(1..2, Y);
+			X: x;
+			X: y;
+			X: z;
+			"#,
+			&[(".dzn", "Y = {P, Q}; x = A(Q); y = Z; z = B(2, P);")],
+			expect![[r#"
+    X = A(Y) ++ {Z} ++ B(1..2,Y)
+    Y = {P} ++ {Q}
+    x = A(Q)
+    y = Z
+    z = B(2,P)"#]],
+		);
+	}
+
+	#[test]
+	fn test_enum_dependencies_across_files() {
+		check_data(
+			r#"
+			enum Y;
+			enum W;
+			enum X = A(W);
+			X: x;
+			"#,
+			&[
+				(".dzn", "W = B(Y) ++ {C}; x = A(B(Q));"),
+				(".json", r#"{"Y": ["P", "Q"]}"#),
+			],
+			expect![[r#"
+    W = B(Y) ++ {C}
+    X = A(W)
+    Y = {P} ++ {Q}
+    x = A(B(Q))"#]],
+		);
+		check_data(
+			r#"
+			enum Y;
+			enum W;
+			W: w;
+			"#,
+			&[
+				(
+					".json",
+					r#"{"W": [{"c": "B", "a": ["Y"]}, {"c": "C", "a": [{"set": [[1, 2]]}]}], "w": {"e": "B", "a": ["Q"]}}"#,
+				),
+				(".dzn", "Y = {P, Q};"),
+			],
+			expect![[r#"
+    W = B(Y) ++ C(1..2)
+    Y = {P} ++ {Q}
+    w = B(Q)"#]],
+		);
+	}
+
+	#[test]
+	fn test_cyclic_enum_definition() {
+		let (_, res) = add_data(
+			"enum V; enum W;",
+			&[(".dzn", "V = A(W);"), (".dzn", "W = B(V);")],
+		);
+		expect!["Cyclic definition of V"].assert_eq(&res.unwrap_err().to_string());
+		let (_, res) = add_data("enum V;", &[(".dzn", "V = A(U);")]);
+		expect!["Undefined identifier"].assert_eq(&res.unwrap_err().to_string());
+	}
+
+	#[test]
+	fn test_enum_constructor_bounds_from_data() {
+		check_data(
+			r#"
+			int: n;
+			enum X = A(1..n) ++ {B};
+			X: x;
+			"#,
+			&[(".dzn", "n = 3; x = A(2);")],
+			expect![[r#"
+    X = A(1..3) ++ {B}
+    n = 3
+    x = A(2)"#]],
+		);
+		let (_, res) = add_data(
+			r#"
+			int: n;
+			enum X = A(1..n);
+			X: x;
+			"#,
+			&[(".dzn", "x = A(2);")],
+		);
+		expect!["No value given for parameter 'n'"].assert_eq(&res.unwrap_err().to_string());
+		let res = Model::from_string("set of int: S; enum X = A(S);".to_owned())
+			.compile(&Solver::lookup("gecode").unwrap());
+		let Err(Error::UnsupportedConstructorArgument(e)) = res else {
+			panic!("expected unsupported constructor argument error");
+		};
+		expect!["S"].assert_eq(&e.src.contents()[e.span.offset()..e.span.offset() + e.span.len()]);
+	}
+
+	/// File handler giving the contents of a file which has not been saved
+	#[derive(Clone)]
+	struct UnsavedFile(PathBuf, Arc<String>);
+
+	impl FileHandler for UnsavedFile {
+		fn read_file(&self, path: &Path) -> std::result::Result<Arc<String>, FileError> {
+			if path == self.0 {
+				return Ok(self.1.clone());
+			}
+			DefaultFileHandler.read_file(path)
+		}
+
+		fn snapshot(&self) -> Box<dyn FileHandler + RefUnwindSafe> {
+			Box::new(self.clone())
+		}
+	}
+
+	#[test]
+	fn test_check_data_file_contents() {
+		let path = PathBuf::from("unsaved.dzn");
+		let mut db = CompilerDatabase::with_file_handler(Box::new(UnsavedFile(
+			path.clone(),
+			Arc::new("n = 1; m = 2;".to_owned()),
+		)));
+		db.set_input_files(Arc::new(vec![InputFile::ModelString("int: n;".to_owned())]));
+		expect!["Undefined identifier"]
+			.assert_eq(&check_data_files(&db, &[path]).iter().join("\n"));
+	}
+
+	#[test]
+	fn test_json_duplicate_span() {
+		let (_, res) = add_data(
+			"int: x; int: y;",
+			&[(".json", r#"{"x": 1}"#), (".json", r#"{"y": 2, "x": 3}"#)],
+		);
+		let Err(Error::IdentifierAlreadyDefined(e)) = res else {
+			panic!("expected identifier already defined error");
+		};
+		expect![[r#""x": 3"#]]
+			.assert_eq(&e.src.contents()[e.span.offset()..e.span.offset() + e.span.len()]);
+	}
+
+	#[test]
+	fn test_failed_assertion() {
+		let model = r#"
+			int: n;
+			constraint assert(n > 0, "n must be positive");
+			"#;
+		let (_, res) = add_data(model, &[(".dzn", "n = 0;")]);
+		let err = res.unwrap_err();
+		expect!["Assertion failed"].assert_eq(&err.to_string());
+		assert!(matches!(err, Error::AssertionFailure(e) if e.msg == "n must be positive"));
+		let (_, res) = add_data(model, &[(".dzn", "n = 1;")]);
+		res.expect("unexpected data error");
+	}
+
+	#[test]
+	fn test_pipeline_inspection() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let options = PipelineOptions {
+			dump_after: vec!["erase_enum".to_owned(), "decapture_model".to_owned()],
+			stop_after: Some("erase_enum".to_owned()),
+			sanity_check: false,
+		};
+		let mut dumped = Vec::new();
+		Model::from_string("var 1..3: x; constraint x > 1;".to_owned())
+			.compile_with_pipeline(&slv, &options, |name, code| {
+				assert!(code.contains(": x :: ('output')"));
+				dumped.push(name.to_owned());
+			})
+			.expect("unexpected compilation error");
+		expect![[r#"["erase_enum"]"#]].assert_eq(&format!("{:?}", dumped));
+	}
+
+	fn forbid_all(db: &dyn ThirDb, mut model: Thir) -> Result<Thir> {
+		let origin = Origin::Introduced("forbid_all");
+		let e = Expression::new(db, &model, origin, BooleanLiteral(false));
+		model.add_constraint(Item::new(Constraint::new(true, e), origin));
+		Ok(model)
+	}
+
+	#[test]
+	fn test_compile_linear() {
+		// The flattener introduces calls to builtins and redefinitions, and emits library
+		// annotations, so these must not be removed from the program
+		let slv = Solver::lookup("gecode").unwrap();
+		let program = Model::from_string(
+			"var 1..3: x;\nvar 1..3: y;\nconstraint 2 * x + 3 * y <= 10;\nsolve maximize max([x, y]);"
+				.to_owned(),
+		)
+		.compile(&slv)
+		.unwrap();
+		let mut out = Vec::new();
+		program.write(&mut out).unwrap();
+		let code = String::from_utf8(out).unwrap();
+		for name in [
+			"int_lin_le(",
+			"array_int_maximum(",
+			"bool_clause_reif(",
+			"annotation output_var",
+		] {
+			assert!(code.contains(name), "`{}` was removed", name);
+		}
+	}
+
+	#[test]
+	fn test_statistics() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let model = "var opt 1..3: x;\nint: n;\nconstraint assert(n > 0, \"n must be positive\");";
+		let data = data_file(".dzn", "n = 2;");
+		let stats = Model::from_string(model.to_owned())
+			.statistics(&slv, &[data.path().to_owned()])
+			.unwrap();
+		assert_eq!(stats.declarations.get("var opt int"), Some(&1));
+		assert!(stats.final_model.items > 0);
+		let data = data_file(".dzn", "n = 0;");
+		let err = Model::from_string(model.to_owned())
+			.statistics(&slv, &[data.path().to_owned()])
+			.unwrap_err();
+		assert!(matches!(err, Error::AssertionFailure(e) if e.msg == "n must be positive"));
+	}
+
+	#[test]
+	fn test_user_transform() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let runs = Arc::new(AtomicUsize::new(0));
+		let compile = |point, name: &str| {
+			let options = PipelineOptions {
+				dump_after: vec![name.to_owned()],
+				..Default::default()
+			};
+			let counter = runs.clone();
+			let pass = Pass::new(name, move |db: &dyn ThirDb, model| {
+				counter.fetch_add(1, Ordering::Relaxed);
+				forbid_all(db, model)
+			});
+			let mut dumped = false;
+			Model::from_string("var 1..3: x;".to_owned())
+				.with_transform(point, pass)
+				.compile_with_pipeline(&slv, &options, |_, code| {
+					dumped = code.contains("constraint false");
+				})
+				.map(|prg| {
+					assert!(dumped);
+					let mut out = Vec::new();
+					prg.write(&mut out).unwrap();
+					String::from_utf8(out).unwrap().contains("constraint false")
+				})
+		};
+		assert!(compile(InsertionPoint::After("erase_enum".to_owned()), "forbid_all").unwrap());
+		assert_eq!(runs.load(Ordering::Relaxed), 1);
+		let err = compile(InsertionPoint::Before("unknown".to_owned()), "forbid_all").unwrap_err();
+		expect!["Unknown THIR pass `unknown`"].assert_eq(&err.to_string());
+		let err = compile(InsertionPoint::After("erase_enum".to_owned()), "erase_opt").unwrap_err();
+		expect!["THIR pass `erase_opt` is already in the pipeline"].assert_eq(&err.to_string());
+		assert_eq!(runs.load(Ordering::Relaxed), 1);
+	}
+
+	#[test]
+	fn test_cache() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let dir = tempfile::tempdir().unwrap();
+		let cache = dir.path().join("cache");
+		let model = dir.path().join("model.mzn");
+		let include = dir.path().join("bounds.mzn");
+		std::fs::write(
+			&model,
+			"include \"bounds.mzn\";\nenum E = A(1..n);\nvar E: x;\nint: m;\nconstraint assert(m > 0, \"m must be positive\");",
+		)
+		.unwrap();
+		std::fs::write(&include, "int: n = 3;").unwrap();
+		let code = |prg: &Program| {
+			let mut out = Vec::new();
+			prg.write(&mut out).unwrap();
+			String::from_utf8(out).unwrap()
+		};
+		let load = || Model::from_file(model.clone()).with_cache(cache.clone());
+		let load_cached = || crate::cache::load(&cache, load().db, &slv).ok();
+
+		assert!(load_cached().is_none());
+		let compiled = load().compile(&slv).unwrap();
+		let mut cached = load_cached().expect("program was not cached");
+		assert_eq!(code(&compiled), code(&cached));
+		assert_eq!(compiled.output_types, cached.output_types);
+		let data = data_file(".dzn", "m = 0;");
+		let err = cached
+			.add_data_files([data.path()].into_iter())
+			.unwrap_err();
+		assert!(matches!(err, Error::AssertionFailure(e) if e.msg == "m must be positive"));
+
+		std::fs::write(&include, "int: n = 4;").unwrap();
+		assert!(load_cached().is_none());
+		let recompiled = load().compile(&slv).unwrap();
+		assert_ne!(code(&compiled), code(&recompiled));
+		assert!(load_cached().is_some());
+	}
+
+	/// File handler counting the number of files read
+	#[derive(Clone)]
+	struct CountReads(Arc<AtomicUsize>);
+
+	impl FileHandler for CountReads {
+		fn read_file(&self, path: &Path) -> std::result::Result<Arc<String>, FileError> {
+			self.0.fetch_add(1, Ordering::Relaxed);
+			DefaultFileHandler.read_file(path)
+		}
+
+		fn snapshot(&self) -> Box<dyn FileHandler + RefUnwindSafe> {
+			Box::new(self.clone())
+		}
+	}
+
+	#[test]
+	fn test_cache_hit() {
+		let slv = Solver::lookup("gecode").unwrap();
+		let dir = tempfile::tempdir().unwrap();
+		let cache = dir.path().join("cache");
+		let path = dir.path().join("model.mzn");
+		std::fs::write(
+			&path,
+			"int: n;\narray [1..n] of var 1..n: x;\nconstraint forall (i in 1..n - 1) (x[i] < x[i + 1]);",
+		)
+		.unwrap();
+		let data = data_file(".dzn", "n = 3;");
+		let compile = |reads: &Arc<AtomicUsize>| {
+			let mut db = CompilerDatabase::with_file_handler(Box::new(CountReads(reads.clone())));
+			db.set_input_files(Arc::new(vec![InputFile::Path(path.clone())]));
+			let model = Model {
+				db,
+				transforms: Vec::new(),
+				cache: Some(cache.clone()),
+			};
+			let mut program = model.compile(&slv).unwrap();
+			program.add_data_files([data.path()].into_iter()).unwrap();
+			let mut out = Vec::new();
+			program.write(&mut out).unwrap();
+			String::from_utf8(out).unwrap()
+		};
+
+		let reads = Arc::new(AtomicUsize::new(0));
+		let compiled = compile(&reads);
+		assert!(reads.load(Ordering::Relaxed) > 0);
+		// The final THIR is loaded from the cache, so no files are parsed to compute it again
+		let reads = Arc::new(AtomicUsize::new(0));
+		let cached = compile(&reads);
+		assert_eq!(reads.load(Ordering::Relaxed), 0);
+		assert_eq!(compiled, cached);
+	}
+}
@@ -10,6 +10,7 @@ use std::{
 };
 
 use itertools::Itertools;
+use rustc_hash::FxHashMap;
 
 /// Value types that can be part of a Solution
 #[derive(Debug, Clone, PartialEq)]
@@ -372,10 +373,13 @@ impl Enum {
 		}
 	}
 
-	pub(crate) fn model_defined<I: IntoIterator<Item = Arc<str>>>(name: Arc<str>, deps: I) -> Self {
+	pub(crate) fn model_defined<I: IntoIterator<Item = PendingConstructor>>(
+		name: Arc<str>,
+		ctors: I,
+	) -> Self {
 		Self {
 			name,
-			state: EnumInner::AwaitData(Vec::from_iter(deps).into_boxed_slice()).into(),
+			state: EnumInner::AwaitData(Vec::from_iter(ctors).into_boxed_slice()).into(),
 		}
 	}
 
@@ -416,12 +420,72 @@ impl Enum {
 		}
 		None
 	}
+
+	/// Try to compute the constructors of an enumerated type that is awaiting
+	/// data, using the given enumerated types and integer parameters for
+	/// constructor arguments.
+	///
+	/// Returns `false` if any of the enumerated types or parameters used as an
+	/// argument has not (yet) been initialised.
+	pub(crate) fn try_initialise(
+		&self,
+		enums: &FxHashMap<Arc<str>, Arc<Enum>>,
+		params: &dyn Fn(&str) -> Option<i64>,
+	) -> bool {
+		let mut state = self.state.lock().unwrap();
+		let EnumInner::AwaitData(pending) = state.deref() else {
+			return matches!(state.deref(), EnumInner::Constructors(_));
+		};
+		let mut ctors = Vec::with_capacity(pending.len());
+		for (name, args) in pending.iter() {
+			let mut idx = Vec::with_capacity(args.len());
+			for arg in args.iter() {
+				idx.push(match arg {
+					CtorArg::Index(i) => i.clone(),
+					CtorArg::Range(lb, ub) => {
+						let bound = |b: &CtorBound| match b {
+							CtorBound::Integer(i) => Some(*i),
+							CtorBound::Parameter(p) => params(p),
+						};
+						let (Some(lb), Some(ub)) = (bound(lb), bound(ub)) else {
+							return false;
+						};
+						Index::Integer(lb..=ub)
+					}
+					CtorArg::Enum(dep) => {
+						let Some(dep) = enums.get(dep) else {
+							return false;
+						};
+						if std::ptr::eq(dep.as_ref(), self) {
+							return false;
+						}
+						let dep_state = dep.state.lock().unwrap();
+						let EnumInner::Constructors(dep_ctors) = dep_state.deref() else {
+							return false;
+						};
+						let len = dep_ctors.iter().map(|(_, _, len)| len).sum();
+						drop(dep_state);
+						Index::Enum(EnumRangeInclusive::from_enum_and_positions(
+							dep.clone(),
+							1,
+							len,
+						))
+					}
+				});
+			}
+			let len = idx.iter().map(Index::len).product();
+			ctors.push((name.clone(), idx.into_boxed_slice(), len));
+		}
+		*state = EnumInner::Constructors(ctors.into_boxed_slice());
+		true
+	}
 }
 
 impl PartialEq for Enum {
 	fn eq(&self, other: &Self) -> bool {
-		self.name == other.name
-			&& self.state.lock().unwrap().deref() == other.state.lock().unwrap().deref()
+		std::ptr::eq(self, other)
+			|| self.name == other.name
+				&& self.state.lock().unwrap().deref() == other.state.lock().unwrap().deref()
 	}
 }
 impl Eq for Enum {}
@@ -438,7 +502,15 @@ impl Display for Enum {
 					if ctor.1.is_empty() {
 						f(&format_args!("{{{}}}", ctor.0)) // TODO: repeated constructors with no arguments should be grouped together
 					} else {
-						f(&format_args!("{}({})", ctor.0, ctor.1.iter().format(",")))
+						f(&format_args!(
+							"{}({})",
+							ctor.0,
+							ctor.1.iter().format_with(",", |idx, f| match idx {
+								// Arguments that take all members of an enumerated type
+								Index::Enum(r) if r.is_whole_enum() => f(r.ty.name()),
+								_ => f(idx),
+							})
+						))
 					}
 				})
 			)
@@ -465,12 +537,103 @@ impl<'a> CtorLock<'a> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum EnumInner {
+	/// Enumerated type that is to be defined in data
 	NoDefinition,
-	AwaitData(Box<[Arc<str>]>),
+	/// Constructors are known, but their arguments might depend on other
+	/// enumerated types that are defined in data
+	AwaitData(Box<[PendingConstructor]>),
+	/// Fully initialised enumerated type
 	Constructors(Box<[Constructor]>),
 }
 
+impl EnumInner {
+	/// Create the definition of an enumerated type from a list of constructors,
+	/// which is only awaiting data if any of the constructors takes an
+	/// enumerated type as an argument
+	pub(crate) fn from_pending(pending: Vec<PendingConstructor>) -> Self {
+		if pending
+			.iter()
+			.all(|(_, args)| args.iter().all(|arg| matches!(arg, CtorArg::Index(_))))
+		{
+			EnumInner::Constructors(
+				pending
+					.into_iter()
+					.map(|(name, args)| {
+						let idx: Box<[Index]> = args
+							.into_vec()
+							.into_iter()
+							.map(|arg| match arg {
+								CtorArg::Index(i) => i,
+								_ => unreachable!(),
+							})
+							.collect();
+						let len = idx.iter().map(Index::len).product();
+						(name, idx, len)
+					})
+					.collect(),
+			)
+		} else {
+			EnumInner::AwaitData(pending.into_boxed_slice())
+		}
+	}
+
+	/// Returns the names of the enumerated types used as constructor arguments
+	pub(crate) fn dependencies(&self) -> impl Iterator<Item = &Arc<str>> {
+		let pending: &[PendingConstructor] = match self {
+			EnumInner::AwaitData(pending) => pending,
+			_ => &[],
+		};
+		pending.iter().flat_map(|(_, args)| {
+			args.iter().filter_map(|arg| match arg {
+				CtorArg::Enum(dep) => Some(dep),
+				_ => None,
+			})
+		})
+	}
+
+	/// Returns the names of the integer parameters used as bounds of constructor arguments
+	pub(crate) fn parameters(&self) -> impl Iterator<Item = &Arc<str>> {
+		let pending: &[PendingConstructor] = match self {
+			EnumInner::AwaitData(pending) => pending,
+			_ => &[],
+		};
+		pending.iter().flat_map(|(_, args)| {
+			args.iter()
+				.flat_map(|arg| match arg {
+					CtorArg::Range(lb, ub) => [Some(lb), Some(ub)],
+					_ => [None, None],
+				})
+				.flatten()
+				.filter_map(|b| match b {
+					CtorBound::Parameter(p) => Some(p),
+					CtorBound::Integer(_) => None,
+				})
+		})
+	}
+}
+
 pub(crate) type Constructor = (Arc<str>, Box<[Index]>, usize);
+pub(crate) type PendingConstructor = (Arc<str>, Box<[CtorArg]>);
+
+/// Argument of a constructor of an enumerated type that is awaiting data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CtorArg {
+	/// Argument takes values from a known index set
+	Index(Index),
+	/// Argument takes all members of the enumerated type with the given name
+	Enum(Arc<str>),
+	/// Argument takes values from an integer range whose bounds are given in data
+	Range(CtorBound, CtorBound),
+}
+
+/// Bound of a constructor argument range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CtorBound {
+	/// Known integer bound
+	Integer(i64),
+	/// Bound given by the integer parameter with the given name
+	Parameter(Arc<str>),
+}
 
 /// Member declaration of an enumerated type
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -604,6 +767,11 @@ impl EnumRangeInclusive {
 		self.ty.clone()
 	}
 
+	/// Returns `true` if the range contains all members of its enumerated type
+	pub(crate) fn is_whole_enum(&self) -> bool {
+		self.start == 1 && self.end == self.ty.len()
+	}
+
 	/// Returns `true` if item is contained in the range.
 	pub fn contains(&self, item: &EnumValue) -> bool {
 		if item.ty != self.ty {
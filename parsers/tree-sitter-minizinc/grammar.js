@@ -535,15 +535,30 @@ module.exports = grammar({
 		record_type_field: ($) =>
 			seq(field("type", $._type), ":", field("name", $._identifier)),
 		operation_type: ($) =>
+			choice(
+				seq(
+					"op",
+					"(",
+					field("return_type", $._type),
+					":",
+					"(",
+					sepBy(",", field("parameter", $._type)),
+					")",
+					")"
+				),
+				seq(
+					"op",
+					"(",
+					sepBy(",", $._operation_type_parameter),
+					")",
+					"->",
+					field("return_type", $._type)
+				)
+			),
+		_operation_type_parameter: ($) =>
 			seq(
-				"op",
-				"(",
-				field("return_type", $._type),
-				":",
-				"(",
-				sepBy(",", field("parameter", $._type)),
-				")",
-				")"
+				field("parameter", $._type),
+				optional(seq(":", field("parameter_name", $._identifier)))
 			),
 		type_base: ($) =>
 			choice(